@@ -0,0 +1,6 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+	println!("cargo:rerun-if-changed=build.rs");
+	#[cfg(feature = "grpc")]
+	tonic_build::compile_protos("proto/light_client.proto")?;
+	Ok(())
+}
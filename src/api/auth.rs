@@ -0,0 +1,527 @@
+//! Authorization and rate limiting of the HTTP API requests.
+//!
+//! Requests are authorized with bearer tokens in the `Authorization` header. Token is either
+//! one of the configured tokens, or HMAC signed token in `{scopes}.{expires_at}.{signature}` format,
+//! where scopes are comma separated, `expires_at` is UNIX timestamp in seconds,
+//! and signature is hex encoded HMAC-SHA256 of `{scopes}.{expires_at}`, using the configured secret.
+//! Scopes of HMAC signed tokens can include `app:{app_id}` entries, which restrict submission
+//! to the listed application IDs. If authorization is not configured, all requests are allowed.
+
+use hmac::{Hmac, Mac};
+use hyper::{Method, StatusCode};
+use sha2::Sha256;
+use std::{
+	collections::{HashMap, HashSet},
+	net::{IpAddr, SocketAddr},
+	str::FromStr,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+use subtle::ConstantTimeEq;
+use warp::{path::FullPath, reject::Reject, Filter, Rejection, Reply};
+
+use crate::{
+	types::{ApiScope, RuntimeConfig},
+	utils::unix_timestamp,
+};
+
+pub type Scopes = HashSet<ApiScope>;
+
+/// Scopes and application IDs granted to the credential
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Permissions {
+	pub scopes: Scopes,
+	/// Application IDs allowed for the submission, or any if not set
+	pub app_ids: Option<HashSet<u32>>,
+}
+
+impl Permissions {
+	fn all() -> Self {
+		Permissions {
+			scopes: [ApiScope::Read, ApiScope::Subscribe, ApiScope::Submit]
+				.into_iter()
+				.collect(),
+			app_ids: None,
+		}
+	}
+
+	pub fn has_scope(&self, scope: ApiScope) -> bool {
+		self.scopes.contains(&scope)
+	}
+
+	pub fn allows_app_id(&self, app_id: u32) -> bool {
+		self.app_ids
+			.as_ref()
+			.map_or(true, |app_ids| app_ids.contains(&app_id))
+	}
+
+	/// Checks if the credential is not restricted to the specific application IDs
+	pub fn allows_any_app_id(&self) -> bool {
+		self.app_ids.is_none()
+	}
+}
+
+/// Interval of the removal of the buckets of the idle IP addresses
+const RATE_LIMIT_EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl Reject for Unauthorized {}
+
+#[derive(Debug)]
+struct Forbidden;
+
+impl Reject for Forbidden {}
+
+#[derive(Debug)]
+struct TooManyRequests;
+
+impl Reject for TooManyRequests {}
+
+#[derive(Clone, Default)]
+pub struct Auth {
+	tokens: Arc<Vec<(String, Permissions)>>,
+	hmac_secret: Option<Arc<Vec<u8>>>,
+}
+
+impl From<&RuntimeConfig> for Auth {
+	fn from(cfg: &RuntimeConfig) -> Self {
+		let tokens = cfg
+			.http_api_tokens
+			.iter()
+			.map(|token| {
+				let permissions = Permissions {
+					scopes: token.scopes.iter().copied().collect(),
+					app_ids: token
+						.app_ids
+						.as_ref()
+						.map(|app_ids| app_ids.iter().copied().collect()),
+				};
+				(token.token.0.clone(), permissions)
+			})
+			.collect();
+
+		Auth {
+			tokens: Arc::new(tokens),
+			hmac_secret: cfg
+				.http_api_hmac_secret
+				.as_ref()
+				.map(|secret| Arc::new(secret.0.as_bytes().to_vec())),
+		}
+	}
+}
+
+impl Auth {
+	pub fn is_enabled(&self) -> bool {
+		!self.tokens.is_empty() || self.hmac_secret.is_some()
+	}
+
+	/// Returns permissions granted to the bearer token, or `None` if token is not valid
+	fn permissions(&self, token: &str, now: u64) -> Option<Permissions> {
+		// All tokens are compared in constant time, so the timing doesn't reveal the matching prefix
+		let mut granted = None;
+		for (configured, permissions) in self.tokens.iter() {
+			if bool::from(configured.as_bytes().ct_eq(token.as_bytes())) {
+				granted = Some(permissions);
+			}
+		}
+		if let Some(permissions) = granted {
+			return Some(permissions.clone());
+		}
+		let secret = self.hmac_secret.as_ref()?;
+		verify_hmac_token(secret, token, now)
+	}
+
+	/// Returns permissions granted to the `Bearer` authorization, or `None` if it is not valid.
+	/// All permissions are granted if authorization is disabled.
+	pub fn bearer_permissions(&self, authorization: Option<&str>) -> Option<Permissions> {
+		if !self.is_enabled() {
+			return Some(Permissions::all());
+		}
+		authorization
+			.and_then(|authorization| authorization.strip_prefix("Bearer "))
+			.and_then(|token| self.permissions(token.trim(), unix_timestamp()))
+	}
+}
+
+fn verify_hmac_token(secret: &[u8], token: &str, now: u64) -> Option<Permissions> {
+	let (payload, signature) = token.rsplit_once('.')?;
+	let (scopes, expires_at) = payload.split_once('.')?;
+
+	let signature = hex::decode(signature).ok()?;
+	let mut mac = Hmac::<Sha256>::new_from_slice(secret).ok()?;
+	mac.update(payload.as_bytes());
+	mac.verify_slice(&signature).ok()?;
+
+	if expires_at.parse::<u64>().ok()? <= now {
+		return None;
+	}
+
+	let mut permissions = Permissions::default();
+	for scope in scopes.split(',') {
+		match scope.strip_prefix("app:") {
+			Some(app_id) => {
+				let app_id = app_id.parse::<u32>().ok()?;
+				permissions
+					.app_ids
+					.get_or_insert_with(HashSet::new)
+					.insert(app_id);
+			},
+			None => {
+				permissions.scopes.insert(ApiScope::from_str(scope).ok()?);
+			},
+		}
+	}
+	Some(permissions)
+}
+
+/// Returns scope required for the request, or `None` if request is allowed without authorization
+fn required_scope(method: &Method, path: &str) -> Option<ApiScope> {
+	let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+	match (method, segments.as_slice()) {
+		(_, ["health"]) => None,
+		// JSON-RPC methods are authorized by the method, after the credential is verified
+		(_, ["v2", "rpc", ..]) => None,
+		(&Method::POST, ["v2", "submit"])
+		| (&Method::POST, ["v2", "submit", "estimate"])
+		| (_, ["v2", "submissions", ..])
+		| (&Method::POST, ["v2", "applications"]) => Some(ApiScope::Submit),
+		(_, ["v2", "subscriptions", ..]) | (_, ["v2", "ws", ..]) | (_, ["v2", "events"]) => {
+			Some(ApiScope::Subscribe)
+		},
+		_ => Some(ApiScope::Read),
+	}
+}
+
+/// Returns permissions granted to the request
+fn granted_permissions(auth: &Auth, authorization: Option<&str>) -> Result<Permissions, Rejection> {
+	auth.bearer_permissions(authorization)
+		.ok_or_else(|| warp::reject::custom(Unauthorized))
+}
+
+/// Extracts permissions granted to the request
+pub fn permissions(auth: Auth) -> impl Filter<Extract = (Permissions,), Error = Rejection> + Clone {
+	warp::header::optional::<String>("authorization").and_then(
+		move |authorization: Option<String>| {
+			let result = granted_permissions(&auth, authorization.as_deref());
+			async move { result }
+		},
+	)
+}
+
+/// Rejects requests without the scope required for the requested route
+pub fn authorize(auth: Auth) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+	warp::method()
+		.and(warp::path::full())
+		.and(warp::header::optional::<String>("authorization"))
+		.and_then(
+			move |method: Method, path: FullPath, authorization: Option<String>| {
+				let result = match required_scope(&method, path.as_str()) {
+					None => Ok(()),
+					Some(scope) => granted_permissions(&auth, authorization.as_deref()).and_then(
+						|permissions| {
+							permissions
+								.has_scope(scope)
+								.then_some(())
+								.ok_or_else(|| warp::reject::custom(Forbidden))
+						},
+					),
+				};
+				async move { result }
+			},
+		)
+		.untuple_one()
+}
+
+struct Bucket {
+	tokens: f64,
+	updated_at: Instant,
+}
+
+#[derive(Default)]
+struct Buckets {
+	buckets: HashMap<IpAddr, Bucket>,
+	evicted_at: Option<Instant>,
+}
+
+/// Token bucket rate limiter, with the bucket per IP address
+#[derive(Clone)]
+pub struct RateLimiter {
+	limit: u32,
+	buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+	pub fn new(limit: u32) -> Self {
+		RateLimiter {
+			limit,
+			buckets: Arc::new(Mutex::new(Buckets::default())),
+		}
+	}
+
+	fn refill(&self, bucket: &mut Bucket, now: Instant) {
+		let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+		bucket.tokens = (bucket.tokens + elapsed * self.limit as f64).min(self.limit as f64);
+		bucket.updated_at = now;
+	}
+
+	/// Takes a token from the bucket of the given address, returning `false` if bucket is empty
+	pub fn check(&self, address: IpAddr, now: Instant) -> bool {
+		let Buckets {
+			buckets,
+			evicted_at,
+		} = &mut *self
+			.buckets
+			.lock()
+			.expect("Rate limiter lock can be acquired");
+
+		// Buckets are refilled within a second, so the idle ones are removed once per interval
+		let evicted_at = evicted_at.get_or_insert(now);
+		if now.saturating_duration_since(*evicted_at) >= RATE_LIMIT_EVICTION_INTERVAL {
+			buckets.retain(|_, bucket| {
+				now.saturating_duration_since(bucket.updated_at) < RATE_LIMIT_EVICTION_INTERVAL
+			});
+			*evicted_at = now;
+		}
+
+		let bucket = buckets.entry(address).or_insert(Bucket {
+			tokens: self.limit as f64,
+			updated_at: now,
+		});
+		self.refill(bucket, now);
+
+		if bucket.tokens < 1.0 {
+			return false;
+		}
+		bucket.tokens -= 1.0;
+		true
+	}
+}
+
+/// Remote address of the client, set as a request extension
+/// when the connection is not accepted by the warp server (e.g. TLS connections)
+#[derive(Clone, Copy, Debug)]
+pub struct RemoteAddress(pub SocketAddr);
+
+/// Rejects requests exceeding the rate limit, if the rate limiter is given
+pub fn rate_limit(
+	limiter: Option<RateLimiter>,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+	warp::addr::remote()
+		.and(warp::ext::optional::<RemoteAddress>())
+		.and_then(
+			move |address: Option<SocketAddr>, remote: Option<RemoteAddress>| {
+				let address = address.or(remote.map(|RemoteAddress(address)| address));
+				let is_allowed = match (limiter.as_ref(), address) {
+					(Some(limiter), Some(address)) => limiter.check(address.ip(), Instant::now()),
+					_ => true,
+				};
+				async move {
+					if !is_allowed {
+						return Err(warp::reject::custom(TooManyRequests));
+					}
+					Ok(())
+				}
+			},
+		)
+		.untuple_one()
+}
+
+pub async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, Rejection> {
+	let status = if rejection.find::<Unauthorized>().is_some() {
+		StatusCode::UNAUTHORIZED
+	} else if rejection.find::<Forbidden>().is_some() {
+		StatusCode::FORBIDDEN
+	} else if rejection.find::<TooManyRequests>().is_some() {
+		StatusCode::TOO_MANY_REQUESTS
+	} else {
+		return Err(rejection);
+	};
+
+	let message = status.canonical_reason().unwrap_or_default();
+	Ok(warp::reply::with_status(message, status))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		authorize, handle_rejection, permissions, rate_limit, required_scope, verify_hmac_token,
+		Auth, RateLimiter, RemoteAddress, RATE_LIMIT_EVICTION_INTERVAL,
+	};
+	use crate::types::{ApiScope, ApiSecret, ApiToken, RuntimeConfig};
+	use hmac::{Hmac, Mac};
+	use hyper::{Method, StatusCode};
+	use sha2::Sha256;
+	use std::{
+		net::{IpAddr, Ipv4Addr},
+		time::{Duration, Instant},
+	};
+	use test_case::test_case;
+	use warp::Filter;
+
+	const SECRET: &[u8] = b"secret";
+
+	fn hmac_token(payload: &str) -> String {
+		let mut mac = Hmac::<Sha256>::new_from_slice(SECRET).unwrap();
+		mac.update(payload.as_bytes());
+		let signature = hex::encode(mac.finalize().into_bytes());
+		format!("{payload}.{signature}")
+	}
+
+	fn auth() -> Auth {
+		let cfg = RuntimeConfig {
+			http_api_tokens: vec![ApiToken {
+				token: ApiSecret("reader".to_string()),
+				scopes: vec![ApiScope::Read],
+				app_ids: None,
+			}],
+			..Default::default()
+		};
+		Auth::from(&cfg)
+	}
+
+	#[test]
+	fn hmac_token_valid() {
+		let token = hmac_token("read,submit.100");
+		let permissions = verify_hmac_token(SECRET, &token, 99).unwrap();
+		assert_eq!(
+			permissions.scopes,
+			[ApiScope::Read, ApiScope::Submit].into_iter().collect()
+		);
+		assert!(permissions.allows_any_app_id());
+	}
+
+	#[test]
+	fn hmac_token_app_ids() {
+		let token = hmac_token("submit,app:1,app:2.100");
+		let permissions = verify_hmac_token(SECRET, &token, 99).unwrap();
+		assert!(permissions.has_scope(ApiScope::Submit));
+		assert!(permissions.allows_app_id(1));
+		assert!(permissions.allows_app_id(2));
+		assert!(!permissions.allows_app_id(3));
+		assert!(!permissions.allows_any_app_id());
+	}
+
+	#[test_case(&hmac_token("read.100"), 100 ; "Expired")]
+	#[test_case(&hmac_token("read.100").replace("read", "submit"), 99 ; "Tampered")]
+	#[test_case(&hmac_token("admin.100"), 99 ; "Unknown scope")]
+	#[test_case(&hmac_token("submit,app:one.100"), 99 ; "Invalid app ID")]
+	#[test_case("read.100", 99 ; "Missing signature")]
+	#[test_case("read.100.00", 99 ; "Invalid signature")]
+	fn hmac_token_invalid(token: &str, now: u64) {
+		assert!(verify_hmac_token(SECRET, token, now).is_none());
+	}
+
+	#[test_case(Method::GET, "/health", None)]
+	#[test_case(Method::GET, "/v1/status", Some(ApiScope::Read))]
+	#[test_case(Method::GET, "/v2/blocks/1/data", Some(ApiScope::Read))]
+	#[test_case(Method::POST, "/v2/submit", Some(ApiScope::Submit))]
+	#[test_case(Method::GET, "/v2/submissions/0x01", Some(ApiScope::Submit))]
+	#[test_case(Method::POST, "/v2/applications", Some(ApiScope::Submit))]
+	#[test_case(Method::POST, "/v2/submit/estimate", Some(ApiScope::Submit))]
+	#[test_case(Method::POST, "/v2/subscriptions", Some(ApiScope::Subscribe))]
+	#[test_case(Method::DELETE, "/v2/subscriptions/1", Some(ApiScope::Subscribe))]
+	#[test_case(Method::GET, "/v2/ws/1", Some(ApiScope::Subscribe))]
+	#[test_case(Method::GET, "/v2/events", Some(ApiScope::Subscribe))]
+	#[test_case(Method::POST, "/v2/rpc", None)]
+	#[test_case(Method::GET, "/v2/rpc/ws", None)]
+	fn route_scope(method: Method, path: &str, expected: Option<ApiScope>) {
+		assert_eq!(required_scope(&method, path), expected);
+	}
+
+	#[test_case(None, "/v2/status", StatusCode::UNAUTHORIZED ; "Missing token")]
+	#[test_case(Some("Bearer unknown"), "/v2/status", StatusCode::UNAUTHORIZED ; "Unknown token")]
+	#[test_case(Some("Bearer reader"), "/v2/status", StatusCode::OK ; "Authorized")]
+	#[test_case(Some("Bearer reader"), "/v2/submit", StatusCode::FORBIDDEN ; "Missing scope")]
+	#[test_case(None, "/health", StatusCode::OK ; "Health without token")]
+	#[tokio::test]
+	async fn authorize_filter(authorization: Option<&str>, path: &str, expected: StatusCode) {
+		let route = authorize(auth()).map(warp::reply).recover(handle_rejection);
+
+		let mut request = warp::test::request().method("POST").path(path);
+		if let Some(authorization) = authorization {
+			request = request.header("authorization", authorization);
+		}
+
+		assert_eq!(request.reply(&route).await.status(), expected);
+	}
+
+	#[tokio::test]
+	async fn permissions_auth_disabled() {
+		let permissions = warp::test::request()
+			.filter(&permissions(Auth::default()))
+			.await
+			.unwrap();
+		assert_eq!(permissions.scopes.len(), 3);
+		assert!(permissions.allows_any_app_id());
+	}
+
+	#[test]
+	fn rate_limiter() {
+		let limiter = RateLimiter::new(2);
+		let address = IpAddr::V4(Ipv4Addr::LOCALHOST);
+		let other_address = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+		let now = Instant::now();
+
+		assert!(limiter.check(address, now));
+		assert!(limiter.check(address, now));
+		assert!(!limiter.check(address, now));
+		assert!(limiter.check(other_address, now));
+		assert!(limiter.check(address, now + Duration::from_millis(500)));
+		assert!(!limiter.check(address, now + Duration::from_millis(500)));
+	}
+
+	#[test]
+	fn rate_limiter_evicts_idle_addresses() {
+		let limiter = RateLimiter::new(1);
+		let address = IpAddr::V4(Ipv4Addr::LOCALHOST);
+		let other_address = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+		let now = Instant::now();
+
+		assert!(limiter.check(address, now));
+		assert!(limiter.check(other_address, now + RATE_LIMIT_EVICTION_INTERVAL / 2));
+		assert!(limiter.check(other_address, now + RATE_LIMIT_EVICTION_INTERVAL));
+		let buckets = limiter.buckets.lock().unwrap();
+		assert!(!buckets.buckets.contains_key(&address));
+		assert!(buckets.buckets.contains_key(&other_address));
+	}
+
+	#[tokio::test]
+	async fn rate_limit_filter() {
+		let route = rate_limit(Some(RateLimiter::new(1)))
+			.map(warp::reply)
+			.recover(handle_rejection);
+
+		let request = || {
+			warp::test::request()
+				.path("/v2/status")
+				.remote_addr("127.0.0.1:7000".parse().unwrap())
+		};
+
+		assert_eq!(request().reply(&route).await.status(), StatusCode::OK);
+		assert_eq!(
+			request().reply(&route).await.status(),
+			StatusCode::TOO_MANY_REQUESTS
+		);
+	}
+
+	#[tokio::test]
+	async fn rate_limit_filter_remote_address_extension() {
+		let route = rate_limit(Some(RateLimiter::new(1)))
+			.map(warp::reply)
+			.recover(handle_rejection);
+
+		let request = || {
+			warp::test::request()
+				.path("/v2/status")
+				.extension(RemoteAddress("127.0.0.1:7000".parse().unwrap()))
+		};
+
+		assert_eq!(request().reply(&route).await.status(), StatusCode::OK);
+		assert_eq!(
+			request().reply(&route).await.status(),
+			StatusCode::TOO_MANY_REQUESTS
+		);
+	}
+}
@@ -1,3 +1,5 @@
+pub mod auth;
 pub mod server;
+pub mod tls;
 mod v1;
 pub mod v2;
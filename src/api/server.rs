@@ -10,29 +10,44 @@
 
 use crate::api::v2;
 use crate::{
-	api::v1,
-	network::rpc::{self, Node},
-	types::{RuntimeConfig, State},
+	api::{
+		auth::{self, Auth, RateLimiter},
+		tls::{self, TlsConfig},
+		v1,
+	},
+	data::Database,
+	keystore::Keystore,
+	network::{
+		p2p,
+		rpc::{self, Node},
+	},
+	types::{RuntimeConfig, State, Submission},
 };
-use anyhow::Context;
-use rocksdb::DB;
+use anyhow::{Context, Result};
+use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
 use std::{
 	net::SocketAddr,
 	str::FromStr,
 	sync::{Arc, Mutex},
 };
-use tracing::info;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
 use warp::{Filter, Reply};
 
-pub struct Server {
-	pub db: Arc<DB>,
+pub struct Server<T: Database> {
+	pub db: T,
 	pub cfg: RuntimeConfig,
 	pub state: Arc<Mutex<State>>,
 	pub version: String,
 	pub network_version: String,
 	pub node: Node,
 	pub node_client: rpc::Client,
+	pub p2p_client: p2p::Client,
 	pub ws_clients: v2::types::WsClients,
+	pub events: v2::types::Events,
+	pub submission_sender: broadcast::Sender<Submission>,
+	pub keystore: Keystore,
+	pub pp: Arc<PublicParameters>,
 }
 
 fn health_route() -> impl Filter<Extract = impl Reply, Error = warp::Rejection> + Clone {
@@ -42,16 +57,29 @@ fn health_route() -> impl Filter<Extract = impl Reply, Error = warp::Rejection>
 		.map(|_| warp::reply::with_status("", warp::http::StatusCode::OK))
 }
 
-impl Server {
-	/// Runs HTTP server
-	pub async fn run(self) {
+impl<T: Database> Server<T> {
+	/// Runs HTTP server, until it fails
+	pub async fn run(self) -> Result<()> {
 		let RuntimeConfig {
 			http_server_host: host,
 			http_server_port: port,
 			app_id,
+			http_cors_origins: cors_origins,
+			http_rate_limit: rate_limit,
 			..
 		} = self.cfg.clone();
 
+		let addr = SocketAddr::from_str(format!("{host}:{port}").as_str())
+			.context("Unable to parse host address from config")?;
+
+		let auth = Auth::from(&self.cfg);
+		if !auth.is_enabled() && !addr.ip().is_loopback() {
+			// Submit and application keys creation are allowed to anyone who can reach the address
+			warn!(%addr, "HTTP API authorization is not configured, all requests are allowed");
+		}
+		let tls_config = TlsConfig::new(&self.cfg);
+		let rate_limiter = rate_limit.map(RateLimiter::new);
+
 		let v1_api = v1::routes(self.db.clone(), app_id, self.state.clone());
 		let v2_api = v2::routes(
 			self.version.clone(),
@@ -60,21 +88,42 @@ impl Server {
 			self.state.clone(),
 			self.cfg,
 			self.node_client.clone(),
+			self.p2p_client.clone(),
 			self.ws_clients.clone(),
-			crate::data::RocksDB(self.db.clone()),
+			self.events.clone(),
+			self.submission_sender.clone(),
+			self.keystore,
+			self.pp,
+			self.db.clone(),
 		);
 
 		let cors = warp::cors()
-			.allow_any_origin()
-			.allow_header("content-type")
+			.allow_headers(vec!["content-type", "authorization", "last-event-id"])
 			.allow_methods(vec!["GET", "POST", "DELETE"]);
 
-		let routes = health_route().or(v1_api).or(v2_api).with(cors);
+		let cors = if cors_origins.is_empty() {
+			cors.allow_any_origin()
+		} else {
+			cors.allow_origins(cors_origins.iter().map(String::as_str))
+		};
 
-		let addr = SocketAddr::from_str(format!("{host}:{port}").as_str())
-			.context("Unable to parse host address from config")
-			.unwrap();
-		info!("RPC running on http://{host}:{port}");
-		warp::serve(routes).run(addr).await;
+		let routes = auth::rate_limit(rate_limiter)
+			.and(health_route().or(auth::authorize(auth).and(v1_api.or(v2_api))))
+			.recover(auth::handle_rejection)
+			.with(cors);
+
+		let Some(tls_config) = tls_config else {
+			let (_, server) = warp::serve(routes)
+				.try_bind_ephemeral(addr)
+				.with_context(|| format!("Cannot bind to {addr}"))?;
+			info!("RPC running on http://{host}:{port}");
+			server.await;
+			return Ok(());
+		};
+
+		info!("RPC running on https://{host}:{port}");
+		tls::serve(routes, addr, tls_config)
+			.await
+			.context("HTTPS server stopped")
 	}
 }
@@ -0,0 +1,302 @@
+//! TLS termination for the HTTP server.
+//!
+//! Certificate and key files are checked for changes periodically, and reloaded without restarting the server.
+//! New certificate is used for new connections, while the existing connections are not affected.
+
+use anyhow::{anyhow, Context, Result};
+use hyper::{
+	server::{accept, conn::AddrStream},
+	service::{make_service_fn, service_fn, Service},
+	Body, Request,
+};
+use rustls_pemfile::Item;
+use std::{
+	convert::Infallible,
+	fs::{self, File},
+	io::BufReader,
+	net::SocketAddr,
+	sync::{Arc, RwLock},
+	time::{Duration, SystemTime},
+};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_rustls::{
+	rustls::{
+		server::{AllowAnyAuthenticatedClient, ClientHello, ResolvesServerCert},
+		sign::{self, CertifiedKey},
+		Certificate, PrivateKey, RootCertStore, ServerConfig,
+	},
+	server::TlsStream,
+	TlsAcceptor,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error, info, warn};
+use warp::{Filter, Reply};
+
+use crate::{api::auth::RemoteAddress, types::RuntimeConfig};
+
+/// Time allowed for the client to complete the TLS handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// TLS configuration (see [`RuntimeConfig`] for details)
+#[derive(Clone)]
+pub struct TlsConfig {
+	pub cert_path: String,
+	pub key_path: String,
+	pub client_ca_path: Option<String>,
+	pub reload_interval: Duration,
+}
+
+impl TlsConfig {
+	/// Creates TLS configuration if both certificate and key paths are configured
+	pub fn new(cfg: &RuntimeConfig) -> Option<Self> {
+		Some(TlsConfig {
+			cert_path: cfg.http_server_tls_cert_path.clone()?,
+			key_path: cfg.http_server_tls_key_path.clone()?,
+			client_ca_path: cfg.http_server_tls_client_ca_path.clone(),
+			reload_interval: Duration::from_secs(cfg.http_server_tls_reload_interval.max(1)),
+		})
+	}
+}
+
+fn read_pem(path: &str) -> Result<Vec<Item>> {
+	let file = File::open(path).with_context(|| format!("Cannot open {path}"))?;
+	rustls_pemfile::read_all(&mut BufReader::new(file))
+		.with_context(|| format!("Cannot read {path}"))
+}
+
+fn read_certificates(path: &str) -> Result<Vec<Certificate>> {
+	let certificates = read_pem(path)?
+		.into_iter()
+		.filter_map(|item| match item {
+			Item::X509Certificate(certificate) => Some(Certificate(certificate)),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+
+	if certificates.is_empty() {
+		return Err(anyhow!("No certificates found in {path}"));
+	}
+	Ok(certificates)
+}
+
+fn read_private_key(path: &str) -> Result<PrivateKey> {
+	read_pem(path)?
+		.into_iter()
+		.find_map(|item| match item {
+			Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+			_ => None,
+		})
+		.with_context(|| format!("No private key found in {path}"))
+}
+
+fn load_certified_key(cert_path: &str, key_path: &str) -> Result<CertifiedKey> {
+	let certificates = read_certificates(cert_path)?;
+	let key = read_private_key(key_path)?;
+	let signing_key = sign::any_supported_type(&key).context("Unsupported private key type")?;
+	Ok(CertifiedKey::new(certificates, signing_key))
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+	fs::metadata(path)
+		.and_then(|metadata| metadata.modified())
+		.ok()
+}
+
+/// Resolves the server certificate, which is reloaded once certificate or key file changes
+struct CertificateResolver {
+	cert_path: String,
+	key_path: String,
+	certified_key: RwLock<Arc<CertifiedKey>>,
+}
+
+impl CertificateResolver {
+	fn new(cert_path: String, key_path: String) -> Result<Self> {
+		let certified_key = load_certified_key(&cert_path, &key_path)?;
+		Ok(CertificateResolver {
+			cert_path,
+			key_path,
+			certified_key: RwLock::new(Arc::new(certified_key)),
+		})
+	}
+
+	fn modified(&self) -> (Option<SystemTime>, Option<SystemTime>) {
+		(modified(&self.cert_path), modified(&self.key_path))
+	}
+
+	fn reload(&self) -> Result<()> {
+		let certified_key = load_certified_key(&self.cert_path, &self.key_path)?;
+		*self
+			.certified_key
+			.write()
+			.expect("Certificate lock can be acquired") = Arc::new(certified_key);
+		Ok(())
+	}
+
+	/// Checks certificate and key files for changes and reloads them.
+	/// In case of an error, previous certificate is used until the files are changed again.
+	async fn watch(self: Arc<Self>, interval: Duration) {
+		let mut last_modified = self.modified();
+		loop {
+			tokio::time::sleep(interval).await;
+
+			let modified = self.modified();
+			if modified == last_modified {
+				continue;
+			}
+			last_modified = modified;
+
+			match self.reload() {
+				Ok(()) => info!("TLS certificate reloaded"),
+				Err(error) => error!("Cannot reload TLS certificate: {error:#}"),
+			}
+		}
+	}
+}
+
+impl ResolvesServerCert for CertificateResolver {
+	fn resolve(&self, _: ClientHello) -> Option<Arc<CertifiedKey>> {
+		let certified_key = self
+			.certified_key
+			.read()
+			.expect("Certificate lock can be acquired");
+		Some(certified_key.clone())
+	}
+}
+
+fn server_config(config: &TlsConfig, resolver: Arc<CertificateResolver>) -> Result<ServerConfig> {
+	let builder = ServerConfig::builder().with_safe_defaults();
+
+	let builder = match config.client_ca_path.as_ref() {
+		None => builder.with_no_client_auth(),
+		Some(client_ca_path) => {
+			let mut roots = RootCertStore::empty();
+			for certificate in read_certificates(client_ca_path)? {
+				roots
+					.add(&certificate)
+					.context("Invalid client CA certificate")?;
+			}
+			builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots))
+		},
+	};
+
+	let mut server_config = builder.with_cert_resolver(resolver);
+	// Web sockets are supported over HTTP/1.1 only
+	server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+	Ok(server_config)
+}
+
+/// Accepts TCP connections and performs TLS handshakes concurrently,
+/// sending established connections to the HTTP server.
+async fn accept_connections(
+	listener: TcpListener,
+	acceptor: TlsAcceptor,
+	sender: mpsc::Sender<Result<TlsStream<AddrStream>, std::io::Error>>,
+) {
+	loop {
+		let (stream, remote_address) = match listener.accept().await {
+			Ok(connection) => connection,
+			Err(error) => {
+				warn!("Cannot accept connection: {error}");
+				continue;
+			},
+		};
+
+		let acceptor = acceptor.clone();
+		let sender = sender.clone();
+		let local_address = match stream.local_addr() {
+			Ok(address) => address,
+			Err(error) => {
+				warn!("Cannot get local address: {error}");
+				continue;
+			},
+		};
+		tokio::spawn(async move {
+			let stream = AddrStream::new(stream, remote_address, local_address);
+			match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+				Ok(Ok(stream)) => {
+					// Sending fails only if the server is stopped
+					let _ = sender.send(Ok(stream)).await;
+				},
+				Ok(Err(error)) => debug!(%remote_address, "TLS handshake failed: {error}"),
+				Err(_) => debug!(%remote_address, "TLS handshake timed out"),
+			}
+		});
+	}
+}
+
+/// Serves the routes over TLS, until the server fails
+pub async fn serve<F>(routes: F, address: SocketAddr, config: TlsConfig) -> Result<()>
+where
+	F: Filter + Clone + Send + Sync + 'static,
+	F::Extract: Reply,
+{
+	let resolver = Arc::new(CertificateResolver::new(
+		config.cert_path.clone(),
+		config.key_path.clone(),
+	)?);
+	let server_config = server_config(&config, resolver.clone())?;
+	tokio::spawn(resolver.watch(config.reload_interval));
+
+	let listener = TcpListener::bind(address)
+		.await
+		.with_context(|| format!("Cannot bind to {address}"))?;
+	let acceptor = TlsAcceptor::from(Arc::new(server_config));
+	let (sender, receiver) = mpsc::channel(128);
+	tokio::spawn(accept_connections(listener, acceptor, sender));
+
+	let service = warp::service(routes);
+	let make_service = make_service_fn(move |stream: &TlsStream<AddrStream>| {
+		let remote_address = RemoteAddress(stream.get_ref().0.remote_addr());
+		let service = service.clone();
+		async move {
+			Ok::<_, Infallible>(service_fn(move |mut request: Request<Body>| {
+				// Remote address is not available to the filters when served with custom acceptor
+				request.extensions_mut().insert(remote_address);
+				service.clone().call(request)
+			}))
+		}
+	});
+
+	hyper::Server::builder(accept::from_stream(ReceiverStream::new(receiver)))
+		.serve(make_service)
+		.await
+		.context("HTTPS server failed")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{read_certificates, read_private_key, TlsConfig};
+	use crate::types::RuntimeConfig;
+	use std::{env, fs};
+
+	#[test]
+	fn tls_config() {
+		let mut cfg = RuntimeConfig {
+			http_server_tls_cert_path: Some("cert.pem".to_string()),
+			..Default::default()
+		};
+		assert!(TlsConfig::new(&cfg).is_none());
+
+		cfg.http_server_tls_key_path = Some("key.pem".to_string());
+		let tls_config = TlsConfig::new(&cfg).unwrap();
+		assert_eq!(tls_config.cert_path, "cert.pem");
+		assert_eq!(tls_config.key_path, "key.pem");
+		assert!(tls_config.client_ca_path.is_none());
+	}
+
+	#[test]
+	fn read_missing_files() {
+		assert!(read_certificates("missing-cert.pem").is_err());
+		assert!(read_private_key("missing-key.pem").is_err());
+	}
+
+	#[test]
+	fn read_files_without_pem_items() {
+		let path = env::temp_dir().join("avail-light-tls-empty.pem");
+		fs::write(&path, "not a pem file").unwrap();
+		let path = path.to_str().unwrap();
+
+		assert!(read_certificates(path).is_err());
+		assert!(read_private_key(path).is_err());
+	}
+}
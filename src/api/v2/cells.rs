@@ -0,0 +1,155 @@
+use crate::{
+	network::{p2p, rpc},
+	proof,
+	types::DataProof,
+	utils,
+};
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
+use kate_recovery::{
+	config,
+	data::Cell,
+	matrix::{Dimensions, Position},
+};
+use sp_core::H256;
+use std::{collections::HashSet, sync::Arc};
+use tracing::warn;
+
+#[async_trait]
+pub trait Fetch {
+	/// Fetches cells with proofs and verifies them against the commitments,
+	/// returning only cells which were found and verified
+	async fn fetch_cells(
+		&self,
+		block_number: u32,
+		block_hash: H256,
+		dimensions: Dimensions,
+		commitments: &[[u8; config::COMMITMENT_SIZE]],
+		positions: &[Position],
+	) -> Result<Vec<Cell>>;
+
+	/// Fetches encoded extrinsics of the application from the block, in the block order
+	async fn fetch_app_data(&self, block_hash: H256, app_id: u32) -> Result<Vec<Vec<u8>>>;
+
+	/// Fetches merkle proof of the data submitted with the given extrinsic in the block
+	async fn fetch_data_proof(&self, block_hash: H256, extrinsic: &[u8]) -> Result<DataProof>;
+}
+
+/// Fetches cells from the DHT, falling back to the node RPC unless it is disabled.
+/// Application data and data proofs are fetched from the node RPC only.
+#[derive(Clone)]
+pub struct Fetcher {
+	pub p2p_client: p2p::Client,
+	pub rpc_client: rpc::Client,
+	pub disable_rpc: bool,
+	pub max_cells_per_rpc: usize,
+	pub pp: Arc<PublicParameters>,
+}
+
+impl Fetcher {
+	fn verify(
+		&self,
+		block_number: u32,
+		dimensions: Dimensions,
+		commitments: &[[u8; config::COMMITMENT_SIZE]],
+		mut cells: Vec<Cell>,
+	) -> Result<Vec<Cell>> {
+		let (verified, unverified) = proof::verify(
+			block_number,
+			dimensions,
+			&cells,
+			commitments,
+			self.pp.clone(),
+		)
+		.context("Failed to verify fetched cells")?;
+
+		if !unverified.is_empty() {
+			warn!(
+				block_number,
+				"Dropping {} cells with invalid proofs",
+				unverified.len()
+			);
+		}
+
+		let verified = verified
+			.into_iter()
+			.map(|position| (position.row, position.col))
+			.collect::<HashSet<_>>();
+		cells.retain(|cell| verified.contains(&(cell.position.row, cell.position.col)));
+		Ok(cells)
+	}
+}
+
+#[async_trait]
+impl Fetch for Fetcher {
+	async fn fetch_cells(
+		&self,
+		block_number: u32,
+		block_hash: H256,
+		dimensions: Dimensions,
+		commitments: &[[u8; config::COMMITMENT_SIZE]],
+		positions: &[Position],
+	) -> Result<Vec<Cell>> {
+		let (dht_cells, _) = self
+			.p2p_client
+			.fetch_cells_from_dht(block_number, positions)
+			.await;
+
+		let mut cells = self.verify(block_number, dimensions, commitments, dht_cells)?;
+
+		// Cells which are not found or have invalid proofs in the DHT are fetched from RPC
+		let verified = cells
+			.iter()
+			.map(|cell| (cell.position.row, cell.position.col))
+			.collect::<HashSet<_>>();
+		let unfetched = positions
+			.iter()
+			.filter(|position| !verified.contains(&(position.row, position.col)))
+			.copied()
+			.collect::<Vec<_>>();
+
+		if !self.disable_rpc && !unfetched.is_empty() {
+			for positions in unfetched.chunks(self.max_cells_per_rpc.max(1)) {
+				let rpc_cells = self
+					.rpc_client
+					.request_kate_proof(block_hash, positions)
+					.await
+					.context("Failed to fetch cells from node RPC")?;
+				cells.extend(self.verify(block_number, dimensions, commitments, rpc_cells)?);
+			}
+		}
+
+		Ok(cells)
+	}
+
+	async fn fetch_app_data(&self, block_hash: H256, app_id: u32) -> Result<Vec<Vec<u8>>> {
+		if self.disable_rpc {
+			bail!("Application data cannot be fetched, since RPC is disabled");
+		}
+		let extrinsics = self
+			.rpc_client
+			.get_block_extrinsics(block_hash)
+			.await
+			.context("Failed to fetch block from node RPC")?;
+
+		let mut app_data = vec![];
+		for extrinsic in extrinsics {
+			if utils::decode_app_id(&extrinsic)? == app_id {
+				app_data.push(extrinsic);
+			}
+		}
+		Ok(app_data)
+	}
+
+	async fn fetch_data_proof(&self, block_hash: H256, extrinsic: &[u8]) -> Result<DataProof> {
+		if self.disable_rpc {
+			bail!("Data proof cannot be fetched, since RPC is disabled");
+		}
+		self.rpc_client
+			.request_data_proof(block_hash, extrinsic.to_vec())
+			.await
+			.context("Failed to fetch data proof from node RPC")
+	}
+}
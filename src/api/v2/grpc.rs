@@ -0,0 +1,658 @@
+//! gRPC service for the light client data and events, defined in `proto/light_client.proto`.
+//!
+//! Unary calls are served by the same handlers as the v2 HTTP API, and the streams are fed by the
+//! same published messages as the web socket and server-sent events clients.
+
+use super::{
+	handlers,
+	types::{
+		self, DataField, DataQuery, Error, ErrorCode, Event, Events, FieldsQueryParameter,
+		PublishMessage,
+	},
+};
+use crate::{
+	api::{
+		auth::{Auth, RateLimiter},
+		tls::TlsConfig,
+	},
+	data::Database,
+	network::rpc::Node,
+	types::{ApiScope, RuntimeConfig, State},
+};
+use anyhow::{Context, Result};
+use futures::{future, Stream, StreamExt};
+use kate_recovery::matrix::Partition;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashSet,
+	fs,
+	net::SocketAddr,
+	pin::Pin,
+	str::FromStr,
+	sync::{Arc, Mutex},
+	time::Instant,
+};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tonic::{
+	transport::{Certificate, Identity, ServerTlsConfig},
+	Request, Response, Status,
+};
+use tracing::{error, info};
+
+pub mod proto {
+	tonic::include_proto!("avail.light.v1");
+}
+
+use proto::light_client_server::{LightClient, LightClientServer};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct GrpcConfig {
+	/// Light client gRPC server port, served on the HTTP server host. If not set, gRPC server is disabled (default: None).
+	pub grpc_server_port: Option<u16>,
+}
+
+impl From<types::BlockRange> for proto::BlockRange {
+	fn from(value: types::BlockRange) -> Self {
+		proto::BlockRange {
+			first: value.first,
+			last: value.last,
+		}
+	}
+}
+
+impl From<types::HistoricalSync> for proto::HistoricalSync {
+	fn from(value: types::HistoricalSync) -> Self {
+		proto::HistoricalSync {
+			synced: value.synced,
+			available: value.available.map(From::from),
+			app_data: value.app_data.map(From::from),
+		}
+	}
+}
+
+impl From<types::FinalitySync> for proto::FinalitySync {
+	fn from(value: types::FinalitySync) -> Self {
+		proto::FinalitySync {
+			synced: value.synced,
+			latest: value.latest,
+			target: value.target,
+			blocks_per_second: value.blocks_per_second,
+			eta: value.eta,
+		}
+	}
+}
+
+impl From<types::Blocks> for proto::Blocks {
+	fn from(value: types::Blocks) -> Self {
+		proto::Blocks {
+			latest: value.latest,
+			available: value.available.map(From::from),
+			app_data: value.app_data.map(From::from),
+			historical_sync: value.historical_sync.map(From::from),
+			finality_sync: value.finality_sync.map(From::from),
+		}
+	}
+}
+
+impl From<types::Mode> for proto::Mode {
+	fn from(value: types::Mode) -> Self {
+		match value {
+			types::Mode::Light => proto::Mode::Light,
+			types::Mode::App => proto::Mode::App,
+			types::Mode::Partition => proto::Mode::Partition,
+		}
+	}
+}
+
+impl From<types::Status> for proto::Status {
+	fn from(value: types::Status) -> Self {
+		proto::Status {
+			modes: value
+				.modes
+				.into_iter()
+				.map(|mode| proto::Mode::from(mode).into())
+				.collect(),
+			app_id: value.app_id,
+			genesis_hash: value.genesis_hash,
+			network: value.network,
+			blocks: Some(value.blocks.into()),
+			partition: value
+				.partition
+				.map(|Partition { number, fraction }| format!("{number}/{fraction}")),
+		}
+	}
+}
+
+impl From<types::BlockStatus> for proto::BlockStatus {
+	fn from(value: types::BlockStatus) -> Self {
+		match value {
+			types::BlockStatus::Unavailable => proto::BlockStatus::Unavailable,
+			types::BlockStatus::Pending => proto::BlockStatus::Pending,
+			types::BlockStatus::VerifyingHeader => proto::BlockStatus::VerifyingHeader,
+			types::BlockStatus::VerifyingConfidence => proto::BlockStatus::VerifyingConfidence,
+			types::BlockStatus::VerifyingData => proto::BlockStatus::VerifyingData,
+			types::BlockStatus::Finished => proto::BlockStatus::Finished,
+		}
+	}
+}
+
+impl From<types::Block> for proto::Block {
+	fn from(value: types::Block) -> Self {
+		proto::Block {
+			status: proto::BlockStatus::from(value.status).into(),
+			confidence: value.confidence,
+			failure: value.failure,
+		}
+	}
+}
+
+impl From<types::Header> for proto::Header {
+	fn from(value: types::Header) -> Self {
+		let extension = value.extension;
+		let app_lookup = proto::DataLookup {
+			size: extension.app_lookup.size,
+			index: extension
+				.app_lookup
+				.index
+				.into_iter()
+				.map(|item| proto::DataLookupItem {
+					app_id: item.app_id.0,
+					start: item.start,
+				})
+				.collect(),
+		};
+		proto::Header {
+			hash: value.hash.as_bytes().to_vec(),
+			parent_hash: value.parent_hash.as_bytes().to_vec(),
+			number: value.number,
+			state_root: value.state_root.as_bytes().to_vec(),
+			extrinsics_root: value.extrinsics_root.as_bytes().to_vec(),
+			extension: Some(proto::Extension {
+				rows: extension.rows.into(),
+				cols: extension.cols.into(),
+				data_root: extension.data_root.as_bytes().to_vec(),
+				commitments: extension
+					.commitments
+					.into_iter()
+					.map(|commitment| commitment.0.to_vec())
+					.collect(),
+				app_lookup: Some(app_lookup),
+			}),
+		}
+	}
+}
+
+impl From<types::HeaderMessage> for proto::HeaderMessage {
+	fn from(value: types::HeaderMessage) -> Self {
+		proto::HeaderMessage {
+			block_number: value.block_number,
+			header: Some(value.header.into()),
+		}
+	}
+}
+
+impl From<types::ConfidenceMessage> for proto::ConfidenceMessage {
+	fn from(value: types::ConfidenceMessage) -> Self {
+		proto::ConfidenceMessage {
+			block_number: value.block_number,
+			confidence: value.confidence,
+		}
+	}
+}
+
+impl From<types::DataTransaction> for proto::DataTransaction {
+	fn from(value: types::DataTransaction) -> Self {
+		proto::DataTransaction {
+			data: value.data.map(|data| data.0),
+			extrinsic: value.extrinsic.map(|extrinsic| extrinsic.0),
+		}
+	}
+}
+
+impl From<types::DataMessage> for proto::DataMessage {
+	fn from(value: types::DataMessage) -> Self {
+		proto::DataMessage {
+			block_number: value.block_number,
+			data_transactions: value
+				.data_transactions
+				.into_iter()
+				.map(From::from)
+				.collect(),
+		}
+	}
+}
+
+impl From<types::DataResponse> for proto::DataMessage {
+	fn from(value: types::DataResponse) -> Self {
+		proto::DataMessage {
+			block_number: value.block_number,
+			data_transactions: value
+				.data_transactions
+				.into_iter()
+				.map(From::from)
+				.collect(),
+		}
+	}
+}
+
+/// Maps the v2 API error to the gRPC status with the matching code
+fn error_status(error: Error) -> Status {
+	if let Some(cause) = error.cause.as_ref() {
+		error!("{}: {cause:#}", error.message);
+	}
+	match error.error_code {
+		ErrorCode::NotFound => Status::not_found(error.message),
+		ErrorCode::BadRequest => Status::invalid_argument(error.message),
+		ErrorCode::Forbidden => Status::permission_denied(error.message),
+		ErrorCode::InternalServerError => Status::internal(error.message),
+		ErrorCode::ServiceUnavailable => Status::unavailable(error.message),
+	}
+}
+
+/// Parses requested data fields, returning `None` if none are requested
+fn data_fields(fields: Vec<i32>) -> Result<Option<HashSet<DataField>>, Status> {
+	if fields.is_empty() {
+		return Ok(None);
+	}
+	fields
+		.into_iter()
+		.map(|field| match proto::DataField::from_i32(field) {
+			Some(proto::DataField::Data) => Ok(DataField::Data),
+			Some(proto::DataField::Extrinsic) => Ok(DataField::Extrinsic),
+			_ => Err(Status::invalid_argument(format!(
+				"Invalid data field {field}"
+			))),
+		})
+		.collect::<Result<HashSet<_>, _>>()
+		.map(Some)
+}
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Streams published messages mapped to the response messages, skipping the unmapped ones.
+/// Stream ends with an error if the client falls behind.
+fn messages_stream<T>(
+	receiver: broadcast::Receiver<Arc<Event>>,
+	map: impl Fn(PublishMessage) -> Option<T> + Send + 'static,
+) -> impl Stream<Item = Result<T, Status>> + Send + 'static
+where
+	T: Send + 'static,
+{
+	BroadcastStream::new(receiver)
+		.scan(false, move |lagged, result| {
+			if *lagged {
+				return future::ready(None);
+			}
+			let item = match result {
+				Ok(event) => Ok(map(event.message.clone())),
+				Err(error) => {
+					*lagged = true;
+					Err(Status::resource_exhausted(format!(
+						"Subscriber lagged behind: {error}"
+					)))
+				},
+			};
+			future::ready(Some(item))
+		})
+		.filter_map(|item| future::ready(item.transpose()))
+}
+
+pub struct Service<D> {
+	pub auth: Auth,
+	pub rate_limiter: Option<RateLimiter>,
+	pub config: RuntimeConfig,
+	pub node: Node,
+	pub state: Arc<Mutex<State>>,
+	pub db: D,
+	pub events: Events,
+}
+
+impl<D: Database> Service<D> {
+	/// Checks the rate limit of the remote address,
+	/// and that the credential in the `authorization` metadata is granted the given scope
+	fn authorize<T>(&self, request: &Request<T>, scope: ApiScope) -> Result<(), Status> {
+		if let (Some(limiter), Some(address)) = (self.rate_limiter.as_ref(), request.remote_addr())
+		{
+			if !limiter.check(address.ip(), Instant::now()) {
+				return Err(Status::resource_exhausted("Too Many Requests"));
+			}
+		}
+		let authorization = request
+			.metadata()
+			.get("authorization")
+			.and_then(|value| value.to_str().ok());
+		let Some(permissions) = self.auth.bearer_permissions(authorization) else {
+			return Err(Status::unauthenticated("Unauthorized"));
+		};
+		if !permissions.has_scope(scope) {
+			return Err(Status::permission_denied("Forbidden"));
+		}
+		Ok(())
+	}
+
+	async fn subscribe<T: Send + 'static>(
+		&self,
+		map: impl Fn(PublishMessage) -> Option<T> + Send + 'static,
+	) -> Result<Response<ResponseStream<T>>, Status> {
+		let Some((_, receiver)) = self.events.subscribe(None).await else {
+			return Err(Status::internal("Cannot subscribe to events"));
+		};
+		Ok(Response::new(Box::pin(messages_stream(receiver, map))))
+	}
+}
+
+#[tonic::async_trait]
+impl<D: Database> LightClient for Service<D> {
+	async fn get_status(
+		&self,
+		request: Request<proto::StatusRequest>,
+	) -> Result<Response<proto::Status>, Status> {
+		self.authorize(&request, ApiScope::Read)?;
+		let status = handlers::status(self.config.clone(), self.node.clone(), self.state.clone());
+		Ok(Response::new(status.into()))
+	}
+
+	async fn get_block(
+		&self,
+		request: Request<proto::BlockRequest>,
+	) -> Result<Response<proto::Block>, Status> {
+		self.authorize(&request, ApiScope::Read)?;
+		let block_number = request.into_inner().block_number;
+		let (config, state, db) = (self.config.clone(), self.state.clone(), self.db.clone());
+		handlers::block(block_number, config, state, db)
+			.await
+			.map(|block| Response::new(block.into()))
+			.map_err(error_status)
+	}
+
+	async fn get_block_header(
+		&self,
+		request: Request<proto::BlockRequest>,
+	) -> Result<Response<proto::Header>, Status> {
+		self.authorize(&request, ApiScope::Read)?;
+		let block_number = request.into_inner().block_number;
+		let (config, state, db) = (self.config.clone(), self.state.clone(), self.db.clone());
+		handlers::block_header(block_number, config, state, db)
+			.await
+			.map(|header| Response::new(header.into()))
+			.map_err(error_status)
+	}
+
+	async fn get_block_data(
+		&self,
+		request: Request<proto::BlockDataRequest>,
+	) -> Result<Response<proto::DataMessage>, Status> {
+		self.authorize(&request, ApiScope::Read)?;
+		let proto::BlockDataRequest {
+			block_number,
+			fields,
+		} = request.into_inner();
+		let query = DataQuery {
+			fields: data_fields(fields)?.map(FieldsQueryParameter),
+		};
+		let (config, state, db) = (self.config.clone(), self.state.clone(), self.db.clone());
+		handlers::block_data(block_number, query, config, state, db)
+			.await
+			.map(|data| Response::new(data.into()))
+			.map_err(error_status)
+	}
+
+	type SubscribeHeadersStream = ResponseStream<proto::HeaderMessage>;
+
+	async fn subscribe_headers(
+		&self,
+		request: Request<proto::SubscribeRequest>,
+	) -> Result<Response<Self::SubscribeHeadersStream>, Status> {
+		self.authorize(&request, ApiScope::Subscribe)?;
+		self.subscribe(|message| match message {
+			PublishMessage::HeaderVerified(header) => Some((*header).into()),
+			_ => None,
+		})
+		.await
+	}
+
+	type SubscribeConfidenceStream = ResponseStream<proto::ConfidenceMessage>;
+
+	async fn subscribe_confidence(
+		&self,
+		request: Request<proto::SubscribeRequest>,
+	) -> Result<Response<Self::SubscribeConfidenceStream>, Status> {
+		self.authorize(&request, ApiScope::Subscribe)?;
+		self.subscribe(|message| match message {
+			PublishMessage::ConfidenceAchieved(confidence) => Some(confidence.into()),
+			_ => None,
+		})
+		.await
+	}
+
+	type SubscribeDataStream = ResponseStream<proto::DataMessage>;
+
+	async fn subscribe_data(
+		&self,
+		request: Request<proto::SubscribeDataRequest>,
+	) -> Result<Response<Self::SubscribeDataStream>, Status> {
+		self.authorize(&request, ApiScope::Subscribe)?;
+		let fields = data_fields(request.into_inner().fields)?.unwrap_or_default();
+		self.subscribe(move |mut message| {
+			message.apply_filter(&fields);
+			match message {
+				PublishMessage::DataVerified(data) => Some(data.into()),
+				_ => None,
+			}
+		})
+		.await
+	}
+}
+
+/// Loads the HTTP server certificate, key and client CA, so gRPC is served with the same TLS configuration
+fn tls_config(config: &TlsConfig) -> Result<ServerTlsConfig> {
+	let cert =
+		fs::read(&config.cert_path).with_context(|| format!("Cannot read {}", config.cert_path))?;
+	let key =
+		fs::read(&config.key_path).with_context(|| format!("Cannot read {}", config.key_path))?;
+	let tls_config = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+
+	let Some(client_ca_path) = config.client_ca_path.as_ref() else {
+		return Ok(tls_config);
+	};
+	let client_ca =
+		fs::read(client_ca_path).with_context(|| format!("Cannot read {client_ca_path}"))?;
+	Ok(tls_config.client_ca_root(Certificate::from_pem(client_ca)))
+}
+
+/// Runs gRPC server on the HTTP server host and the given port, until it fails.
+/// Server uses TLS if it is configured for the HTTP server.
+pub async fn run(
+	port: u16,
+	config: RuntimeConfig,
+	node: Node,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+	events: Events,
+) -> Result<()> {
+	let host = config.http_server_host.clone();
+	let addr = SocketAddr::from_str(&format!("{host}:{port}"))
+		.context("Unable to parse gRPC server address")?;
+
+	let tls_config = TlsConfig::new(&config);
+	let service = Service {
+		auth: Auth::from(&config),
+		rate_limiter: config.http_rate_limit.map(RateLimiter::new),
+		config,
+		node,
+		state,
+		db,
+		events,
+	};
+
+	let mut server = tonic::transport::Server::builder();
+	let scheme = match tls_config {
+		Some(tls_config) => {
+			server = server
+				.tls_config(self::tls_config(&tls_config)?)
+				.context("Invalid gRPC TLS configuration")?;
+			"https"
+		},
+		None => "http",
+	};
+
+	info!("gRPC running on {scheme}://{host}:{port}");
+	server
+		.add_service(LightClientServer::new(service))
+		.serve(addr)
+		.await
+		.context("gRPC server stopped")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		data_fields, error_status, messages_stream, proto, proto::light_client_server::LightClient,
+		tls_config, Service,
+	};
+	use crate::{
+		api::{
+			auth::Auth,
+			tls::TlsConfig,
+			v2::types::{Error, Events, PublishMessage, Topic},
+		},
+		data::MemoryDB,
+		network::rpc::Node,
+		types::{ApiScope, ApiSecret, ApiToken, RuntimeConfig, State},
+	};
+	use futures::StreamExt;
+	use std::sync::{Arc, Mutex};
+	use test_case::test_case;
+	use tonic::{Code, Request};
+
+	fn service(auth: Auth) -> Service<MemoryDB> {
+		Service {
+			auth,
+			rate_limiter: None,
+			config: RuntimeConfig::default(),
+			node: Node::default(),
+			state: Arc::new(Mutex::new(State::default())),
+			db: MemoryDB::default(),
+			events: Events::default(),
+		}
+	}
+
+	fn confidence_message(block_number: u32) -> PublishMessage {
+		let message = format!(
+			r#"{{"topic":"confidence-achieved","message":{{"block_number":{block_number},"confidence":93.75}}}}"#
+		);
+		serde_json::from_str(&message).unwrap()
+	}
+
+	#[test_case(Error::not_found(), Code::NotFound ; "Not found")]
+	#[test_case(Error::bad_request_unknown("Bad request"), Code::InvalidArgument ; "Bad request")]
+	#[test_case(Error::forbidden_unknown("Forbidden"), Code::PermissionDenied ; "Forbidden")]
+	#[test_case(Error::internal_server_error(anyhow::anyhow!("Error")), Code::Internal ; "Internal")]
+	#[test_case(Error::service_unavailable("Unavailable"), Code::Unavailable ; "Unavailable")]
+	fn error_status_code(error: Error, expected: Code) {
+		assert_eq!(error_status(error).code(), expected);
+	}
+
+	#[test]
+	fn data_fields_parse() {
+		assert!(data_fields(vec![]).unwrap().is_none());
+		assert_eq!(data_fields(vec![1, 2]).unwrap().unwrap().len(), 2);
+		let error = data_fields(vec![0]).err().unwrap();
+		assert_eq!(error.code(), Code::InvalidArgument);
+	}
+
+	#[tokio::test]
+	async fn get_block_not_found() {
+		let request = Request::new(proto::BlockRequest { block_number: 10 });
+		let status = service(Auth::default())
+			.get_block(request)
+			.await
+			.unwrap_err();
+		assert_eq!(status.code(), Code::NotFound);
+	}
+
+	#[test_case(None, Code::Unauthenticated ; "Missing token")]
+	#[test_case(Some("Bearer unknown"), Code::Unauthenticated ; "Unknown token")]
+	#[test_case(Some("Bearer submit"), Code::PermissionDenied ; "Missing scope")]
+	#[tokio::test]
+	async fn get_status_unauthorized(authorization: Option<&str>, expected: Code) {
+		let config = RuntimeConfig {
+			http_api_tokens: vec![ApiToken {
+				token: ApiSecret("submit".to_string()),
+				scopes: vec![ApiScope::Submit],
+				app_ids: None,
+			}],
+			..Default::default()
+		};
+		let mut request = Request::new(proto::StatusRequest {});
+		if let Some(authorization) = authorization {
+			request
+				.metadata_mut()
+				.insert("authorization", authorization.parse().unwrap());
+		}
+		let status = service(Auth::from(&config))
+			.get_status(request)
+			.await
+			.unwrap_err();
+		assert_eq!(status.code(), expected);
+	}
+
+	#[tokio::test]
+	async fn subscribe_confidence() {
+		let service = service(Auth::default());
+		let request = Request::new(proto::SubscribeRequest {});
+		let mut stream = service
+			.subscribe_confidence(request)
+			.await
+			.unwrap()
+			.into_inner();
+
+		let submission = PublishMessage::SubmissionStatus(crate::types::Submission::new(
+			sp_core::H256::random(),
+		));
+		service
+			.events
+			.publish(Topic::SubmissionStatus, submission)
+			.await;
+		service
+			.events
+			.publish(Topic::ConfidenceAchieved, confidence_message(1))
+			.await;
+
+		let message = stream.next().await.unwrap().unwrap();
+		assert_eq!(message.block_number, 1);
+		assert_eq!(message.confidence, Some(93.75));
+	}
+
+	#[tokio::test]
+	async fn messages_stream_lagged() {
+		let events = Events::new(1);
+		let (_, receiver) = events.subscribe(None).await.unwrap();
+		for block_number in 1..=3 {
+			events
+				.publish(Topic::ConfidenceAchieved, confidence_message(block_number))
+				.await;
+		}
+
+		let stream = messages_stream(receiver, |message| message.block_number());
+		let items = stream.collect::<Vec<_>>().await;
+		assert_eq!(items.len(), 1);
+		assert_eq!(
+			items[0].as_ref().unwrap_err().code(),
+			Code::ResourceExhausted
+		);
+	}
+
+	#[test]
+	fn tls_config_missing_files() {
+		let config = TlsConfig::new(&RuntimeConfig {
+			http_server_tls_cert_path: Some("missing-cert.pem".to_string()),
+			http_server_tls_key_path: Some("missing-key.pem".to_string()),
+			..Default::default()
+		})
+		.unwrap();
+		assert!(tls_config(&config).is_err());
+	}
+}
@@ -1,82 +1,278 @@
 use super::{
-	transactions,
+	cells, jsonrpc, sse, transactions,
 	types::{
-		block_status, filter_fields, Block, BlockStatus, DataQuery, DataResponse, DataTransaction,
-		Error, FieldsQueryParameter, Header, Status, SubmitResponse, Subscription, SubscriptionId,
+		block_status, filter_fields, ApplicationKeyQuery, ApplicationKeyRequest,
+		ApplicationKeyResponse, Block, BlockField, BlockStatus, BlockSummary, BlocksQuery,
+		BlocksResponse, CellRange, CellsQuery, CellsResponse, DataField, DataQuery, DataResponse,
+		DataTransaction, Error, EstimateResponse, EventId, Events, EventsQuery,
+		FieldsQueryParameter, Header, Matrix, ProofQuery, ProofResponse, RowResponse, RowsQuery,
+		RowsResponse, Status, SubmitQuery, SubmitResponse, Subscription, SubscriptionId,
 		Transaction, Version, WsClients,
 	},
 	ws,
 };
 use crate::{
-	api::v2::types::{ErrorCode, InternalServerError},
+	api::{
+		auth::Permissions,
+		v2::types::{ErrorCode, InternalServerError},
+	},
 	data::Database,
 	network::rpc::Node,
-	types::{RuntimeConfig, State},
-	utils::calculate_confidence,
+	types::{RuntimeConfig, State, Submission},
+	utils::{calculate_confidence, extract_app_lookup, extract_kate},
 };
 use anyhow::anyhow;
-use hyper::StatusCode;
+use avail_core::AppId;
+use avail_subxt::primitives::Header as DaHeader;
+use codec::Encode;
+use hyper::{body::Bytes, StatusCode};
+use kate_recovery::{
+	commitments, config,
+	matrix::{Dimensions, Position},
+};
+use sp_core::{blake2_256, H256};
 use std::{
-	convert::Infallible,
+	collections::{HashMap, HashSet},
 	sync::{Arc, Mutex},
 };
 use tracing::error;
 use uuid::Uuid;
 use warp::{ws::Ws, Rejection, Reply};
 
+const BLOCKS_DEFAULT_LIMIT: u32 = 100;
+const BLOCKS_MAX_LIMIT: u32 = 1000;
+const CELLS_MAX_LIMIT: usize = 1000;
+const ROWS_MAX_LIMIT: usize = 64;
+
 pub async fn subscriptions(
 	subscription: Subscription,
 	clients: WsClients,
-) -> Result<SubscriptionId, Infallible> {
+	state: Arc<Mutex<State>>,
+) -> Result<SubscriptionId, Error> {
+	if let Some(from_block) = subscription.from_block {
+		let latest = state.lock().expect("State lock can be acquired").latest;
+		if from_block < clients.config.first_replay_block(latest) {
+			return Err(Error::bad_request_unknown("Replay block is too old."));
+		}
+	}
 	let subscription_id = Uuid::new_v4().to_string();
-	clients.subscribe(&subscription_id, subscription).await;
+	clients
+		.subscribe(&subscription_id, subscription)
+		.await
+		.map_err(|error| Error::service_unavailable(&error.to_string()))?;
 	Ok(SubscriptionId { subscription_id })
 }
 
+pub async fn unsubscribe(subscription_id: String, clients: WsClients) -> Result<impl Reply, Error> {
+	if !clients.unsubscribe(&subscription_id).await {
+		return Err(Error::not_found());
+	}
+	Ok(StatusCode::NO_CONTENT)
+}
+
+/// Validates the transaction against the submitter limits and the granted permissions.
+/// Returns the application ID of the data transaction.
+fn submit_app_id(
+	submitter: &impl transactions::Submit,
+	permissions: &Permissions,
+	query: &SubmitQuery,
+	transaction: &Transaction,
+) -> Result<Option<u32>, Error> {
+	if transaction.is_empty() {
+		return Err(Error::bad_request_unknown("Transaction is empty."));
+	}
+	let key = query.key.as_deref();
+	match transaction {
+		Transaction::Data(data) => {
+			if !submitter.has_signer(key) {
+				return Err(Error::not_found());
+			};
+			if data.0.len() > submitter.max_blob_size() {
+				return Err(Error::bad_request_unknown("Data blob is too large."));
+			};
+			let Some(app_id) = query.app_id.or(submitter.default_app_id()) else {
+				return Err(Error::bad_request_unknown("App ID is not set."));
+			};
+			if !permissions.allows_app_id(app_id) {
+				return Err(Error::forbidden_unknown(
+					"Submit to the application is not allowed.",
+				));
+			}
+			Ok(Some(app_id))
+		},
+		Transaction::Extrinsic(_) => {
+			// Application of the signed extrinsic cannot be verified
+			if !permissions.allows_any_app_id() {
+				return Err(Error::forbidden_unknown("Extrinsic submit is not allowed."));
+			}
+			Ok(None)
+		},
+	}
+}
+
 pub async fn submit(
 	submitter: Arc<impl transactions::Submit>,
+	permissions: Permissions,
+	query: SubmitQuery,
+	transaction: Transaction,
+) -> Result<warp::reply::Response, Error> {
+	if query.is_async {
+		let submission = submit_async(submitter, permissions, query, transaction).await?;
+		return Ok(warp::reply::with_status(submission, StatusCode::ACCEPTED).into_response());
+	}
+
+	submit_and_wait(submitter, permissions, query, transaction)
+		.await
+		.map(Reply::into_response)
+}
+
+/// Submits transaction and waits for its finalization
+pub async fn submit_and_wait(
+	submitter: Arc<impl transactions::Submit>,
+	permissions: Permissions,
+	query: SubmitQuery,
 	transaction: Transaction,
 ) -> Result<SubmitResponse, Error> {
-	if matches!(&transaction, Transaction::Data(_)) && !submitter.has_signer() {
+	let app_id = submit_app_id(submitter.as_ref(), &permissions, &query, &transaction)?;
+	submitter
+		.submit(transaction, query.key.as_deref(), app_id)
+		.await
+		.map_err(Error::internal_server_error)
+}
+
+/// Submits transaction without waiting for finalization
+pub async fn submit_async(
+	submitter: Arc<impl transactions::Submit>,
+	permissions: Permissions,
+	query: SubmitQuery,
+	transaction: Transaction,
+) -> Result<Submission, Error> {
+	let app_id = submit_app_id(submitter.as_ref(), &permissions, &query, &transaction)?;
+	submitter
+		.submit_async(transaction, query.key.as_deref(), app_id)
+		.await
+		.map_err(Error::internal_server_error)
+}
+
+pub async fn estimate(
+	submitter: Arc<impl transactions::Submit>,
+	permissions: Permissions,
+	query: SubmitQuery,
+	transaction: Transaction,
+) -> Result<EstimateResponse, Error> {
+	let app_id = submit_app_id(submitter.as_ref(), &permissions, &query, &transaction)?;
+	submitter
+		.estimate(transaction, query.key.as_deref(), app_id)
+		.await
+		.map_err(Error::internal_server_error)
+}
+
+pub async fn create_application_key(
+	submitter: Arc<impl transactions::Submit>,
+	permissions: Permissions,
+	query: ApplicationKeyQuery,
+	request: ApplicationKeyRequest,
+) -> Result<ApplicationKeyResponse, Error> {
+	if request.name.is_empty() {
+		return Err(Error::bad_request_unknown("Application key name is empty."));
+	}
+	let key = query.key.as_deref();
+	if !submitter.has_signer(key) {
 		return Err(Error::not_found());
-	};
+	}
+	if !permissions.allows_any_app_id() {
+		return Err(Error::forbidden_unknown(
+			"Creating application key is not allowed.",
+		));
+	}
 
 	submitter
-		.submit(transaction)
+		.create_application_key(request.name.into_bytes(), key)
 		.await
 		.map_err(Error::internal_server_error)
 }
 
+pub async fn submission(hash: H256, db: impl Database) -> Result<Submission, Error> {
+	db.get_submission(hash)
+		.map_err(Error::internal_server_error)?
+		.ok_or_else(Error::not_found)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn ws(
 	subscription_id: String,
 	ws: Ws,
+	permissions: Permissions,
 	clients: WsClients,
 	version: Version,
 	config: RuntimeConfig,
 	node: Node,
 	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync + 'static>>,
 	state: Arc<Mutex<State>>,
+	db: impl Database,
 ) -> Result<impl Reply, Rejection> {
 	if !clients.has_subscription(&subscription_id).await {
 		return Err(warp::reject::not_found());
 	}
-	// NOTE: Multiple connections to the same client are currently allowed
+	// NOTE: New connection to the same client replaces the previous one
 	Ok(ws.on_upgrade(move |web_socket| {
 		ws::connect(
 			subscription_id,
 			web_socket,
+			permissions,
 			clients,
 			version,
 			config,
 			node,
 			submitter.clone(),
 			state.clone(),
+			db.clone(),
 		)
 	}))
 }
 
-pub fn status(config: RuntimeConfig, node: Node, state: Arc<Mutex<State>>) -> impl Reply {
+pub async fn jsonrpc(
+	permissions: Permissions,
+	body: Bytes,
+	server: jsonrpc::Server<impl transactions::Submit + Send + Sync + 'static, impl Database>,
+) -> warp::reply::Response {
+	match server.handle(&body, &permissions).await {
+		Some(response) => warp::reply::json(&response).into_response(),
+		// Notifications have no response
+		None => StatusCode::NO_CONTENT.into_response(),
+	}
+}
+
+pub async fn jsonrpc_ws(
+	ws: Ws,
+	permissions: Permissions,
+	server: jsonrpc::Server<impl transactions::Submit + Send + Sync + 'static, impl Database>,
+) -> impl Reply {
+	ws.on_upgrade(move |web_socket| jsonrpc::connect(web_socket, permissions, server))
+}
+
+pub async fn events(
+	query: EventsQuery,
+	last_event_id: Option<EventId>,
+	events: Events,
+) -> Result<impl Reply, Error> {
+	let FieldsQueryParameter(topics) = query.topics;
+	let data_fields = query
+		.data_fields
+		.map(|FieldsQueryParameter(data_fields)| data_fields)
+		.unwrap_or_default();
+
+	let Some((buffered, receiver)) = events.subscribe(last_event_id).await else {
+		return Err(Error::bad_request_unknown(
+			"Events after the last event ID are no longer available",
+		));
+	};
+
+	let stream = sse::events_stream(buffered, receiver, topics, data_fields);
+	Ok(warp::sse::reply(warp::sse::keep_alive().stream(stream)))
+}
+
+pub fn status(config: RuntimeConfig, node: Node, state: Arc<Mutex<State>>) -> Status {
 	let state = state.lock().expect("Lock should be acquired");
 	Status::new(&config, &node, &state)
 }
@@ -99,10 +295,21 @@ pub async fn block(
 	config: RuntimeConfig,
 	state: Arc<Mutex<State>>,
 	db: impl Database,
-) -> Result<impl Reply, Error> {
-	let state = state.lock().expect("Lock should be acquired");
+) -> Result<Block, Error> {
+	let block_state = db
+		.get_block_state(block_number)
+		.map_err(Error::internal_server_error)?;
 
-	let Some(block_status) = block_status(&config.sync_start_block, &state, block_number) else {
+	let block_status = {
+		let state = state.lock().expect("Lock should be acquired");
+		block_status(
+			&config.sync_start_block,
+			&state,
+			block_number,
+			block_state.as_ref(),
+		)
+	};
+	let Some(block_status) = block_status else {
 		return Err(Error::not_found());
 	};
 
@@ -111,18 +318,30 @@ pub async fn block(
 		.map_err(Error::internal_server_error)?
 		.map(calculate_confidence);
 
-	Ok(Block::new(block_status, confidence))
+	let failure = block_state.and_then(|block_state| block_state.failure);
+	Ok(Block::new(block_status, confidence, failure))
 }
 
-pub async fn block_header(
+fn available_header(
 	block_number: u32,
-	config: RuntimeConfig,
-	state: Arc<Mutex<State>>,
-	db: impl Database,
-) -> Result<Header, Error> {
-	let state = state.lock().expect("Lock should be acquired");
+	config: &RuntimeConfig,
+	state: &Arc<Mutex<State>>,
+	db: &impl Database,
+) -> Result<DaHeader, Error> {
+	let block_state = db
+		.get_block_state(block_number)
+		.map_err(Error::internal_server_error)?;
 
-	let Some(block_status) = block_status(&config.sync_start_block, &state, block_number) else {
+	let block_status = {
+		let state = state.lock().expect("Lock should be acquired");
+		block_status(
+			&config.sync_start_block,
+			&state,
+			block_number,
+			block_state.as_ref(),
+		)
+	};
+	let Some(block_status) = block_status else {
 		return Err(Error::not_found());
 	};
 
@@ -135,10 +354,160 @@ pub async fn block_header(
 
 	db.get_header(block_number)
 		.and_then(|header| header.ok_or_else(|| anyhow!("Header not found")))
-		.and_then(|header| header.try_into())
 		.map_err(Error::internal_server_error)
 }
 
+pub async fn block_header(
+	block_number: u32,
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+) -> Result<Header, Error> {
+	available_header(block_number, &config, &state, &db)?
+		.try_into()
+		.map_err(Error::internal_server_error)
+}
+
+struct BlockMatrix {
+	block_hash: H256,
+	dimensions: Dimensions,
+	commitments: Vec<[u8; config::COMMITMENT_SIZE]>,
+	matrix: Matrix,
+}
+
+fn matrix(header: &DaHeader) -> Result<BlockMatrix, Error> {
+	let (rows, cols, _, commitment) = extract_kate(&header.extension);
+	let Some(dimensions) = Dimensions::new(rows, cols) else {
+		return Err(Error::bad_request_unknown("Block has no data matrix"));
+	};
+	let commitments = commitments::from_slice(&commitment).map_err(Error::internal_server_error)?;
+	let matrix = Matrix::try_from(header).map_err(Error::internal_server_error)?;
+	let block_hash = Encode::using_encoded(header, blake2_256).into();
+	Ok(BlockMatrix {
+		block_hash,
+		dimensions,
+		commitments,
+		matrix,
+	})
+}
+
+pub async fn block_cells(
+	block_number: u32,
+	query: CellsQuery,
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+	fetcher: Arc<impl cells::Fetch>,
+) -> Result<CellsResponse, Error> {
+	let header = available_header(block_number, &config, &state, &db)?;
+	let BlockMatrix {
+		block_hash,
+		dimensions,
+		commitments,
+		matrix,
+	} = matrix(&header)?;
+
+	let positions = query.positions.0;
+	if positions.len() > CELLS_MAX_LIMIT {
+		return Err(Error::bad_request_unknown("Too many positions requested"));
+	}
+
+	let is_outside =
+		|row: u32, col: u16| row >= dimensions.extended_rows() || col >= dimensions.cols().get();
+	if positions
+		.iter()
+		.any(|position| is_outside(position.row, position.col))
+	{
+		return Err(Error::bad_request_unknown(
+			"Position is outside of the matrix",
+		));
+	}
+
+	let mut cells = fetcher
+		.fetch_cells(
+			block_number,
+			block_hash,
+			dimensions,
+			&commitments,
+			&positions,
+		)
+		.await
+		.map_err(Error::internal_server_error)?;
+	cells.sort_by_key(|cell| (cell.position.row, cell.position.col));
+
+	Ok(CellsResponse {
+		block_number,
+		matrix,
+		cells: cells.into_iter().map(From::from).collect(),
+	})
+}
+
+/// Fetches verified cells of the given rows, and returns only rows with all cells verified
+async fn fetch_rows(
+	fetcher: &impl cells::Fetch,
+	block_number: u32,
+	block_matrix: &BlockMatrix,
+	rows: &[u32],
+) -> anyhow::Result<Vec<RowResponse>> {
+	let cols = block_matrix.dimensions.cols().get();
+	let positions = rows
+		.iter()
+		.flat_map(|&row| (0..cols).map(move |col| Position { row, col }))
+		.collect::<Vec<_>>();
+
+	let cells = fetcher
+		.fetch_cells(
+			block_number,
+			block_matrix.block_hash,
+			block_matrix.dimensions,
+			&block_matrix.commitments,
+			&positions,
+		)
+		.await?
+		.into_iter()
+		.map(|cell| ((cell.position.row, cell.position.col), cell))
+		.collect::<HashMap<_, _>>();
+
+	Ok(rows
+		.iter()
+		.filter_map(|&row| RowResponse::from_cells(row, cols, &cells))
+		.collect())
+}
+
+pub async fn block_rows(
+	block_number: u32,
+	query: RowsQuery,
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+	fetcher: Arc<impl cells::Fetch>,
+) -> Result<RowsResponse, Error> {
+	let header = available_header(block_number, &config, &state, &db)?;
+	let block_matrix = matrix(&header)?;
+
+	let rows = query.rows.0;
+	if rows.len() > ROWS_MAX_LIMIT {
+		return Err(Error::bad_request_unknown("Too many rows requested"));
+	}
+
+	if rows
+		.iter()
+		.any(|&row| row >= block_matrix.dimensions.extended_rows())
+	{
+		return Err(Error::bad_request_unknown("Row is outside of the matrix"));
+	}
+
+	let rows = fetch_rows(fetcher.as_ref(), block_number, &block_matrix, &rows)
+		.await
+		.map_err(Error::internal_server_error)?;
+
+	Ok(RowsResponse {
+		block_number,
+		matrix: block_matrix.matrix,
+		rows,
+	})
+}
+
 pub async fn block_data(
 	block_number: u32,
 	query: DataQuery,
@@ -146,13 +515,24 @@ pub async fn block_data(
 	state: Arc<Mutex<State>>,
 	db: impl Database,
 ) -> Result<DataResponse, Error> {
-	let state = state.lock().expect("Lock should be acquired");
-
 	let Some(app_id) = config.app_id else {
 		return Err(Error::not_found());
 	};
 
-	let Some(block_status) = block_status(&config.sync_start_block, &state, block_number) else {
+	let block_state = db
+		.get_block_state(block_number)
+		.map_err(Error::internal_server_error)?;
+
+	let block_status = {
+		let state = state.lock().expect("Lock should be acquired");
+		block_status(
+			&config.sync_start_block,
+			&state,
+			block_number,
+			block_state.as_ref(),
+		)
+	};
+	let Some(block_status) = block_status else {
 		return Err(Error::not_found());
 	};
 
@@ -160,15 +540,30 @@ pub async fn block_data(
 		return Err(Error::bad_request_unknown("Block data is not available"));
 	};
 
+	let fields = query
+		.fields
+		.as_ref()
+		.map(|FieldsQueryParameter(fields)| fields);
+	let data_transactions = data_transactions(&db, app_id, block_number, fields)?;
+
+	Ok(DataResponse {
+		block_number,
+		data_transactions,
+	})
+}
+
+fn data_transactions(
+	db: &impl Database,
+	app_id: u32,
+	block_number: u32,
+	fields: Option<&HashSet<DataField>>,
+) -> Result<Vec<DataTransaction>, Error> {
 	let data = db
 		.get_data(app_id, block_number)
 		.map_err(Error::internal_server_error)?;
 
 	let Some(data) = data else {
-		return Ok(DataResponse {
-			block_number,
-			data_transactions: vec![],
-		});
+		return Ok(vec![]);
 	};
 
 	let mut data_transactions: Vec<DataTransaction> = data
@@ -177,16 +572,237 @@ pub async fn block_data(
 		.collect::<anyhow::Result<_>>()
 		.map_err(Error::internal_server_error)?;
 
-	if let Some(FieldsQueryParameter(fields)) = &query.fields {
+	if let Some(fields) = fields {
 		filter_fields(&mut data_transactions, fields);
 	}
 
-	Ok(DataResponse {
+	Ok(data_transactions)
+}
+
+pub async fn blocks(
+	query: BlocksQuery,
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+) -> Result<BlocksResponse, Error> {
+	let latest = state.lock().expect("Lock should be acquired").latest;
+
+	let to = query.to.unwrap_or(latest);
+	if query.from > to {
+		return Err(Error::bad_request_unknown("Invalid block range"));
+	}
+
+	let from = query.cursor.unwrap_or(query.from);
+	if from < query.from || from > to.saturating_add(1) {
+		return Err(Error::bad_request_unknown("Cursor is out of range"));
+	}
+
+	let to = to.min(latest);
+	if from > to {
+		return Ok(BlocksResponse {
+			blocks: vec![],
+			next_cursor: None,
+		});
+	}
+
+	let limit = query
+		.limit
+		.unwrap_or(BLOCKS_DEFAULT_LIMIT)
+		.clamp(1, BLOCKS_MAX_LIMIT);
+	let last = from.saturating_add(limit - 1).min(to);
+
+	let fields = query
+		.fields
+		.map(|FieldsQueryParameter(fields)| fields)
+		.unwrap_or_default();
+
+	let data_fields = fields
+		.iter()
+		.filter_map(|field| match field {
+			BlockField::Header => None,
+			BlockField::Data => Some(DataField::Data),
+			BlockField::Extrinsic => Some(DataField::Extrinsic),
+		})
+		.collect::<HashSet<_>>();
+
+	let block_states = db
+		.get_block_states(from, last)
+		.map_err(Error::internal_server_error)?
+		.into_iter()
+		.collect::<HashMap<_, _>>();
+
+	// State lock is released before reading headers and data from the database
+	let statuses = {
+		let state = state.lock().expect("Lock should be acquired");
+		(from..=last)
+			.map_while(|block_number| {
+				let block_state = block_states.get(&block_number);
+				block_status(&config.sync_start_block, &state, block_number, block_state)
+			})
+			.collect::<Vec<_>>()
+	};
+
+	let mut headers = if fields.contains(&BlockField::Header) {
+		db.get_headers(from, last)
+			.map_err(Error::internal_server_error)?
+			.into_iter()
+			.collect::<HashMap<_, _>>()
+	} else {
+		HashMap::new()
+	};
+
+	let confidences = db
+		.get_confidences(from, last)
+		.map_err(Error::internal_server_error)?
+		.into_iter()
+		.collect::<HashMap<_, _>>();
+
+	let mut blocks = vec![];
+	for (block_number, status) in (from..=last).zip(statuses) {
+		let has_header = !matches!(
+			status,
+			BlockStatus::Unavailable | BlockStatus::Pending | BlockStatus::VerifyingHeader
+		);
+
+		let header = headers
+			.remove(&block_number)
+			.filter(|_| has_header)
+			.map(Header::try_from)
+			.transpose()
+			.map_err(Error::internal_server_error)?;
+
+		let data_transactions = match config.app_id {
+			Some(app_id) if !data_fields.is_empty() && status == BlockStatus::Finished => Some(
+				data_transactions(&db, app_id, block_number, Some(&data_fields))?,
+			),
+			_ => None,
+		};
+
+		blocks.push(BlockSummary {
+			block_number,
+			status,
+			confidence: confidences
+				.get(&block_number)
+				.copied()
+				.map(calculate_confidence),
+			header,
+			data_transactions,
+		});
+	}
+
+	Ok(BlocksResponse {
+		blocks,
+		next_cursor: (last < to).then_some(last + 1),
+	})
+}
+
+pub async fn block_proof(
+	block_number: u32,
+	query: ProofQuery,
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+	fetcher: Arc<impl cells::Fetch>,
+) -> Result<ProofResponse, Error> {
+	let header = available_header(block_number, &config, &state, &db)?;
+	let BlockMatrix {
+		block_hash,
+		dimensions,
+		commitments,
+		matrix,
+	} = matrix(&header)?;
+	let (_, _, data_root, _) = extract_kate(&header.extension);
+
+	let app_id = AppId(query.app_id);
+	let lookup = extract_app_lookup(&header.extension)
+		.map_err(|error| anyhow!("Invalid data lookup: {error}"))
+		.map_err(Error::internal_server_error)?;
+
+	let Some(range) = lookup.range_of(app_id) else {
+		return Err(Error::not_found());
+	};
+
+	let mut range = CellRange {
+		start: range.start,
+		end: range.end,
+	};
+
+	// Only cells of the requested transaction are fetched, located using the application data from the block
+	let mut data_proof = None;
+	if let Some(index) = query.index {
+		let data = fetcher
+			.fetch_app_data(block_hash, query.app_id)
+			.await
+			.map_err(Error::internal_server_error)?;
+		if data.encoded_size() > range.count() * config::DATA_CHUNK_SIZE {
+			let error = anyhow!("Application data doesn't fit into the application cells");
+			return Err(Error::internal_server_error(error));
+		}
+		let Some(transaction_range) =
+			CellRange::of_data_transaction(range.start, &data, index as usize)
+		else {
+			return Err(Error::not_found());
+		};
+
+		let proof = fetcher
+			.fetch_data_proof(block_hash, &data[index as usize])
+			.await
+			.map_err(Error::internal_server_error)?;
+		if proof.root != data_root {
+			let error = anyhow!("Data proof root doesn't match the data root");
+			return Err(Error::internal_server_error(error));
+		}
+		data_proof = Some(proof);
+		range = transaction_range;
+	}
+
+	if range.count() > CELLS_MAX_LIMIT {
+		return Err(Error::bad_request_unknown("Too many cells in the range"));
+	}
+
+	let positions = range_positions(dimensions, range.start, range.end);
+	let mut cells = fetcher
+		.fetch_cells(
+			block_number,
+			block_hash,
+			dimensions,
+			&commitments,
+			&positions,
+		)
+		.await
+		.map_err(Error::internal_server_error)?;
+
+	if cells.len() != positions.len() {
+		let error = anyhow!("Fetched {} of {} cells", cells.len(), positions.len());
+		return Err(Error::internal_server_error(error));
+	}
+	cells.sort_by_key(|cell| (cell.position.row, cell.position.col));
+
+	Ok(ProofResponse {
 		block_number,
-		data_transactions,
+		block_hash,
+		data_root,
+		app_id: query.app_id,
+		range,
+		matrix,
+		cells: cells.into_iter().map(From::from).collect(),
+		data_proof,
 	})
 }
 
+/// Positions of the data cells in the given range, in the extended matrix
+fn range_positions(dimensions: Dimensions, start: u32, end: u32) -> Vec<Position> {
+	dimensions
+		.iter_extended_data_positions()
+		.skip(start as usize)
+		.take(end.saturating_sub(start) as usize)
+		.map(|position| Position {
+			row: position.row,
+			col: position.col,
+		})
+		.collect()
+}
+
 pub async fn handle_rejection(error: Rejection) -> Result<impl Reply, Rejection> {
 	if error.find::<InternalServerError>().is_some() {
 		return Ok(StatusCode::INTERNAL_SERVER_ERROR.into_response());
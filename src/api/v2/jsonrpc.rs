@@ -0,0 +1,656 @@
+//! JSON-RPC 2.0 interface to the v2 API, over HTTP and web socket.
+//!
+//! Methods are served by the same handlers as the REST endpoints, so the results and the errors
+//! are the same. Subscriptions to the published topics are available over web socket only.
+
+use super::{
+	handlers, transactions,
+	types::{
+		DataField, DataQuery, Error, ErrorCode, Event, Events, FieldsQueryParameter,
+		PublishMessage, Sender, SubmitQuery, Topic, Transaction, Version, WsConfig,
+	},
+};
+use crate::{
+	api::auth::Permissions,
+	data::Database,
+	network::rpc::Node,
+	types::{ApiScope, RuntimeConfig, SlowClientPolicy, State},
+};
+use futures::{future, FutureExt, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use std::{
+	collections::{HashMap, HashSet},
+	sync::{
+		atomic::{AtomicU64, AtomicUsize, Ordering},
+		Arc, Mutex,
+	},
+};
+use tokio::{
+	sync::{broadcast, mpsc, mpsc::error::TrySendError, Notify, Semaphore},
+	task::JoinHandle,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error, warn};
+use warp::ws::{Message, WebSocket};
+
+const JSONRPC_VERSION: &str = "2.0";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+const NOT_FOUND: i64 = -32001;
+const FORBIDDEN: i64 = -32003;
+const SERVICE_UNAVAILABLE: i64 = -32005;
+
+/// Maximum number of requests in a batch
+const MAX_BATCH_SIZE: usize = 100;
+
+/// Maximum number of requests handled concurrently for a single web socket connection
+const MAX_CONCURRENT_REQUESTS: usize = 32;
+
+/// Method name of the notifications sent to the subscribers
+const SUBSCRIPTION_METHOD: &str = "subscription";
+
+/// Topics available for subscription
+const TOPICS: [Topic; 3] = [
+	Topic::HeaderVerified,
+	Topic::ConfidenceAchieved,
+	Topic::DataVerified,
+];
+
+#[derive(Deserialize)]
+struct Request {
+	jsonrpc: String,
+	method: String,
+	#[serde(default)]
+	params: Value,
+	/// Request without ID is a notification, which has no response
+	#[serde(default, deserialize_with = "present")]
+	id: Option<Value>,
+}
+
+/// Deserializes present value as `Some`, including `null`
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+	Value::deserialize(deserializer).map(Some)
+}
+
+#[derive(Serialize, Debug)]
+pub struct RpcError {
+	pub code: i64,
+	pub message: String,
+	/// Error code of the v2 API, if error is returned by the handler
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub data: Option<ErrorCode>,
+}
+
+impl RpcError {
+	fn new(code: i64, message: &str) -> Self {
+		RpcError {
+			code,
+			message: message.to_string(),
+			data: None,
+		}
+	}
+
+	fn invalid_params(error: impl std::fmt::Display) -> Self {
+		Self::new(INVALID_PARAMS, &format!("Invalid params: {error}"))
+	}
+}
+
+impl From<Error> for RpcError {
+	fn from(error: Error) -> Self {
+		if let Some(cause) = error.cause.as_ref() {
+			error!("{}: {cause:#}", error.message);
+		}
+		let code = match error.error_code {
+			ErrorCode::NotFound => NOT_FOUND,
+			ErrorCode::BadRequest => INVALID_PARAMS,
+			ErrorCode::Forbidden => FORBIDDEN,
+			ErrorCode::InternalServerError => INTERNAL_ERROR,
+			ErrorCode::ServiceUnavailable => SERVICE_UNAVAILABLE,
+		};
+		RpcError {
+			code,
+			message: error.message,
+			data: Some(error.error_code),
+		}
+	}
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+	Result(Value),
+	Error(RpcError),
+}
+
+#[derive(Serialize)]
+struct Response {
+	jsonrpc: &'static str,
+	#[serde(flatten)]
+	outcome: Outcome,
+	id: Value,
+}
+
+impl Response {
+	fn new(id: Value, result: Result<Value, RpcError>) -> Self {
+		let outcome = match result {
+			Ok(value) => Outcome::Result(value),
+			Err(error) => Outcome::Error(error),
+		};
+		Response {
+			jsonrpc: JSONRPC_VERSION,
+			outcome,
+			id,
+		}
+	}
+
+	fn error(code: i64, message: &str) -> Self {
+		Self::new(Value::Null, Err(RpcError::new(code, message)))
+	}
+}
+
+#[derive(Deserialize)]
+struct BlockParams {
+	block_number: u32,
+}
+
+#[derive(Deserialize)]
+struct DataParams {
+	block_number: u32,
+	fields: Option<HashSet<DataField>>,
+}
+
+#[derive(Deserialize)]
+struct SubmitParams {
+	transaction: Transaction,
+	#[serde(flatten)]
+	query: SubmitQuery,
+}
+
+#[derive(Deserialize)]
+struct SubscribeParams {
+	topics: HashSet<Topic>,
+	#[serde(default)]
+	data_fields: HashSet<DataField>,
+}
+
+#[derive(Deserialize)]
+struct UnsubscribeParams {
+	subscription: u64,
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+	serde_json::from_value(params).map_err(RpcError::invalid_params)
+}
+
+fn to_result(value: impl Serialize) -> Result<Value, RpcError> {
+	serde_json::to_value(value).map_err(|error| {
+		RpcError::new(INTERNAL_ERROR, &format!("Cannot serialize result: {error}"))
+	})
+}
+
+fn authorize(permissions: &Permissions, scope: ApiScope) -> Result<(), RpcError> {
+	if !permissions.has_scope(scope) {
+		return Err(Error::forbidden_unknown("Method is not allowed.").into());
+	}
+	Ok(())
+}
+
+/// Active subscriptions of the web socket connection.
+/// Number of subscriptions is limited across all connections.
+struct Subscriptions {
+	next_id: AtomicU64,
+	/// Subscription tasks, or `None` once the connection is closed
+	tasks: Mutex<Option<HashMap<u64, JoinHandle<()>>>>,
+	count: Arc<AtomicUsize>,
+	max_count: usize,
+}
+
+impl Subscriptions {
+	fn new(count: Arc<AtomicUsize>, max_count: usize) -> Self {
+		Subscriptions {
+			next_id: AtomicU64::default(),
+			tasks: Mutex::new(Some(HashMap::new())),
+			count,
+			max_count,
+		}
+	}
+
+	/// Returns `None` if maximum number of subscriptions is reached, or if the connection is closed
+	fn insert(&self, task: impl FnOnce(u64) -> JoinHandle<()>) -> Option<u64> {
+		let mut tasks = self.tasks.lock().expect("Lock should be acquired");
+		let tasks = tasks.as_mut()?;
+		let is_reserved = self
+			.count
+			.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+				(count < self.max_count).then_some(count + 1)
+			})
+			.is_ok();
+		if !is_reserved {
+			return None;
+		}
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+		tasks.insert(id, task(id));
+		Some(id)
+	}
+
+	fn remove(&self, id: u64) -> bool {
+		let mut tasks = self.tasks.lock().expect("Lock should be acquired");
+		let Some(task) = tasks.as_mut().and_then(|tasks| tasks.remove(&id)) else {
+			return false;
+		};
+		task.abort();
+		self.count.fetch_sub(1, Ordering::SeqCst);
+		true
+	}
+
+	/// Removes all subscriptions, and refuses new ones, since the connection is closed
+	fn close(&self) {
+		let mut tasks = self.tasks.lock().expect("Lock should be acquired");
+		for (_, task) in tasks.take().into_iter().flatten() {
+			task.abort();
+			self.count.fetch_sub(1, Ordering::SeqCst);
+		}
+	}
+}
+
+/// Web socket connection state, used by the subscription methods
+struct Connection {
+	sender: Sender,
+	subscriptions: Subscriptions,
+	/// Signals the connection to close
+	disconnect: Arc<Notify>,
+	slow_client_policy: SlowClientPolicy,
+}
+
+pub struct Server<T, D> {
+	pub version: Version,
+	pub config: RuntimeConfig,
+	pub node: Node,
+	pub submitter: Option<Arc<T>>,
+	pub state: Arc<Mutex<State>>,
+	pub db: D,
+	pub events: Events,
+	/// Number of subscriptions across all web socket connections
+	pub subscriptions: Arc<AtomicUsize>,
+}
+
+// Derived implementation would require the submitter to be `Clone`
+impl<T, D: Clone> Clone for Server<T, D> {
+	fn clone(&self) -> Self {
+		Server {
+			version: self.version.clone(),
+			config: self.config.clone(),
+			node: self.node.clone(),
+			submitter: self.submitter.clone(),
+			state: self.state.clone(),
+			db: self.db.clone(),
+			events: self.events.clone(),
+			subscriptions: self.subscriptions.clone(),
+		}
+	}
+}
+
+impl<T, D> Server<T, D>
+where
+	T: transactions::Submit + Send + Sync + 'static,
+	D: Database,
+{
+	/// Handles single request or a batch of requests.
+	/// Returns `None` if there is no response, when only notifications are received.
+	pub async fn handle(&self, message: &[u8], permissions: &Permissions) -> Option<Value> {
+		self.handle_message(message, permissions, None).await
+	}
+
+	async fn handle_message(
+		&self,
+		message: &[u8],
+		permissions: &Permissions,
+		connection: Option<&Connection>,
+	) -> Option<Value> {
+		let Ok(message) = serde_json::from_slice::<Value>(message) else {
+			return to_response(Response::error(PARSE_ERROR, "Parse error"));
+		};
+
+		match message {
+			Value::Array(requests) if requests.is_empty() => {
+				to_response(Response::error(INVALID_REQUEST, "Invalid Request"))
+			},
+			Value::Array(requests) if requests.len() > MAX_BATCH_SIZE => {
+				to_response(Response::error(INVALID_REQUEST, "Batch is too large"))
+			},
+			Value::Array(requests) => {
+				let responses = future::join_all(
+					requests
+						.into_iter()
+						.map(|request| self.handle_request(request, permissions, connection)),
+				)
+				.await
+				.into_iter()
+				.flatten()
+				.collect::<Vec<_>>();
+				(!responses.is_empty()).then(|| json!(responses))
+			},
+			request => self
+				.handle_request(request, permissions, connection)
+				.await
+				.and_then(to_response),
+		}
+	}
+
+	async fn handle_request(
+		&self,
+		request: Value,
+		permissions: &Permissions,
+		connection: Option<&Connection>,
+	) -> Option<Response> {
+		let Ok(request) = serde_json::from_value::<Request>(request) else {
+			return Some(Response::error(INVALID_REQUEST, "Invalid Request"));
+		};
+
+		if request.jsonrpc != JSONRPC_VERSION {
+			let error = RpcError::new(INVALID_REQUEST, "Invalid Request");
+			return Some(Response::new(request.id.unwrap_or_default(), Err(error)));
+		}
+
+		let result = self
+			.call(&request.method, request.params, permissions, connection)
+			.await;
+		request.id.map(|id| Response::new(id, result))
+	}
+
+	async fn call(
+		&self,
+		method: &str,
+		params: Value,
+		permissions: &Permissions,
+		connection: Option<&Connection>,
+	) -> Result<Value, RpcError> {
+		let (config, state, db) = (self.config.clone(), self.state.clone(), self.db.clone());
+		match method {
+			"version" => {
+				authorize(permissions, ApiScope::Read)?;
+				to_result(&self.version)
+			},
+			"status" => {
+				authorize(permissions, ApiScope::Read)?;
+				to_result(handlers::status(config, self.node.clone(), state))
+			},
+			"block" => {
+				authorize(permissions, ApiScope::Read)?;
+				let BlockParams { block_number } = parse_params(params)?;
+				to_result(handlers::block(block_number, config, state, db).await?)
+			},
+			"header" => {
+				authorize(permissions, ApiScope::Read)?;
+				let BlockParams { block_number } = parse_params(params)?;
+				to_result(handlers::block_header(block_number, config, state, db).await?)
+			},
+			"data" => {
+				authorize(permissions, ApiScope::Read)?;
+				let DataParams {
+					block_number,
+					fields,
+				} = parse_params(params)?;
+				let query = DataQuery {
+					fields: fields.map(FieldsQueryParameter),
+				};
+				to_result(handlers::block_data(block_number, query, config, state, db).await?)
+			},
+			"submit" => {
+				authorize(permissions, ApiScope::Submit)?;
+				let Some(submitter) = self.submitter.clone() else {
+					return Err(Error::not_found().into());
+				};
+				let SubmitParams { transaction, query } = parse_params(params)?;
+				let permissions = permissions.clone();
+				if query.is_async {
+					let submission =
+						handlers::submit_async(submitter, permissions, query, transaction).await?;
+					return to_result(submission);
+				}
+				to_result(
+					handlers::submit_and_wait(submitter, permissions, query, transaction).await?,
+				)
+			},
+			"subscribe" => {
+				authorize(permissions, ApiScope::Subscribe)?;
+				let Some(connection) = connection else {
+					return Err(subscriptions_not_available());
+				};
+				let SubscribeParams {
+					topics,
+					data_fields,
+				} = parse_params(params)?;
+				if topics.is_empty() || !topics.iter().all(|topic| TOPICS.contains(topic)) {
+					return Err(RpcError::invalid_params("Topic is not supported"));
+				}
+				let Some((_, receiver)) = self.events.subscribe(None).await else {
+					return Err(RpcError::new(INTERNAL_ERROR, "Cannot subscribe to events"));
+				};
+				let subscriber = Subscriber {
+					sender: connection.sender.clone(),
+					disconnect: connection.disconnect.clone(),
+					slow_client_policy: connection.slow_client_policy,
+				};
+				let id = connection.subscriptions.insert(|id| {
+					tokio::spawn(notify(id, receiver, topics, data_fields, subscriber))
+				});
+				let Some(id) = id else {
+					let message = "Maximum number of subscriptions is reached";
+					return Err(Error::service_unavailable(message).into());
+				};
+				to_result(id)
+			},
+			"unsubscribe" => {
+				authorize(permissions, ApiScope::Subscribe)?;
+				let Some(connection) = connection else {
+					return Err(subscriptions_not_available());
+				};
+				let UnsubscribeParams { subscription } = parse_params(params)?;
+				to_result(connection.subscriptions.remove(subscription))
+			},
+			_ => Err(RpcError::new(METHOD_NOT_FOUND, "Method not found")),
+		}
+	}
+}
+
+fn subscriptions_not_available() -> RpcError {
+	RpcError::new(
+		METHOD_NOT_FOUND,
+		"Subscriptions are available only over web socket",
+	)
+}
+
+fn to_response(response: Response) -> Option<Value> {
+	serde_json::to_value(response)
+		.map_err(|error| error!("Cannot serialize response: {error}"))
+		.ok()
+}
+
+async fn send(sender: &Sender, message: &Value) -> anyhow::Result<()> {
+	sender
+		.send(Ok(Message::text(message.to_string())))
+		.await
+		.map_err(|_| anyhow::anyhow!("Web socket connection is closed"))
+}
+
+/// Connection of the subscription, with the slow client policy
+struct Subscriber {
+	sender: Sender,
+	disconnect: Arc<Notify>,
+	slow_client_policy: SlowClientPolicy,
+}
+
+/// Sends published messages on the subscribed topics as notifications, until the connection is closed.
+/// Connection is closed if the subscriber lags behind the published messages,
+/// or if its queue is full and the slow client policy is to disconnect.
+async fn notify(
+	id: u64,
+	mut receiver: broadcast::Receiver<Arc<Event>>,
+	topics: HashSet<Topic>,
+	data_fields: HashSet<DataField>,
+	subscriber: Subscriber,
+) {
+	loop {
+		let event = match receiver.recv().await {
+			Ok(event) => event,
+			Err(broadcast::error::RecvError::Lagged(skipped)) => {
+				warn!(
+					subscription = id,
+					skipped, "Subscriber lagged behind, closing the connection"
+				);
+				subscriber.disconnect.notify_one();
+				return;
+			},
+			Err(broadcast::error::RecvError::Closed) => return,
+		};
+		if !topics.contains(&event.topic) {
+			continue;
+		}
+
+		let mut message: PublishMessage = event.message.clone();
+		message.apply_filter(&data_fields);
+
+		let notification = json!({
+			"jsonrpc": JSONRPC_VERSION,
+			"method": SUBSCRIPTION_METHOD,
+			"params": { "subscription": id, "result": message },
+		});
+		match subscriber
+			.sender
+			.try_send(Ok(Message::text(notification.to_string())))
+		{
+			Ok(()) => (),
+			Err(TrySendError::Full(_))
+				if subscriber.slow_client_policy == SlowClientPolicy::Disconnect =>
+			{
+				warn!(
+					subscription = id,
+					"Client queue is full, closing the connection"
+				);
+				subscriber.disconnect.notify_one();
+				return;
+			},
+			Err(TrySendError::Full(_)) => {
+				warn!(
+					subscription = id,
+					"Client queue is full, message is dropped"
+				);
+			},
+			Err(TrySendError::Closed(_)) => return,
+		}
+	}
+}
+
+/// Serves JSON-RPC requests received over web socket, until the connection is closed
+pub async fn connect<T, D>(web_socket: WebSocket, permissions: Permissions, server: Server<T, D>)
+where
+	T: transactions::Submit + Send + Sync + 'static,
+	D: Database,
+{
+	let ws_config = WsConfig::from(&server.config);
+	let (web_socket_sender, mut web_socket_receiver) = web_socket.split();
+	let (sender, receiver) = mpsc::channel(ws_config.queue_size);
+
+	tokio::task::spawn(
+		ReceiverStream::new(receiver)
+			.forward(web_socket_sender)
+			.map(|result| {
+				if let Err(error) = result {
+					error!("Error sending web socket message: {error}");
+				}
+			}),
+	);
+
+	let connection = Arc::new(Connection {
+		sender,
+		subscriptions: Subscriptions::new(
+			server.subscriptions.clone(),
+			ws_config.max_subscriptions,
+		),
+		disconnect: Arc::new(Notify::new()),
+		slow_client_policy: ws_config.slow_client_policy,
+	});
+	let permissions = Arc::new(permissions);
+	let requests = Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS));
+
+	loop {
+		let result = tokio::select! {
+			_ = connection.disconnect.notified() => break,
+			result = web_socket_receiver.next() => match result {
+				Some(result) => result,
+				None => break,
+			},
+		};
+
+		let message = match result {
+			Err(error) => {
+				error!("Error receiving client message: {error}");
+				continue;
+			},
+			Ok(message) if !message.is_text() => continue,
+			Ok(message) => message,
+		};
+
+		// Requests are handled concurrently, since submit waits for the finalization,
+		// and messages are not received while the maximum number of requests is handled
+		let Ok(permit) = requests.clone().acquire_owned().await else {
+			break;
+		};
+		let (server, permissions, connection) =
+			(server.clone(), permissions.clone(), connection.clone());
+		tokio::spawn(async move {
+			let response = server
+				.handle_message(message.as_bytes(), &permissions, Some(&connection))
+				.await;
+			drop(permit);
+			let Some(response) = response else {
+				return;
+			};
+			if let Err(error) = send(&connection.sender, &response).await {
+				warn!("Error sending response: {error:#}");
+			}
+		});
+	}
+
+	debug!("Closing JSON-RPC web socket connection");
+	connection.subscriptions.close();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Subscriptions;
+	use std::sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	};
+
+	#[tokio::test]
+	async fn subscriptions_limit() {
+		let count = Arc::new(AtomicUsize::default());
+		let subscriptions = Subscriptions::new(count.clone(), 1);
+		let id = subscriptions.insert(|_| tokio::spawn(async {})).unwrap();
+		assert!(subscriptions.insert(|_| tokio::spawn(async {})).is_none());
+		assert!(subscriptions.remove(id));
+		assert!(!subscriptions.remove(id));
+		assert_eq!(count.load(Ordering::SeqCst), 0);
+	}
+
+	#[tokio::test]
+	async fn subscriptions_refused_after_close() {
+		let count = Arc::new(AtomicUsize::default());
+		let subscriptions = Subscriptions::new(count.clone(), 10);
+		subscriptions.insert(|_| tokio::spawn(async {})).unwrap();
+		assert_eq!(count.load(Ordering::SeqCst), 1);
+
+		subscriptions.close();
+		assert_eq!(count.load(Ordering::SeqCst), 0);
+		assert!(subscriptions.insert(|_| tokio::spawn(async {})).is_none());
+		assert_eq!(count.load(Ordering::SeqCst), 0);
+	}
+}
@@ -1,13 +1,25 @@
 use self::{
 	handlers::{handle_rejection, log_internal_server_error},
-	types::{DataQuery, PublishMessage, Version, WsClients},
+	types::{
+		ApplicationKeyQuery, BlocksQuery, CellsQuery, DataQuery, EventId, Events, EventsQuery,
+		ProofQuery, PublishMessage, RowsQuery, SubmitQuery, Version, WsClients,
+	},
 };
 use crate::{
-	api::v2::types::Topic,
-	data::{Database, RocksDB},
-	network::rpc::{Client, Node},
-	types::{RuntimeConfig, State},
+	api::{
+		auth::{self, Auth},
+		v2::types::Topic,
+	},
+	data::Database,
+	keystore::Keystore,
+	network::{
+		p2p,
+		rpc::{Client, Node},
+	},
+	types::{RuntimeConfig, State, Submission},
 };
+use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
+use sp_core::H256;
 use std::{
 	convert::Infallible,
 	fmt::Display,
@@ -17,7 +29,13 @@ use tokio::sync::broadcast;
 use tracing::{debug, error, info};
 use warp::{Filter, Rejection, Reply};
 
+mod cells;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 mod handlers;
+mod jsonrpc;
+mod openapi;
+mod sse;
 mod transactions;
 pub mod types;
 mod ws;
@@ -43,6 +61,15 @@ fn version_route(
 		.map(move || version.clone())
 }
 
+fn openapi_route(
+	version: Version,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	let document = openapi::document(&version.version);
+	warp::path!("v2" / "openapi.json")
+		.and(warp::get())
+		.map(move || warp::reply::json(&document))
+}
+
 fn status_route(
 	config: RuntimeConfig,
 	node: Node,
@@ -56,6 +83,21 @@ fn status_route(
 		.map(handlers::status)
 }
 
+fn blocks_route(
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "blocks")
+		.and(warp::get())
+		.and(warp::query::<BlocksQuery>())
+		.and(warp::any().map(move || config.clone()))
+		.and(warp::any().map(move || state.clone()))
+		.and(warp::any().map(move || db.clone()))
+		.then(handlers::blocks)
+		.map(log_internal_server_error)
+}
+
 fn block_route(
 	config: RuntimeConfig,
 	state: Arc<Mutex<State>>,
@@ -99,25 +141,138 @@ fn block_data_route(
 		.map(log_internal_server_error)
 }
 
+fn block_cells_route(
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+	fetcher: Arc<impl cells::Fetch + Send + Sync>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "blocks" / u32 / "cells")
+		.and(warp::get())
+		.and(warp::query::<CellsQuery>())
+		.and(warp::any().map(move || config.clone()))
+		.and(warp::any().map(move || state.clone()))
+		.and(warp::any().map(move || db.clone()))
+		.and(warp::any().map(move || fetcher.clone()))
+		.then(handlers::block_cells)
+		.map(log_internal_server_error)
+}
+
+fn block_rows_route(
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+	fetcher: Arc<impl cells::Fetch + Send + Sync>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "blocks" / u32 / "rows")
+		.and(warp::get())
+		.and(warp::query::<RowsQuery>())
+		.and(warp::any().map(move || config.clone()))
+		.and(warp::any().map(move || state.clone()))
+		.and(warp::any().map(move || db.clone()))
+		.and(warp::any().map(move || fetcher.clone()))
+		.then(handlers::block_rows)
+		.map(log_internal_server_error)
+}
+
+fn block_proof_route(
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+	fetcher: Arc<impl cells::Fetch + Send + Sync>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "blocks" / u32 / "proof")
+		.and(warp::get())
+		.and(warp::query::<ProofQuery>())
+		.and(warp::any().map(move || config.clone()))
+		.and(warp::any().map(move || state.clone()))
+		.and(warp::any().map(move || db.clone()))
+		.and(warp::any().map(move || fetcher.clone()))
+		.then(handlers::block_proof)
+		.map(log_internal_server_error)
+}
+
 fn submit_route(
 	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync>>,
+	auth: Auth,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
 	warp::path!("v2" / "submit")
 		.and(warp::post())
 		.and_then(move || optionally(submitter.clone()))
+		.and(auth::permissions(auth))
+		.and(warp::query::<SubmitQuery>())
 		.and(warp::body::json())
 		.then(handlers::submit)
 		.map(log_internal_server_error)
 }
 
+fn estimate_route(
+	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync>>,
+	auth: Auth,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "submit" / "estimate")
+		.and(warp::post())
+		.and_then(move || optionally(submitter.clone()))
+		.and(auth::permissions(auth))
+		.and(warp::query::<SubmitQuery>())
+		.and(warp::body::json())
+		.then(handlers::estimate)
+		.map(log_internal_server_error)
+}
+
+fn application_key_route(
+	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync>>,
+	auth: Auth,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "applications")
+		.and(warp::post())
+		.and_then(move || optionally(submitter.clone()))
+		.and(auth::permissions(auth))
+		.and(warp::query::<ApplicationKeyQuery>())
+		.and(warp::body::json())
+		.then(handlers::create_application_key)
+		.map(log_internal_server_error)
+}
+
+fn submission_route(
+	db: impl Database,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "submissions" / H256)
+		.and(warp::get())
+		.and(warp::any().map(move || db.clone()))
+		.then(handlers::submission)
+		.map(log_internal_server_error)
+}
+
 fn subscriptions_route(
 	clients: WsClients,
+	state: Arc<Mutex<State>>,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
 	warp::path!("v2" / "subscriptions")
 		.and(warp::post())
 		.and(warp::body::json())
 		.and(with_ws_clients(clients))
-		.and_then(handlers::subscriptions)
+		.and(warp::any().map(move || state.clone()))
+		.then(handlers::subscriptions)
+}
+
+fn unsubscribe_route(
+	clients: WsClients,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "subscriptions" / String)
+		.and(warp::delete())
+		.and(with_ws_clients(clients))
+		.then(handlers::unsubscribe)
+}
+
+fn events_route(events: Events) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "events")
+		.and(warp::get())
+		.and(warp::query::<EventsQuery>())
+		.and(warp::header::optional::<EventId>("last-event-id"))
+		.and(warp::any().map(move || events.clone()))
+		.then(handlers::events)
+		.map(log_internal_server_error)
 }
 
 fn ws_route(
@@ -127,22 +282,56 @@ fn ws_route(
 	node: Node,
 	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync + 'static>>,
 	state: Arc<Mutex<State>>,
+	db: impl Database,
+	auth: Auth,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
 	warp::path!("v2" / "ws" / String)
 		.and(warp::ws())
+		.and(auth::permissions(auth))
 		.and(with_ws_clients(clients))
 		.and(warp::any().map(move || version.clone()))
 		.and(warp::any().map(move || config.clone()))
 		.and(warp::any().map(move || node.clone()))
 		.and(warp::any().map(move || submitter.clone()))
 		.and(warp::any().map(move || state.clone()))
+		.and(warp::any().map(move || db.clone()))
 		.and_then(handlers::ws)
 }
 
+fn jsonrpc_route(
+	server: jsonrpc::Server<
+		impl transactions::Submit + Clone + Send + Sync + 'static,
+		impl Database,
+	>,
+	auth: Auth,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "rpc")
+		.and(warp::post())
+		.and(auth::permissions(auth))
+		.and(warp::body::bytes())
+		.and(warp::any().map(move || server.clone()))
+		.then(handlers::jsonrpc)
+}
+
+fn jsonrpc_ws_route(
+	server: jsonrpc::Server<
+		impl transactions::Submit + Clone + Send + Sync + 'static,
+		impl Database,
+	>,
+	auth: Auth,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "rpc" / "ws")
+		.and(warp::ws())
+		.and(auth::permissions(auth))
+		.and(warp::any().map(move || server.clone()))
+		.then(handlers::jsonrpc_ws)
+}
+
 pub async fn publish<T: Clone + TryInto<PublishMessage>>(
 	topic: Topic,
 	mut receiver: broadcast::Receiver<T>,
 	clients: WsClients,
+	events: Events,
 ) where
 	<T as TryInto<PublishMessage>>::Error: Display,
 {
@@ -163,6 +352,8 @@ pub async fn publish<T: Clone + TryInto<PublishMessage>>(
 			},
 		};
 
+		events.publish(topic, message.clone()).await;
+
 		match clients.publish(&topic, message).await {
 			Ok(results) => {
 				let published = results.iter().filter(|&result| result.is_ok()).count();
@@ -185,27 +376,97 @@ pub fn routes(
 	state: Arc<Mutex<State>>,
 	config: RuntimeConfig,
 	node_client: Client,
+	p2p_client: p2p::Client,
 	ws_clients: WsClients,
-	db: RocksDB,
+	events: Events,
+	submission_sender: broadcast::Sender<Submission>,
+	keystore: Keystore,
+	pp: Arc<PublicParameters>,
+	db: impl Database,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
 	let version = Version {
 		version,
 		network_version,
 	};
 
-	let app_id = config.app_id.as_ref();
-	let pair_signer = config.avail_secret_key.clone().map(From::from);
-
-	let submitter = app_id.map(|&app_id| {
-		Arc::new(transactions::Submitter {
-			node_client,
-			app_id,
-			pair_signer,
-		})
+	let fetcher = Arc::new(cells::Fetcher {
+		p2p_client,
+		rpc_client: node_client.clone(),
+		disable_rpc: config.disable_rpc,
+		max_cells_per_rpc: config.max_cells_per_rpc.unwrap_or(30),
+		pp,
 	});
 
+	let submitter = Some(Arc::new(transactions::Submitter::new(
+		node_client,
+		&keystore,
+		db.clone(),
+		submission_sender,
+		&config,
+	)));
+
+	filters(
+		version, node, state, config, fetcher, submitter, ws_clients, events, db,
+	)
+}
+
+/// Routes served by [`filters`] as `(method, path)`, with the path parameters in `{name}` format.
+/// Tests check that each route is served and matches an operation in the specification,
+/// so a route added to [`filters`] has to be listed here as well.
+#[cfg(test)]
+const ROUTES: &[(&str, &str)] = &[
+	("get", "/v2/version"),
+	("get", "/v2/openapi.json"),
+	("get", "/v2/status"),
+	("get", "/v2/blocks"),
+	("get", "/v2/blocks/{block_number}"),
+	("get", "/v2/blocks/{block_number}/header"),
+	("get", "/v2/blocks/{block_number}/data"),
+	("get", "/v2/blocks/{block_number}/cells"),
+	("get", "/v2/blocks/{block_number}/rows"),
+	("get", "/v2/blocks/{block_number}/proof"),
+	("post", "/v2/subscriptions"),
+	("delete", "/v2/subscriptions/{subscription_id}"),
+	("get", "/v2/events"),
+	("post", "/v2/submit"),
+	("post", "/v2/submit/estimate"),
+	("post", "/v2/applications"),
+	("get", "/v2/submissions/{hash}"),
+	("post", "/v2/rpc"),
+	("get", "/v2/rpc/ws"),
+	("get", "/v2/ws/{subscription_id}"),
+];
+
+/// Combines the API routes, served with the given cells fetcher and transactions submitter
+#[allow(clippy::too_many_arguments)]
+fn filters(
+	version: Version,
+	node: Node,
+	state: Arc<Mutex<State>>,
+	config: RuntimeConfig,
+	fetcher: Arc<impl cells::Fetch + Send + Sync + 'static>,
+	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync + 'static>>,
+	ws_clients: WsClients,
+	events: Events,
+	db: impl Database,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	let auth = Auth::from(&config);
+
+	let jsonrpc_server = jsonrpc::Server {
+		version: version.clone(),
+		config: config.clone(),
+		node: node.clone(),
+		submitter: submitter.clone(),
+		state: state.clone(),
+		db: db.clone(),
+		events: events.clone(),
+		subscriptions: Default::default(),
+	};
+
 	version_route(version.clone())
+		.or(openapi_route(version.clone()))
 		.or(status_route(config.clone(), node.clone(), state.clone()))
+		.or(blocks_route(config.clone(), state.clone(), db.clone()))
 		.or(block_route(config.clone(), state.clone(), db.clone()))
 		.or(block_header_route(
 			config.clone(),
@@ -213,25 +474,65 @@ pub fn routes(
 			db.clone(),
 		))
 		.or(block_data_route(config.clone(), state.clone(), db.clone()))
-		.or(subscriptions_route(ws_clients.clone()))
-		.or(submit_route(submitter.clone()))
+		.or(block_cells_route(
+			config.clone(),
+			state.clone(),
+			db.clone(),
+			fetcher.clone(),
+		))
+		.or(block_rows_route(
+			config.clone(),
+			state.clone(),
+			db.clone(),
+			fetcher.clone(),
+		))
+		.or(block_proof_route(
+			config.clone(),
+			state.clone(),
+			db.clone(),
+			fetcher,
+		))
+		.or(subscriptions_route(ws_clients.clone(), state.clone()))
+		.or(unsubscribe_route(ws_clients.clone()))
+		.or(events_route(events))
+		.or(submit_route(submitter.clone(), auth.clone()))
+		.or(estimate_route(submitter.clone(), auth.clone()))
+		.or(application_key_route(submitter.clone(), auth.clone()))
+		.or(submission_route(db.clone()))
+		.or(jsonrpc_route(jsonrpc_server.clone(), auth.clone()))
+		.or(jsonrpc_ws_route(jsonrpc_server, auth.clone()))
 		.or(ws_route(
-			ws_clients, version, config, node, submitter, state,
+			ws_clients,
+			version,
+			config.clone(),
+			node,
+			submitter,
+			state,
+			db,
+			auth,
 		))
 		.recover(handle_rejection)
 }
 
 #[cfg(test)]
 mod tests {
-	use super::{transactions, types::Transaction};
+	use super::{cells, jsonrpc, openapi, transactions, types::Transaction};
 	use crate::{
-		api::v2::types::{
-			DataField, ErrorCode, SubmitResponse, Subscription, SubscriptionId, Topic, Version,
-			WsClients, WsError, WsResponse,
+		api::{
+			auth::{self, Auth},
+			v2::types::{
+				ApplicationKeyResponse, ConfidenceMessage, DataField, ErrorCode, EstimateResponse,
+				Events, PublishMessage, SubmitResponse, Subscription, SubscriptionId, Topic,
+				Version, WsClients, WsError, WsResponse,
+			},
 		},
-		data::Database,
+		data::{Database, MemoryDB},
 		network::rpc::Node,
-		types::{BlockRange, OptionBlockRange, RuntimeConfig, State},
+		types::{
+			ApiScope, ApiSecret, ApiToken, BlockRange, BlockState, DataProof, OptionBlockRange,
+			RuntimeConfig, State, Submission, SubmissionStatus, Weight,
+		},
+		utils::test_utils::header,
 	};
 	use async_trait::async_trait;
 	use avail_subxt::{
@@ -243,16 +544,22 @@ mod tests {
 		primitives::Header as DaHeader,
 	};
 	use hyper::StatusCode;
-	use kate_recovery::{com::AppData, matrix::Partition};
+	use kate_recovery::{
+		config,
+		data::Cell,
+		matrix::{Dimensions, Partition, Position},
+	};
+	use serde_json::{json, Value};
 	use sp_core::H256;
 	use std::{
-		collections::HashSet,
+		collections::{BTreeSet, HashSet},
 		str::FromStr,
 		sync::{Arc, Mutex},
+		time::Duration,
 	};
-	use subxt::config::substrate::Digest;
 	use test_case::test_case;
 	use uuid::Uuid;
+	use warp::{reject::MethodNotAllowed, Filter, Rejection, Reply};
 
 	fn v1() -> Version {
 		Version {
@@ -356,7 +663,7 @@ mod tests {
 			let mut state = state.lock().unwrap();
 			state.latest = latest;
 		}
-		let route = super::block_route(config, state, MockDatabase::default());
+		let route = super::block_route(config, state, MemoryDB::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path(&format!("/v2/blocks/{block_number}"))
@@ -376,14 +683,9 @@ mod tests {
 			state.header_verified.set(10);
 			state.data_verified.set(10);
 		}
-		let route = super::block_route(
-			config,
-			state,
-			MockDatabase {
-				confidence: Some(4),
-				..Default::default()
-			},
-		);
+		let db = MemoryDB::default();
+		db.store_confidence(10, 4).unwrap();
+		let route = super::block_route(config, state, db);
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/blocks/10")
@@ -397,6 +699,52 @@ mod tests {
 		);
 	}
 
+	#[tokio::test]
+	async fn block_route_stored_state() {
+		let config = RuntimeConfig::default();
+		let state = Arc::new(Mutex::new(State {
+			latest: 10,
+			..Default::default()
+		}));
+		let db = MemoryDB::default();
+		db.store_confidence(5, 4).unwrap();
+		let block_state = BlockState {
+			header_verified: true,
+			finality_verified: true,
+			confidence_achieved: true,
+			data_verified: true,
+			failure: None,
+		};
+		db.store_block_state(5, &block_state).unwrap();
+		db.update_block_state(6, |block_state| block_state.header_verified = true)
+			.unwrap();
+		db.store_block_failure(6, "Failed to fetch 2 cells".to_string())
+			.unwrap();
+		let route = super::block_route(config, state, db);
+
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/5")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response.body(),
+			r#"{"status":"finished","confidence":93.75}"#
+		);
+
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/6")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response.body(),
+			r#"{"status":"verifying-confidence","confidence":null,"failure":"Failed to fetch 2 cells"}"#
+		);
+	}
+
 	#[test_case(0, r#"Block header is not available"#  ; "Block is unavailable")]
 	#[test_case(6, r#"Block header is not available"#  ; "Block is pending")]
 	#[test_case(10, r#"Block header is not available"#  ; "Block is in verifying-header state")]
@@ -413,7 +761,7 @@ mod tests {
 			..Default::default()
 		}));
 
-		let route = super::block_header_route(config, state, MockDatabase::default());
+		let route = super::block_header_route(config, state, MemoryDB::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path(&format!("/v2/blocks/{block_number}/header"))
@@ -431,7 +779,7 @@ mod tests {
 			..Default::default()
 		}));
 
-		let route = super::block_header_route(config, state, MockDatabase::default());
+		let route = super::block_header_route(config, state, MemoryDB::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/blocks/11/header")
@@ -440,23 +788,6 @@ mod tests {
 		assert_eq!(response.status(), StatusCode::NOT_FOUND);
 	}
 
-	fn header() -> DaHeader {
-		DaHeader {
-			parent_hash: H256::default(),
-			number: 1,
-			state_root: H256::default(),
-			extrinsics_root: H256::default(),
-			extension: HeaderExtension::V2(v2::HeaderExtension {
-				commitment: KateCommitment::default(),
-				app_lookup: CompactDataLookup {
-					size: 0,
-					index: vec![],
-				},
-			}),
-			digest: Digest { logs: vec![] },
-		}
-	}
-
 	#[tokio::test]
 	async fn block_header_route_ok() {
 		let config = RuntimeConfig::default();
@@ -465,10 +796,10 @@ mod tests {
 			header_verified: Some(BlockRange::init(1)),
 			..Default::default()
 		}));
-		let database = MockDatabase {
-			header: Some(header()),
-			..Default::default()
-		};
+		let database = MemoryDB::default();
+		database
+			.store_header(1, &header(1, H256::default()))
+			.unwrap();
 		let route = super::block_header_route(config, state, database);
 		let response = warp::test::request()
 			.method("GET")
@@ -481,6 +812,110 @@ mod tests {
 		);
 	}
 
+	fn blocks_state() -> Arc<Mutex<State>> {
+		let mut state = State {
+			latest: 10,
+			header_verified: Some(BlockRange::init(1)),
+			confidence_achieved: Some(BlockRange::init(1)),
+			data_verified: Some(BlockRange::init(1)),
+			..Default::default()
+		};
+		state.header_verified.set(9);
+		state.confidence_achieved.set(9);
+		state.data_verified.set(8);
+		Arc::new(Mutex::new(state))
+	}
+
+	fn blocks_db() -> MemoryDB {
+		let db = MemoryDB::default();
+		for block_number in 1..=9 {
+			let header = header(block_number, H256::default());
+			db.store_header(block_number, &header).unwrap();
+			db.store_confidence(block_number, 4).unwrap();
+		}
+		db
+	}
+
+	async fn blocks_request(path: &str, config: RuntimeConfig) -> serde_json::Value {
+		let route = super::blocks_route(config, blocks_state(), blocks_db());
+		let response = warp::test::request()
+			.method("GET")
+			.path(path)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		serde_json::from_slice(response.body()).unwrap()
+	}
+
+	fn block_numbers(response: &serde_json::Value) -> Vec<u64> {
+		response["blocks"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|block| block["block_number"].as_u64().unwrap())
+			.collect()
+	}
+
+	#[tokio::test]
+	async fn blocks_route_pagination() {
+		let config = RuntimeConfig::default();
+
+		let response = blocks_request("/v2/blocks?from=2&limit=4", config.clone()).await;
+		assert_eq!(block_numbers(&response), vec![2, 3, 4, 5]);
+		assert_eq!(response["next_cursor"], 6);
+		assert_eq!(response["blocks"][0]["status"], "finished");
+		assert_eq!(response["blocks"][0]["confidence"], 93.75);
+		assert!(response["blocks"][0].get("header").is_none());
+
+		let response = blocks_request("/v2/blocks?from=2&cursor=8&limit=4", config).await;
+		assert_eq!(block_numbers(&response), vec![8, 9, 10]);
+		assert!(response.get("next_cursor").is_none());
+		assert_eq!(response["blocks"][1]["status"], "verifying-data");
+		assert_eq!(response["blocks"][2]["status"], "verifying-header");
+		assert!(response["blocks"][2]["confidence"].is_null());
+	}
+
+	#[tokio::test]
+	async fn blocks_route_to_latest() {
+		let response = blocks_request("/v2/blocks?from=9&to=20", RuntimeConfig::default()).await;
+		assert_eq!(block_numbers(&response), vec![9, 10]);
+		assert!(response.get("next_cursor").is_none());
+
+		let response = blocks_request("/v2/blocks?from=11&to=20", RuntimeConfig::default()).await;
+		assert!(block_numbers(&response).is_empty());
+	}
+
+	#[tokio::test]
+	async fn blocks_route_fields() {
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			..Default::default()
+		};
+		let response = blocks_request("/v2/blocks?from=8&to=10&fields=header,data", config).await;
+
+		let blocks = response["blocks"].as_array().unwrap();
+		assert_eq!(blocks[0]["header"]["number"], 8);
+		assert_eq!(blocks[0]["data_transactions"], serde_json::json!([]));
+		assert_eq!(blocks[1]["header"]["number"], 9);
+		assert!(blocks[1].get("data_transactions").is_none());
+		assert!(blocks[2].get("header").is_none());
+	}
+
+	#[test_case("/v2/blocks?from=5&to=4" ; "Invalid range")]
+	#[test_case("/v2/blocks?from=5&cursor=4" ; "Cursor below range")]
+	#[test_case("/v2/blocks?from=5&to=6&cursor=8" ; "Cursor above range")]
+	#[test_case("/v2/blocks?from=1&fields=unknown" ; "Invalid field")]
+	#[tokio::test]
+	async fn blocks_route_bad_request(path: &str) {
+		let route = super::blocks_route(RuntimeConfig::default(), blocks_state(), blocks_db());
+		let response = warp::test::request()
+			.method("GET")
+			.path(path)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+	}
+
 	#[test_case(0, r#"Block data is not available"#  ; "Block is unavailable")]
 	#[test_case(6, r#"Block data is not available"#  ; "Block is pending")]
 	#[test_case(8, r#"Block data is not available"#  ; "Block is in verifying-data state")]
@@ -502,7 +937,7 @@ mod tests {
 			..Default::default()
 		}));
 
-		let route = super::block_data_route(config, state, MockDatabase::default());
+		let route = super::block_data_route(config, state, MemoryDB::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path(&format!("/v2/blocks/{block_number}/data"))
@@ -520,7 +955,7 @@ mod tests {
 			..Default::default()
 		}));
 
-		let route = super::block_data_route(config, state, MockDatabase::default());
+		let route = super::block_data_route(config, state, MemoryDB::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/blocks/11/data")
@@ -543,7 +978,7 @@ mod tests {
 			..Default::default()
 		}));
 
-		let route = super::block_data_route(config, state, MockDatabase::default());
+		let route = super::block_data_route(config, state, MemoryDB::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/blocks/5/data")
@@ -569,8 +1004,11 @@ mod tests {
 			data_verified: Some(BlockRange::init(5)),
 			..Default::default()
 		}));
-		let db = MockDatabase {
-			app_data: Some(vec![vec![
+		let db = MemoryDB::default();
+		db.store_data(
+			1,
+			5,
+			&vec![vec![
 				189, 1, 132, 0, 212, 53, 147, 199, 21, 253, 211, 28, 97, 20, 26, 189, 4, 169, 159,
 				214, 130, 44, 133, 88, 133, 76, 205, 227, 154, 86, 132, 231, 165, 109, 162, 125, 1,
 				50, 12, 43, 176, 19, 42, 23, 73, 70, 223, 198, 180, 103, 34, 60, 246, 184, 49, 140,
@@ -578,9 +1016,9 @@ mod tests {
 				8, 4, 68, 137, 5, 156, 94, 209, 7, 169, 105, 62, 63, 1, 122, 253, 195, 112, 173,
 				239, 21, 73, 163, 240, 106, 109, 131, 0, 4, 0, 4, 29, 1, 20, 116, 101, 115, 116,
 				10,
-			]]),
-			..Default::default()
-		};
+			]],
+		)
+		.unwrap();
 
 		let route = super::block_data_route(config, state, db);
 		let response = warp::test::request()
@@ -611,65 +1049,366 @@ mod tests {
 			.collect()
 	}
 
-	#[derive(Clone)]
-	struct MockSubmitter {
-		pub has_signer: bool,
-	}
+	struct MockFetcher;
 
 	#[async_trait]
-	impl transactions::Submit for MockSubmitter {
-		async fn submit(&self, _: Transaction) -> anyhow::Result<SubmitResponse> {
-			Ok(SubmitResponse {
-				block_hash: H256::random(),
-				hash: H256::random(),
-				index: 0,
-			})
+	impl cells::Fetch for MockFetcher {
+		async fn fetch_cells(
+			&self,
+			_: u32,
+			_: H256,
+			_: Dimensions,
+			_: &[[u8; config::COMMITMENT_SIZE]],
+			positions: &[Position],
+		) -> anyhow::Result<Vec<Cell>> {
+			// Cell at the last column of the second row is not found
+			Ok(positions
+				.iter()
+				.filter(|position| position.row != 1 || position.col < 3)
+				.map(|&position| Cell {
+					position,
+					content: [position.col as u8; 80],
+				})
+				.collect())
 		}
 
-		fn has_signer(&self) -> bool {
-			self.has_signer
+		async fn fetch_app_data(&self, _: H256, _: u32) -> anyhow::Result<Vec<Vec<u8>>> {
+			Ok(vec![vec![1; 10], vec![2; 40]])
 		}
-	}
-
-	#[derive(Clone, Default)]
-	struct MockDatabase {
-		confidence: Option<u32>,
-		header: Option<DaHeader>,
-		app_data: Option<AppData>,
-	}
 
-	impl Database for MockDatabase {
-		fn get_confidence(&self, _: u32) -> anyhow::Result<Option<u32>> {
-			Ok(self.confidence)
+		async fn fetch_data_proof(&self, _: H256, _: &[u8]) -> anyhow::Result<DataProof> {
+			Ok(DataProof {
+				root: H256::default(),
+				proof: vec![],
+				number_of_leaves: 1,
+				leaf_index: 0,
+				leaf: H256::default(),
+			})
 		}
+	}
 
-		fn get_header(&self, _: u32) -> anyhow::Result<Option<DaHeader>> {
-			Ok(self.header.clone())
+	fn matrix_header() -> DaHeader {
+		DaHeader {
+			extension: HeaderExtension::V2(v2::HeaderExtension {
+				commitment: KateCommitment {
+					rows: 1,
+					cols: 4,
+					commitment: vec![1; 96],
+					data_root: H256::default(),
+				},
+				app_lookup: CompactDataLookup {
+					size: 0,
+					index: vec![],
+				},
+			}),
+			..header(1, H256::default())
 		}
+	}
 
-		fn get_data(&self, _app_id: u32, _: u32) -> anyhow::Result<Option<AppData>> {
-			Ok(self.app_data.clone())
-		}
+	fn matrix_state() -> Arc<Mutex<State>> {
+		Arc::new(Mutex::new(State {
+			latest: 1,
+			header_verified: Some(BlockRange::init(1)),
+			..Default::default()
+		}))
 	}
 
-	#[test_case(r#"{"raw":""}"#, b"Request body deserialize error: unknown variant `raw`" ; "Invalid json schema")]
-	#[test_case(r#"{"data":"dHJhbnooNhY3Rpb24:"}"#, b"Request body deserialize error: Invalid byte" ; "Invalid base64 value")]
 	#[tokio::test]
-	async fn submit_route_bad_request(json: &str, message: &[u8]) {
-		let route = super::submit_route(Some(Arc::new(MockSubmitter { has_signer: true })));
+	async fn block_cells_route_ok() {
+		let db = MemoryDB::default();
+		db.store_header(1, &matrix_header()).unwrap();
+		let route = super::block_cells_route(
+			RuntimeConfig::default(),
+			matrix_state(),
+			db,
+			Arc::new(MockFetcher),
+		);
 		let response = warp::test::request()
-			.method("POST")
-			.path("/v2/submit")
-			.body(json)
+			.method("GET")
+			.path("/v2/blocks/1/cells?positions=1:2,0:1,1:3,0:1")
 			.reply(&route)
 			.await;
-		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-		assert!(response.body().starts_with(message));
-	}
 
-	#[tokio::test]
-	async fn submit_route_no_signign_key() {
-		let route = super::submit_route(Some(Arc::new(MockSubmitter { has_signer: false })));
+		assert_eq!(response.status(), StatusCode::OK);
+		let response: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		assert_eq!(response["matrix"]["rows"], 1);
+		assert_eq!(response["matrix"]["cols"], 4);
+		assert_eq!(
+			response["matrix"]["commitments"].as_array().unwrap().len(),
+			2
+		);
+		let cells = response["cells"].as_array().unwrap();
+		assert_eq!(cells.len(), 2);
+		assert_eq!(cells[0]["row"], 0);
+		assert_eq!(cells[0]["col"], 1);
+		assert_eq!(cells[0]["data"], format!("0x{}", "01".repeat(32)));
+		assert_eq!(cells[0]["proof"], format!("0x{}", "01".repeat(48)));
+		assert_eq!(cells[1]["row"], 1);
+		assert_eq!(cells[1]["col"], 2);
+	}
+
+	#[test_case("/v2/blocks/1/cells?positions=2:0", "Position is outside of the matrix" ; "Row is outside of the matrix")]
+	#[test_case("/v2/blocks/1/cells?positions=0:4", "Position is outside of the matrix" ; "Column is outside of the matrix")]
+	#[test_case("/v2/blocks/1/rows?rows=0,2", "Row is outside of the matrix" ; "Requested row is outside of the matrix")]
+	#[tokio::test]
+	async fn block_cells_and_rows_route_bad_request(path: &str, expected: &str) {
+		let db = MemoryDB::default();
+		db.store_header(1, &matrix_header()).unwrap();
+		let fetcher = Arc::new(MockFetcher);
+		let route = super::block_cells_route(
+			RuntimeConfig::default(),
+			matrix_state(),
+			db.clone(),
+			fetcher.clone(),
+		)
+		.or(super::block_rows_route(
+			RuntimeConfig::default(),
+			matrix_state(),
+			db,
+			fetcher,
+		));
+		let response = warp::test::request()
+			.method("GET")
+			.path(path)
+			.reply(&route)
+			.await;
+
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+		assert_eq!(response.body(), expected);
+	}
+
+	#[tokio::test]
+	async fn block_rows_route_ok() {
+		let db = MemoryDB::default();
+		db.store_header(1, &matrix_header()).unwrap();
+		let route = super::block_rows_route(
+			RuntimeConfig::default(),
+			matrix_state(),
+			db,
+			Arc::new(MockFetcher),
+		);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/1/rows?rows=1,0")
+			.reply(&route)
+			.await;
+
+		assert_eq!(response.status(), StatusCode::OK);
+		let response: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		// Row 1 is not returned, since one of its cells is not found
+		let rows = response["rows"].as_array().unwrap();
+		assert_eq!(rows.len(), 1);
+		assert_eq!(rows[0]["row"], 0);
+		let data = (0..4).map(|col| format!("{col:02x}").repeat(32));
+		assert_eq!(rows[0]["data"], format!("0x{}", data.collect::<String>()));
+		let proofs = rows[0]["proofs"].as_array().unwrap();
+		assert_eq!(proofs.len(), 4);
+		assert_eq!(proofs[3], format!("0x{}", "03".repeat(48)));
+	}
+
+	fn proof_route(
+		db: MemoryDB,
+		app_cells: u32,
+	) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+		let mut header = matrix_header();
+		if let HeaderExtension::V2(extension) = &mut header.extension {
+			// All data in the matrix belongs to the app 0
+			extension.app_lookup.size = app_cells;
+		}
+		db.store_header(1, &header).unwrap();
+		super::block_proof_route(
+			RuntimeConfig::default(),
+			matrix_state(),
+			db,
+			Arc::new(MockFetcher),
+		)
+	}
+
+	#[tokio::test]
+	async fn block_proof_route_ok() {
+		let route = proof_route(MemoryDB::default(), 1);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/1/proof?app_id=0")
+			.reply(&route)
+			.await;
+
+		assert_eq!(response.status(), StatusCode::OK);
+		let response: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		assert_eq!(response["block_number"], 1);
+		assert_eq!(response["app_id"], 0);
+		assert_eq!(response["range"]["start"], 0);
+		assert_eq!(response["range"]["end"], 1);
+		assert_eq!(
+			response["matrix"]["commitments"].as_array().unwrap().len(),
+			2
+		);
+		let cells = response["cells"].as_array().unwrap();
+		assert_eq!(cells.len(), 1);
+		assert_eq!(cells[0]["row"], 0);
+		assert_eq!(cells[0]["col"], 0);
+		assert_eq!(cells[0]["proof"], format!("0x{}", "00".repeat(48)));
+		assert!(response.get("data_proof").is_none());
+	}
+
+	#[tokio::test]
+	async fn block_proof_route_not_found() {
+		let route = proof_route(MemoryDB::default(), 1);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/1/proof?app_id=1")
+			.reply(&route)
+			.await;
+
+		assert_eq!(response.status(), StatusCode::NOT_FOUND);
+	}
+
+	#[tokio::test]
+	async fn block_proof_route_transaction() {
+		let route = proof_route(MemoryDB::default(), 3);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/1/proof?app_id=0&index=1")
+			.reply(&route)
+			.await;
+
+		assert_eq!(response.status(), StatusCode::OK);
+		let response: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		// Second transaction is encoded at bytes 12..53 of the application data
+		assert_eq!(response["range"]["start"], 0);
+		assert_eq!(response["range"]["end"], 2);
+		assert_eq!(response["cells"].as_array().unwrap().len(), 2);
+		assert!(response.get("data_proof").is_some());
+	}
+
+	#[tokio::test]
+	async fn block_proof_route_too_many_cells() {
+		let route = proof_route(MemoryDB::default(), 1001);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/1/proof?app_id=0")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+		// Only cells of the transaction are fetched
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/1/proof?app_id=0&index=1")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+	}
+
+	#[derive(Clone)]
+	struct MockSubmitter {
+		pub has_signer: bool,
+	}
+
+	#[async_trait]
+	impl transactions::Submit for MockSubmitter {
+		async fn submit(
+			&self,
+			_: Transaction,
+			_: Option<&str>,
+			_: Option<u32>,
+		) -> anyhow::Result<SubmitResponse> {
+			Ok(SubmitResponse {
+				block_hash: H256::random(),
+				hash: H256::random(),
+				index: 0,
+			})
+		}
+
+		async fn submit_async(
+			&self,
+			_: Transaction,
+			_: Option<&str>,
+			_: Option<u32>,
+		) -> anyhow::Result<Submission> {
+			Ok(Submission::new(H256::random()))
+		}
+
+		async fn estimate(
+			&self,
+			transaction: Transaction,
+			_: Option<&str>,
+			_: Option<u32>,
+		) -> anyhow::Result<EstimateResponse> {
+			Ok(EstimateResponse {
+				fee: 124_000_000_000_000_000,
+				weight: Weight {
+					ref_time: 1_000,
+					proof_size: 0,
+				},
+				class: "normal".to_string(),
+				valid: Some(matches!(transaction, Transaction::Data(_))),
+				error: None,
+			})
+		}
+
+		async fn create_application_key(
+			&self,
+			_: Vec<u8>,
+			_: Option<&str>,
+		) -> anyhow::Result<ApplicationKeyResponse> {
+			Ok(ApplicationKeyResponse {
+				app_id: 10,
+				block_hash: H256::random(),
+				hash: H256::random(),
+			})
+		}
+
+		fn has_signer(&self, key: Option<&str>) -> bool {
+			self.has_signer && matches!(key, None | Some("sequencer"))
+		}
+
+		fn max_blob_size(&self) -> usize {
+			16
+		}
+
+		fn default_app_id(&self) -> Option<u32> {
+			Some(1)
+		}
+	}
+
+	/// Authorization with the submit token restricted to the application ID 2
+	fn app_auth() -> Auth {
+		let cfg = RuntimeConfig {
+			http_api_tokens: vec![ApiToken {
+				token: ApiSecret("app".to_string()),
+				scopes: vec![ApiScope::Submit],
+				app_ids: Some(vec![2]),
+			}],
+			..Default::default()
+		};
+		Auth::from(&cfg)
+	}
+
+	#[test_case(r#"{"raw":""}"#, b"Request body deserialize error: unknown variant `raw`" ; "Invalid json schema")]
+	#[test_case(r#"{"data":"dHJhbnooNhY3Rpb24:"}"#, b"Request body deserialize error: Invalid byte" ; "Invalid base64 value")]
+	#[tokio::test]
+	async fn submit_route_bad_request(json: &str, message: &[u8]) {
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter { has_signer: true })),
+			Auth::default(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit")
+			.body(json)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+		assert!(response.body().starts_with(message));
+	}
+
+	#[tokio::test]
+	async fn submit_route_no_signign_key() {
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter { has_signer: false })),
+			Auth::default(),
+		);
 		let response = warp::test::request()
 			.method("POST")
 			.path("/v2/submit")
@@ -679,11 +1418,46 @@ mod tests {
 		assert_eq!(response.status(), StatusCode::NOT_FOUND);
 	}
 
+	#[test_case("/v2/submit?key=sequencer", StatusCode::OK ; "Allowed key")]
+	#[test_case("/v2/submit?key=unknown", StatusCode::NOT_FOUND ; "Unknown key")]
+	#[tokio::test]
+	async fn submit_route_key(path: &str, expected: StatusCode) {
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter { has_signer: true })),
+			Auth::default(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path(path)
+			.body(r#"{"data":"dHJhbnNhY3Rpb24K"}"#)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), expected);
+	}
+
+	#[tokio::test]
+	async fn submit_route_blob_too_large() {
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter { has_signer: true })),
+			Auth::default(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit")
+			.body(r#"{"data":"dHJhbnNhY3Rpb24gZGF0YSBibG9i"}"#)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+	}
+
 	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K"}"# ; "No errors in case of submitted data")]
 	#[test_case(r#"{"extrinsic":"dHJhbnNhY3Rpb24K"}"# ; "No errors in case of submitted extrinsic")]
 	#[tokio::test]
 	async fn submit_route_extrinsic(body: &str) {
-		let route = super::submit_route(Some(Arc::new(MockSubmitter { has_signer: true })));
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter { has_signer: true })),
+			Auth::default(),
+		);
 		let response = warp::test::request()
 			.method("POST")
 			.path("/v2/submit")
@@ -695,10 +1469,233 @@ mod tests {
 		let _ = serde_json::to_string(&response).unwrap();
 	}
 
+	#[tokio::test]
+	async fn submit_route_async() {
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter { has_signer: true })),
+			Auth::default(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit?async=true")
+			.body(r#"{"data":"dHJhbnNhY3Rpb24K"}"#)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::ACCEPTED);
+		let submission: Submission = serde_json::from_slice(response.body()).unwrap();
+		assert_eq!(submission.status, SubmissionStatus::Submitted);
+	}
+
+	#[test_case("/v2/submit?app_id=2", r#"{"data":"dHJhbnNhY3Rpb24K"}"#, StatusCode::OK ; "Allowed app ID")]
+	#[test_case("/v2/submit", r#"{"data":"dHJhbnNhY3Rpb24K"}"#, StatusCode::FORBIDDEN ; "Default app ID")]
+	#[test_case("/v2/submit?app_id=3", r#"{"data":"dHJhbnNhY3Rpb24K"}"#, StatusCode::FORBIDDEN ; "Other app ID")]
+	#[test_case("/v2/submit", r#"{"extrinsic":"dHJhbnNhY3Rpb24K"}"#, StatusCode::FORBIDDEN ; "Extrinsic")]
+	#[tokio::test]
+	async fn submit_route_app_id(path: &str, body: &str, expected: StatusCode) {
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter { has_signer: true })),
+			app_auth(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path(path)
+			.header("Authorization", "Bearer app")
+			.body(body)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), expected);
+	}
+
+	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K"}"#, true ; "Data")]
+	#[test_case(r#"{"extrinsic":"dHJhbnNhY3Rpb24K"}"#, false ; "Extrinsic")]
+	#[tokio::test]
+	async fn estimate_route(body: &str, valid: bool) {
+		let route = super::estimate_route(
+			Some(Arc::new(MockSubmitter { has_signer: true })),
+			Auth::default(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit/estimate")
+			.body(body)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		let response: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		assert_eq!(response["fee"], "124000000000000000");
+		assert_eq!(response["weight"]["ref_time"], 1000);
+		assert_eq!(response["valid"], valid);
+	}
+
+	#[tokio::test]
+	async fn estimate_route_blob_too_large() {
+		let route = super::estimate_route(
+			Some(Arc::new(MockSubmitter { has_signer: true })),
+			Auth::default(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit/estimate")
+			.body(r#"{"data":"dHJhbnNhY3Rpb24gZGF0YSBibG9i"}"#)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+	}
+
+	#[test_case(r#"{"name":"rollup"}"#, StatusCode::OK ; "Valid name")]
+	#[test_case(r#"{"name":""}"#, StatusCode::BAD_REQUEST ; "Empty name")]
+	#[tokio::test]
+	async fn application_key_route(body: &str, expected: StatusCode) {
+		let route = super::application_key_route(
+			Some(Arc::new(MockSubmitter { has_signer: true })),
+			Auth::default(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/applications")
+			.body(body)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), expected);
+		if expected == StatusCode::OK {
+			let response: ApplicationKeyResponse = serde_json::from_slice(response.body()).unwrap();
+			assert_eq!(response.app_id, 10);
+		}
+	}
+
+	#[tokio::test]
+	async fn application_key_route_restricted() {
+		let route = super::application_key_route(
+			Some(Arc::new(MockSubmitter { has_signer: true })),
+			app_auth(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/applications")
+			.header("Authorization", "Bearer app")
+			.body(r#"{"name":"rollup"}"#)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::FORBIDDEN);
+	}
+
+	/// Sample values of the path parameters in the specification
+	fn sample_path(path: &str) -> String {
+		path.replace("{block_number}", "1")
+			.replace("{subscription_id}", "cae63f2b-1ba9-4a4a-a0e8-cd3b6bf4c83f")
+			.replace("{hash}", &"0".repeat(64))
+	}
+
+	/// Checks if the request is matched by a route. Handlers can respond with 404,
+	/// but the unmatched requests are rejected.
+	async fn is_served(
+		route: &(impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone + 'static),
+		method: &str,
+		path: &str,
+	) -> bool {
+		let result = warp::test::request()
+			.method(&method.to_uppercase())
+			.path(&sample_path(path))
+			.filter(route)
+			.await;
+		match result {
+			Ok(_) => true,
+			Err(rejection) => {
+				!rejection.is_not_found() && rejection.find::<MethodNotAllowed>().is_none()
+			},
+		}
+	}
+
+	#[tokio::test]
+	async fn routes_match_operations() {
+		let route = super::filters(
+			v1(),
+			Node::default(),
+			Arc::new(Mutex::new(State::default())),
+			RuntimeConfig::default(),
+			Arc::new(MockFetcher),
+			Some(Arc::new(MockSubmitter { has_signer: true })),
+			WsClients::default(),
+			Events::default(),
+			MemoryDB::default(),
+		);
+
+		let operations = openapi::operations()
+			.into_iter()
+			.map(|(method, path, _)| (method, path))
+			.collect::<BTreeSet<_>>();
+		let routes = super::ROUTES.iter().copied().collect::<BTreeSet<_>>();
+
+		for &(method, path) in operations.union(&routes) {
+			assert!(
+				is_served(&route, method, path).await,
+				"Not served: {method} {path}"
+			);
+		}
+		assert_eq!(
+			operations.difference(&routes).collect::<Vec<_>>(),
+			Vec::<&(&str, &str)>::new(),
+			"Operations without routes"
+		);
+		assert_eq!(
+			routes.difference(&operations).collect::<Vec<_>>(),
+			Vec::<&(&str, &str)>::new(),
+			"Routes without operations"
+		);
+
+		assert!(!is_served(&route, "get", "/v2/unknown").await);
+		assert!(!is_served(&route, "delete", "/v2/status").await);
+	}
+
+	#[tokio::test]
+	async fn openapi_route() {
+		let route = super::openapi_route(v1());
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/openapi.json")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		let document: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		assert_eq!(document["openapi"], "3.0.3");
+		assert_eq!(document["info"]["version"], "v1.0.0");
+		assert!(document["paths"]["/v2/blocks/{block_number}/header"]["get"].is_object());
+	}
+
+	#[tokio::test]
+	async fn submission_route() {
+		let db = MemoryDB::default();
+		let submission = Submission {
+			hash: H256::repeat_byte(1),
+			status: SubmissionStatus::InBlock,
+			block_hash: Some(H256::repeat_byte(2)),
+			index: None,
+			error: None,
+		};
+		db.store_submission(&submission).unwrap();
+		let route = super::submission_route(db);
+
+		let response = warp::test::request()
+			.method("GET")
+			.path(&format!("/v2/submissions/{:?}", H256::repeat_byte(1)))
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		let response: Submission = serde_json::from_slice(response.body()).unwrap();
+		assert_eq!(response, submission);
+
+		let response = warp::test::request()
+			.method("GET")
+			.path(&format!("/v2/submissions/{:?}", H256::repeat_byte(3)))
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::NOT_FOUND);
+	}
+
 	#[tokio::test]
 	async fn subscriptions_route() {
 		let clients = WsClients::default();
-		let route = super::subscriptions_route(clients.clone());
+		let route = super::subscriptions_route(clients.clone(), Default::default());
 
 		let body = r#"{"topics":["confidence-achieved","data-verified","header-verified"],"data_fields":["data","extrinsic"]}"#;
 		let response = warp::test::request()
@@ -711,19 +1708,245 @@ mod tests {
 		let SubscriptionId { subscription_id } = serde_json::from_slice(response.body()).unwrap();
 		assert!(uuid::Uuid::from_str(&subscription_id).is_ok());
 
-		let clients = clients.0.read().await;
-		let client = clients.get(&subscription_id).unwrap();
+		let subscription = clients.subscription(&subscription_id).await.unwrap();
 
 		let expected = Subscription {
 			topics: all_topics(),
 			data_fields: all_data_fields(),
+			from_block: None,
 		};
-		assert!(client.subscription == expected);
+		assert!(subscription == expected);
+	}
+
+	#[test_case(899, StatusCode::BAD_REQUEST ; "Too old replay block")]
+	#[test_case(900, StatusCode::OK ; "Oldest replay block")]
+	#[tokio::test]
+	async fn subscriptions_route_replay_limit(from_block: u32, expected: StatusCode) {
+		let clients = WsClients::default();
+		let state = Arc::new(Mutex::new(State {
+			latest: 1000,
+			..Default::default()
+		}));
+		let route = super::subscriptions_route(clients, state);
+
+		let body = format!(
+			r#"{{"topics":["confidence-achieved"],"data_fields":[],"from_block":{from_block}}}"#
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.body(body)
+			.path("/v2/subscriptions")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), expected);
+	}
+
+	#[tokio::test]
+	async fn unsubscribe_route() {
+		let clients = WsClients::default();
+		clients
+			.subscribe("1", Subscription::default())
+			.await
+			.unwrap();
+		let route = super::unsubscribe_route(clients.clone());
+
+		for expected in [StatusCode::NO_CONTENT, StatusCode::NOT_FOUND] {
+			let response = warp::test::request()
+				.method("DELETE")
+				.path("/v2/subscriptions/1")
+				.reply(&route)
+				.await;
+			assert_eq!(response.status(), expected);
+		}
+		assert!(!clients.has_subscription("1").await);
+	}
+
+	#[test_case("topics=header-verified", "1-1" ; "Unknown last event ID")]
+	#[test_case("topics=header-verified", "3" ; "Invalid last event ID")]
+	#[test_case("topics=unknown-topic", "1-1" ; "Unknown topic")]
+	#[test_case("data_fields=data", "1-1" ; "Missing topics")]
+	#[tokio::test]
+	async fn events_route_bad_request(query: &str, last_event_id: &str) {
+		let events = Events::new(1);
+		for _ in 0..3 {
+			let message: PublishMessage = serde_json::from_str(
+				r#"{"topic":"confidence-achieved","message":{"block_number":1}}"#,
+			)
+			.unwrap();
+			events.publish(Topic::ConfidenceAchieved, message).await;
+		}
+		let route = super::events_route(events);
+
+		let response = warp::test::request()
+			.method("GET")
+			.path(&format!("/v2/events?{query}"))
+			.header("last-event-id", last_event_id)
+			.reply(&route)
+			.await;
+
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+	}
+
+	fn jsonrpc_server(
+		submitter: Option<MockSubmitter>,
+		events: Events,
+	) -> jsonrpc::Server<MockSubmitter, MemoryDB> {
+		jsonrpc::Server {
+			version: v1(),
+			config: RuntimeConfig::default(),
+			node: Node::default(),
+			submitter: submitter.map(Arc::new),
+			state: Arc::new(Mutex::new(State::default())),
+			db: MemoryDB::default(),
+			events,
+			subscriptions: Default::default(),
+		}
+	}
+
+	async fn jsonrpc_request(
+		server: jsonrpc::Server<MockSubmitter, MemoryDB>,
+		auth: Auth,
+		token: Option<&str>,
+		body: &str,
+	) -> (StatusCode, Value) {
+		let route = super::jsonrpc_route(server, auth).recover(auth::handle_rejection);
+		let mut request = warp::test::request()
+			.method("POST")
+			.path("/v2/rpc")
+			.body(body);
+		if let Some(token) = token {
+			request = request.header("authorization", format!("Bearer {token}"));
+		}
+		let response = request.reply(&route).await;
+		let body = serde_json::from_slice(response.body()).unwrap_or_default();
+		(response.status(), body)
+	}
+
+	#[test_case(r#"{"jsonrpc":"2.0","id":1,"method":"version"}"#, json!({"jsonrpc":"2.0","id":1,"result":{"version":"v1.0.0","network_version":"nv1.0.0"}}) ; "Version")]
+	#[test_case(r#"{"jsonrpc":"2.0","id":"a","method":"block","params":{"block_number":10}}"#, json!({"jsonrpc":"2.0","id":"a","error":{"code":-32001,"message":"Not Found","data":"not-found"}}) ; "Block not found")]
+	#[test_case(r#"{"jsonrpc":"2.0","id":1,"method":"header","params":{}}"#, json!({"jsonrpc":"2.0","id":1,"error":{"code":-32602,"message":"Invalid params: missing field `block_number`"}}) ; "Invalid params")]
+	#[test_case(r#"{"jsonrpc":"2.0","id":1,"method":"unknown"}"#, json!({"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"Method not found"}}) ; "Unknown method")]
+	#[test_case(r#"{"jsonrpc":"2.0","id":1,"method":"subscribe","params":{"topics":["header-verified"]}}"#, json!({"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"Subscriptions are available only over web socket"}}) ; "Subscribe over HTTP")]
+	#[test_case(r#"{"jsonrpc":"1.0","id":1,"method":"version"}"#, json!({"jsonrpc":"2.0","id":1,"error":{"code":-32600,"message":"Invalid Request"}}) ; "Invalid version")]
+	#[test_case(r#"{"id":1}"#, json!({"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"Invalid Request"}}) ; "Invalid request")]
+	#[test_case(r#"[]"#, json!({"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"Invalid Request"}}) ; "Empty batch")]
+	#[test_case(r#"{"jsonrpc""#, json!({"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"Parse error"}}) ; "Parse error")]
+	#[test_case(r#"[{"jsonrpc":"2.0","id":1,"method":"version"},{"jsonrpc":"2.0","method":"version"},1]"#, json!([{"jsonrpc":"2.0","id":1,"result":{"version":"v1.0.0","network_version":"nv1.0.0"}},{"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"Invalid Request"}}]) ; "Batch")]
+	#[tokio::test]
+	async fn jsonrpc_route(body: &str, expected: Value) {
+		let server = jsonrpc_server(None, Events::default());
+		let (status, response) = jsonrpc_request(server, Auth::default(), None, body).await;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(response, expected);
+	}
+
+	#[tokio::test]
+	async fn jsonrpc_route_batch_too_large() {
+		let server = jsonrpc_server(None, Events::default());
+		let request = r#"{"jsonrpc":"2.0","id":1,"method":"version"}"#;
+		let body = format!("[{}]", vec![request; 101].join(","));
+		let (status, response) = jsonrpc_request(server, Auth::default(), None, &body).await;
+		assert_eq!(status, StatusCode::OK);
+		let expected = json!({"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"Batch is too large"}});
+		assert_eq!(response, expected);
+	}
+
+	#[tokio::test]
+	async fn jsonrpc_route_notification() {
+		let server = jsonrpc_server(None, Events::default());
+		let body = r#"{"jsonrpc":"2.0","method":"status"}"#;
+		let (status, _) = jsonrpc_request(server, Auth::default(), None, body).await;
+		assert_eq!(status, StatusCode::NO_CONTENT);
+	}
+
+	#[test_case(r#"{"transaction":{"data":"dHJhbnNhY3Rpb24K"}}"#, "index" ; "Submit")]
+	#[test_case(r#"{"transaction":{"data":"dHJhbnNhY3Rpb24K"},"async":true}"#, "status" ; "Submit async")]
+	#[tokio::test]
+	async fn jsonrpc_route_submit(params: &str, expected_field: &str) {
+		let server = jsonrpc_server(Some(MockSubmitter { has_signer: true }), Events::default());
+		let body = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"submit","params":{params}}}"#);
+		let (_, response) = jsonrpc_request(server, Auth::default(), None, &body).await;
+		assert!(response["result"].get(expected_field).is_some());
+	}
+
+	#[test_case(r#"{"jsonrpc":"2.0","id":1,"method":"status"}"#, -32003, "Method is not allowed." ; "Read not allowed")]
+	#[test_case(r#"{"jsonrpc":"2.0","id":1,"method":"submit","params":{"transaction":{"data":"dHJhbnNhY3Rpb24K"}}}"#, -32003, "Submit to the application is not allowed." ; "App ID not allowed")]
+	#[test_case(r#"{"jsonrpc":"2.0","id":1,"method":"submit","params":{"transaction":{"data":"dHJhbnNhY3Rpb24K"},"key":"unknown","app_id":2}}"#, -32001, "Not Found" ; "Unknown key")]
+	#[tokio::test]
+	async fn jsonrpc_route_submit_errors(body: &str, code: i64, message: &str) {
+		let server = jsonrpc_server(Some(MockSubmitter { has_signer: true }), Events::default());
+		let (_, response) = jsonrpc_request(server, app_auth(), Some("app"), body).await;
+		assert_eq!(response["error"]["code"], code);
+		assert_eq!(response["error"]["message"], message);
+	}
+
+	#[tokio::test]
+	async fn jsonrpc_route_unauthorized() {
+		let server = jsonrpc_server(None, Events::default());
+		let body = r#"{"jsonrpc":"2.0","id":1,"method":"version"}"#;
+		let (status, _) = jsonrpc_request(server, app_auth(), None, body).await;
+		assert_eq!(status, StatusCode::UNAUTHORIZED);
+	}
+
+	#[tokio::test]
+	async fn jsonrpc_ws_route_subscribe() {
+		let events = Events::default();
+		let route = super::jsonrpc_ws_route(jsonrpc_server(None, events.clone()), Auth::default());
+		let mut ws_client = warp::test::ws()
+			.path("/v2/rpc/ws")
+			.handshake(route)
+			.await
+			.expect("handshake");
+
+		async fn receive(ws_client: &mut warp::test::WsClient) -> Value {
+			let message = ws_client.recv().await.unwrap();
+			serde_json::from_str(message.to_str().unwrap()).unwrap()
+		}
+
+		ws_client
+			.send_text(
+				r#"{"jsonrpc":"2.0","id":1,"method":"subscribe","params":{"topics":["submission-status"]}}"#,
+			)
+			.await;
+		let response = receive(&mut ws_client).await;
+		assert_eq!(response["error"]["code"], -32602);
+
+		ws_client
+			.send_text(
+				r#"{"jsonrpc":"2.0","id":2,"method":"subscribe","params":{"topics":["confidence-achieved"]}}"#,
+			)
+			.await;
+		let response = receive(&mut ws_client).await;
+		assert_eq!(response, json!({"jsonrpc":"2.0","id":2,"result":1}));
+
+		let submission = PublishMessage::SubmissionStatus(Submission::new(H256::random()));
+		events.publish(Topic::SubmissionStatus, submission).await;
+		let confidence: PublishMessage = serde_json::from_str(
+			r#"{"topic":"confidence-achieved","message":{"block_number":1,"confidence":93.75}}"#,
+		)
+		.unwrap();
+		events.publish(Topic::ConfidenceAchieved, confidence).await;
+
+		let notification = receive(&mut ws_client).await;
+		assert_eq!(
+			notification,
+			json!({"jsonrpc":"2.0","method":"subscription","params":{"subscription":1,"result":{"topic":"confidence-achieved","message":{"block_number":1,"confidence":93.75}}}})
+		);
+
+		ws_client
+			.send_text(
+				r#"{"jsonrpc":"2.0","id":3,"method":"unsubscribe","params":{"subscription":1}}"#,
+			)
+			.await;
+		let response = receive(&mut ws_client).await;
+		assert_eq!(response, json!({"jsonrpc":"2.0","id":3,"result":true}));
 	}
 
 	struct MockSetup {
 		ws_client: warp::test::WsClient,
+		clients: WsClients,
 		state: Arc<Mutex<State>>,
+		db: MemoryDB,
 	}
 
 	impl MockSetup {
@@ -732,9 +1955,11 @@ mod tests {
 			let clients = WsClients::default();
 			clients
 				.subscribe(&client_uuid, Subscription::default())
-				.await;
+				.await
+				.unwrap();
 
 			let state = Arc::new(Mutex::new(State::default()));
+			let db = MemoryDB::default();
 			let route = super::ws_route(
 				clients.clone(),
 				v1(),
@@ -742,6 +1967,8 @@ mod tests {
 				Node::default(),
 				submitter.map(Arc::new),
 				state.clone(),
+				db.clone(),
+				Auth::default(),
 			);
 			let ws_client = warp::test::ws()
 				.path(&format!("/v2/ws/{client_uuid}"))
@@ -749,7 +1976,12 @@ mod tests {
 				.await
 				.expect("handshake");
 
-			MockSetup { ws_client, state }
+			MockSetup {
+				ws_client,
+				clients,
+				state,
+				db,
+			}
 		}
 
 		async fn ws_send_text(&mut self, message: &str) -> String {
@@ -759,6 +1991,109 @@ mod tests {
 		}
 	}
 
+	#[tokio::test]
+	async fn ws_route_replay() {
+		let client_uuid = uuid::Uuid::new_v4().to_string();
+		let clients = WsClients::default();
+		let subscription = Subscription {
+			topics: [Topic::ConfidenceAchieved].into_iter().collect(),
+			data_fields: HashSet::new(),
+			from_block: Some(2),
+		};
+		clients.subscribe(&client_uuid, subscription).await.unwrap();
+
+		let db = MemoryDB::default();
+		for block_number in 1..=4 {
+			db.store_confidence(block_number, 10).unwrap();
+		}
+		let state = Arc::new(Mutex::new(State {
+			latest: 3,
+			..Default::default()
+		}));
+		let route = super::ws_route(
+			clients.clone(),
+			v1(),
+			RuntimeConfig::default(),
+			Node::default(),
+			None::<Arc<MockSubmitter>>,
+			state,
+			db,
+			Auth::default(),
+		);
+		let mut ws_client = warp::test::ws()
+			.path(&format!("/v2/ws/{client_uuid}"))
+			.handshake(route)
+			.await
+			.expect("handshake");
+
+		for block_number in 2..=3 {
+			let message = ws_client.recv().await.unwrap();
+			let message: PublishMessage = serde_json::from_str(message.to_str().unwrap()).unwrap();
+			assert!(matches!(message, PublishMessage::ConfidenceAchieved(_)));
+			assert_eq!(message.block_number(), Some(block_number));
+		}
+	}
+
+	#[tokio::test]
+	async fn ws_route_replay_overlaps_live() {
+		let client_uuid = uuid::Uuid::new_v4().to_string();
+		let clients = WsClients::default();
+		let subscription = Subscription {
+			topics: [Topic::ConfidenceAchieved].into_iter().collect(),
+			data_fields: HashSet::new(),
+			from_block: Some(2),
+		};
+		clients.subscribe(&client_uuid, subscription).await.unwrap();
+
+		let db = MemoryDB::default();
+		for block_number in 1..=3 {
+			db.store_confidence(block_number, 10).unwrap();
+		}
+		let state = Arc::new(Mutex::new(State {
+			latest: 3,
+			..Default::default()
+		}));
+		let route = super::ws_route(
+			clients.clone(),
+			v1(),
+			RuntimeConfig::default(),
+			Node::default(),
+			None::<Arc<MockSubmitter>>,
+			state.clone(),
+			db.clone(),
+			Auth::default(),
+		);
+		let mut ws_client = warp::test::ws()
+			.path(&format!("/v2/ws/{client_uuid}"))
+			.handshake(route)
+			.await
+			.expect("handshake");
+
+		// Live messages are published while the stored ones are replayed
+		for block_number in 3..=5 {
+			db.store_confidence(block_number, 10).unwrap();
+			state.lock().unwrap().latest = block_number;
+			let message: PublishMessage = serde_json::from_str(&format!(
+				r#"{{"topic":"confidence-achieved","message":{{"block_number":{block_number}}}}}"#
+			))
+			.unwrap();
+			clients
+				.publish(&Topic::ConfidenceAchieved, message)
+				.await
+				.unwrap();
+		}
+
+		for block_number in 2..=5 {
+			let message = ws_client.recv().await.unwrap();
+			let message: PublishMessage = serde_json::from_str(message.to_str().unwrap()).unwrap();
+			assert_eq!(message.block_number(), Some(block_number));
+		}
+		tokio::select! {
+			_ = ws_client.recv() => panic!("Message is received more than once"),
+			_ = tokio::time::sleep(Duration::from_millis(100)) => {},
+		};
+	}
+
 	#[tokio::test]
 	async fn ws_route_version() {
 		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
@@ -821,8 +2156,6 @@ mod tests {
 		Uuid::try_parse(uuid).unwrap()
 	}
 
-	#[test_case(r#"{"type":"submit","request_id":"16b24956-2e01-4ba8-bad5-456c561c87d7","message":{"data":""}}"#, None, Some("16b24956-2e01-4ba8-bad5-456c561c87d7"), "Submit is not configured" ; "No submitter")]
-	#[test_case(r#"{"type":"submit","request_id":"537a3c39-c029-4283-9612-17465bf7cfd1","message":{"data":"dHJhbnNhY3Rpb24K"}}"#, Some(false), Some("537a3c39-c029-4283-9612-17465bf7cfd1"), "Signer is not configured" ; "No signer")]
 	#[test_case(r#"{"type":"submit","request_id":"36bc1f28-e093-422f-964b-1cb1b3882baf","message":{"extrinsic":""}}"#, Some(false), Some("36bc1f28-e093-422f-964b-1cb1b3882baf"), "Transaction is empty" ; "Empty extrinsic")]
 	#[test_case(r#"{"type":"submit","request_id":"cc60b2f3-d9ff-4c73-9632-d21d07f7b620","message":{"data":""}}"#, Some(true), Some("cc60b2f3-d9ff-4c73-9632-d21d07f7b620"), "Transaction is empty" ; "Empty data")]
 	#[test_case(r#"{"type":"submit","request_id":"9181df86-22f0-42a1-a965-60adb9fc6bdc","message":{"extrinsic":"bad"}}"#, Some(false), None, "Failed to parse request" ; "Bad extrinsic")]
@@ -844,6 +2177,22 @@ mod tests {
 		assert!(error.message.contains(expected));
 	}
 
+	#[test_case(None ; "No submitter")]
+	#[test_case(Some(false) ; "No signer")]
+	#[tokio::test]
+	async fn ws_route_submit_not_found(signer: Option<bool>) {
+		let submitter = signer.map(|has_signer| MockSubmitter { has_signer });
+		let mut test = MockSetup::new(RuntimeConfig::default(), submitter).await;
+		let request = r#"{"type":"submit","request_id":"537a3c39-c029-4283-9612-17465bf7cfd1","message":{"data":"dHJhbnNhY3Rpb24K"}}"#;
+		let response = test.ws_send_text(request).await;
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::NotFound);
+		assert_eq!(
+			error.request_id,
+			Some(to_uuid("537a3c39-c029-4283-9612-17465bf7cfd1"))
+		);
+	}
+
 	#[tokio::test]
 	async fn ws_route_submit_data() {
 		let submitter = Some(MockSubmitter { has_signer: true });
@@ -879,4 +2228,102 @@ mod tests {
 		assert_eq!(response.request_id, expected_request_id);
 		assert_eq!(response.message.index, 0);
 	}
+
+	#[tokio::test]
+	async fn ws_route_submit_async() {
+		let submitter = Some(MockSubmitter { has_signer: true });
+		let mut test = MockSetup::new(RuntimeConfig::default(), submitter).await;
+
+		let request = r#"{"type":"submit-async","request_id":"fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a","message":{"data":"dHJhbnNhY3Rpb24K"}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::SubmissionStatus(response) = serde_json::from_str(&response).unwrap()
+		else {
+			panic!("Invalid response");
+		};
+		let expected_request_id = to_uuid("fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a");
+		assert_eq!(response.request_id, expected_request_id);
+		assert_eq!(response.message.status, SubmissionStatus::Submitted);
+	}
+
+	#[tokio::test]
+	async fn ws_route_block() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		{
+			let mut state = test.state.lock().unwrap();
+			state.latest = 10;
+			state.header_verified.set(10);
+			state.data_verified.set(10);
+		}
+		test.db.store_confidence(10, 4).unwrap();
+
+		let request = r#"{"type":"block","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0","message":{"block_number":10}}"#;
+		let response = test.ws_send_text(request).await;
+		assert_eq!(
+			r#"{"topic":"block","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0","message":{"status":"finished","confidence":93.75}}"#,
+			response
+		);
+	}
+
+	#[test_case(r#"{"type":"block","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0","message":{"block_number":11}}"# ; "Block")]
+	#[test_case(r#"{"type":"block-header","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0","message":{"block_number":11}}"# ; "Block header")]
+	#[test_case(r#"{"type":"block-data","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0","message":{"block_number":11}}"# ; "Block data")]
+	#[tokio::test]
+	async fn ws_route_block_not_found(request: &str) {
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			..Default::default()
+		};
+		let mut test = MockSetup::new(config, None).await;
+		test.state.lock().unwrap().latest = 10;
+
+		let response = test.ws_send_text(request).await;
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::NotFound);
+		assert_eq!(
+			error.request_id,
+			Some(to_uuid("cae63fff-c4b8-4af9-b4fe-0605a5329aa0"))
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_subscribe() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		let message = PublishMessage::ConfidenceAchieved(ConfidenceMessage {
+			block_number: 1,
+			confidence: None,
+		});
+
+		let request = r#"{"type":"subscribe","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0","message":{"topics":["confidence-achieved"],"data_fields":["data"]}}"#;
+		let response = test.ws_send_text(request).await;
+		let WsResponse::Subscription(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		assert!(response.message.topics.contains(&Topic::ConfidenceAchieved));
+		assert!(response.message.data_fields.contains(&DataField::Data));
+
+		let results = test
+			.clients
+			.publish(&Topic::ConfidenceAchieved, message.clone())
+			.await
+			.unwrap();
+		assert_eq!(results.len(), 1);
+		let published = test.ws_client.recv().await.unwrap();
+		let published: PublishMessage = serde_json::from_str(published.to_str().unwrap()).unwrap();
+		assert_eq!(published.block_number(), Some(1));
+
+		let request = r#"{"type":"unsubscribe","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0","message":{"topics":["confidence-achieved"]}}"#;
+		let response = test.ws_send_text(request).await;
+		let WsResponse::Subscription(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		assert!(response.message.topics.is_empty());
+
+		let results = test
+			.clients
+			.publish(&Topic::ConfidenceAchieved, message)
+			.await
+			.unwrap();
+		assert!(results.is_empty());
+	}
 }
@@ -0,0 +1,744 @@
+//! OpenAPI 3 specification of the API version 2.
+//!
+//! Schemas of the request and response types are derived from their serialized form with
+//! [`schemars`], and registered in the specification components under the type schema name.
+//! Specification is served on the `/v2/openapi.json` endpoint.
+
+use schemars::{gen::SchemaSettings, JsonSchema};
+use serde_json::{json, Map, Value};
+
+use super::types::{
+	ApplicationKeyRequest, ApplicationKeyResponse, Block, BlocksResponse, CellsResponse, DataField,
+	DataResponse, EstimateResponse, Header, ProofResponse, PublishMessage, Request, RowsResponse,
+	Status, SubmitResponse, Subscription, SubscriptionId, Topic, Transaction, Version, WsError,
+	WsResponse,
+};
+use crate::types::Submission;
+
+const OPENAPI_VERSION: &str = "3.0.3";
+
+fn schema_ref<T: JsonSchema>() -> Value {
+	json!({ "$ref": format!("#/components/schemas/{}", T::schema_name()) })
+}
+
+fn string() -> Value {
+	json!({ "type": "string" })
+}
+
+fn integer() -> Value {
+	json!({ "type": "integer", "minimum": 0 })
+}
+
+fn boolean() -> Value {
+	json!({ "type": "boolean" })
+}
+
+fn array(items: Value) -> Value {
+	json!({ "type": "array", "items": items })
+}
+
+fn string_enum(values: &[&str]) -> Value {
+	json!({ "type": "string", "enum": values })
+}
+
+fn hash() -> Value {
+	json!({ "type": "string", "pattern": "^0x[0-9a-f]{64}$" })
+}
+
+/// Schemas of the types used in operations, including the schemas of the types they contain
+fn schemas() -> Map<String, Value> {
+	let mut generator = SchemaSettings::openapi3().into_generator();
+
+	macro_rules! add_schemas {
+		($($schema:ty),* $(,)?) => {
+			$(generator.subschema_for::<$schema>();)*
+		};
+	}
+
+	add_schemas!(
+		Version,
+		Status,
+		Block,
+		Header,
+		DataResponse,
+		BlocksResponse,
+		CellsResponse,
+		RowsResponse,
+		ProofResponse,
+		Transaction,
+		SubmitResponse,
+		Submission,
+		EstimateResponse,
+		ApplicationKeyRequest,
+		ApplicationKeyResponse,
+		Topic,
+		DataField,
+		Subscription,
+		SubscriptionId,
+		Request,
+		WsResponse,
+		WsError,
+		PublishMessage,
+	);
+
+	generator
+		.take_definitions()
+		.into_iter()
+		.map(|(name, schema)| {
+			let schema = serde_json::to_value(schema).expect("Schema is serializable");
+			(name, schema)
+		})
+		.collect()
+}
+
+fn path_parameter(name: &str, schema: Value) -> Value {
+	json!({ "name": name, "in": "path", "required": true, "schema": schema })
+}
+
+fn query_parameter(name: &str, required: bool, schema: Value) -> Value {
+	json!({ "name": name, "in": "query", "required": required, "schema": schema })
+}
+
+/// Comma separated list query parameter
+fn list_parameter(name: &str, required: bool, items: Value) -> Value {
+	json!({
+		"name": name,
+		"in": "query",
+		"required": required,
+		"style": "form",
+		"explode": false,
+		"schema": array(items),
+	})
+}
+
+fn block_number() -> Value {
+	path_parameter("block_number", integer())
+}
+
+fn json_body(schema: Value) -> Value {
+	json!({ "required": true, "content": { "application/json": { "schema": schema } } })
+}
+
+fn json_response(description: &str, schema: Value) -> Value {
+	json!({ "description": description, "content": { "application/json": { "schema": schema } } })
+}
+
+/// Error responses contain the error message in the body
+fn error_response(description: &str) -> Value {
+	json!({ "description": description, "content": { "text/plain": { "schema": string() } } })
+}
+
+fn operation(summary: &str, parameters: Vec<Value>, responses: Vec<(&str, Value)>) -> Value {
+	let responses = responses
+		.into_iter()
+		.map(|(code, response)| (code.to_string(), response))
+		.collect::<Map<_, _>>();
+	json!({ "summary": summary, "parameters": parameters, "responses": responses })
+}
+
+fn submit_parameters() -> Vec<Value> {
+	vec![
+		query_parameter("key", false, string()),
+		query_parameter("app_id", false, integer()),
+	]
+}
+
+/// Operations as `(method, path, operation)`, with the path parameters in `{name}` format
+pub fn operations() -> Vec<(&'static str, &'static str, Value)> {
+	let not_found = || error_response("Not Found");
+	let bad_request = || error_response("Bad Request");
+	let forbidden = || error_response("Forbidden");
+
+	let blocks = operation(
+		"Gets status and confidence for the range of blocks",
+		vec![
+			query_parameter("from", true, integer()),
+			query_parameter("to", false, integer()),
+			list_parameter(
+				"fields",
+				false,
+				string_enum(&["header", "data", "extrinsic"]),
+			),
+			query_parameter("cursor", false, integer()),
+			query_parameter(
+				"limit",
+				false,
+				json!({ "type": "integer", "minimum": 1, "maximum": 1000 }),
+			),
+		],
+		vec![
+			("200", json_response("OK", schema_ref::<BlocksResponse>())),
+			("400", bad_request()),
+		],
+	);
+
+	let mut submit = operation(
+		"Submits data or signed extrinsic",
+		[
+			submit_parameters(),
+			vec![query_parameter("async", false, boolean())],
+		]
+		.concat(),
+		vec![
+			("200", json_response("OK", schema_ref::<SubmitResponse>())),
+			("202", json_response("Accepted", schema_ref::<Submission>())),
+			("400", bad_request()),
+			("403", forbidden()),
+			("404", not_found()),
+		],
+	);
+	submit["requestBody"] = json_body(schema_ref::<Transaction>());
+
+	let mut estimate = operation(
+		"Estimates fee and weight of the transaction, and checks its validity",
+		submit_parameters(),
+		vec![
+			("200", json_response("OK", schema_ref::<EstimateResponse>())),
+			("400", bad_request()),
+			("403", forbidden()),
+			("404", not_found()),
+		],
+	);
+	estimate["requestBody"] = json_body(schema_ref::<Transaction>());
+
+	let mut applications = operation(
+		"Creates application key",
+		vec![query_parameter("key", false, string())],
+		vec![
+			(
+				"200",
+				json_response("OK", schema_ref::<ApplicationKeyResponse>()),
+			),
+			("400", bad_request()),
+			("403", forbidden()),
+			("404", not_found()),
+		],
+	);
+	applications["requestBody"] = json_body(schema_ref::<ApplicationKeyRequest>());
+
+	let mut subscriptions = operation(
+		"Creates subscription for the web socket connection",
+		vec![],
+		vec![
+			("200", json_response("OK", schema_ref::<SubscriptionId>())),
+			("400", bad_request()),
+			("503", error_response("Service Unavailable")),
+		],
+	);
+	subscriptions["requestBody"] = json_body(schema_ref::<Subscription>());
+
+	let events = json!({
+		"description": "OK",
+		"content": { "text/event-stream": { "schema": schema_ref::<PublishMessage>() } },
+	});
+
+	let mut ws = operation(
+		"Upgrades to the web socket connection",
+		vec![path_parameter("subscription_id", string())],
+		vec![
+			("101", json!({ "description": "Switching Protocols" })),
+			("404", not_found()),
+		],
+	);
+	ws["description"] = json!(format!(
+		"Client sends {} messages, and receives {}, {} and {} messages.",
+		Request::schema_name(),
+		WsResponse::schema_name(),
+		WsError::schema_name(),
+		PublishMessage::schema_name(),
+	));
+
+	// JSON-RPC request and response schemas depend on the method, see the API documentation
+	let jsonrpc_message =
+		json!({ "oneOf": [{ "type": "object" }, array(json!({ "type": "object" }))] });
+	let mut jsonrpc = operation(
+		"Calls the JSON-RPC 2.0 method, or a batch of methods",
+		vec![],
+		vec![
+			("200", json_response("OK", jsonrpc_message.clone())),
+			("204", json!({ "description": "No Content" })),
+		],
+	);
+	jsonrpc["requestBody"] = json_body(jsonrpc_message);
+
+	let jsonrpc_ws = operation(
+		"Upgrades to the JSON-RPC 2.0 web socket connection, with the topic subscriptions",
+		vec![],
+		vec![("101", json!({ "description": "Switching Protocols" }))],
+	);
+
+	vec![
+		(
+			"get",
+			"/v2/version",
+			operation(
+				"Gets the version of the light client and the compatible network",
+				vec![],
+				vec![("200", json_response("OK", schema_ref::<Version>()))],
+			),
+		),
+		(
+			"get",
+			"/v2/status",
+			operation(
+				"Gets the current status of the light client",
+				vec![],
+				vec![("200", json_response("OK", schema_ref::<Status>()))],
+			),
+		),
+		("get", "/v2/blocks", blocks),
+		(
+			"get",
+			"/v2/blocks/{block_number}",
+			operation(
+				"Gets the block status and confidence",
+				vec![block_number()],
+				vec![
+					("200", json_response("OK", schema_ref::<Block>())),
+					("404", not_found()),
+				],
+			),
+		),
+		(
+			"get",
+			"/v2/blocks/{block_number}/header",
+			operation(
+				"Gets the block header",
+				vec![block_number()],
+				vec![
+					("200", json_response("OK", schema_ref::<Header>())),
+					("400", bad_request()),
+					("404", not_found()),
+				],
+			),
+		),
+		(
+			"get",
+			"/v2/blocks/{block_number}/data",
+			operation(
+				"Gets the block application data",
+				vec![
+					block_number(),
+					list_parameter("fields", false, schema_ref::<DataField>()),
+				],
+				vec![
+					("200", json_response("OK", schema_ref::<DataResponse>())),
+					("400", bad_request()),
+					("404", not_found()),
+				],
+			),
+		),
+		(
+			"get",
+			"/v2/blocks/{block_number}/cells",
+			operation(
+				"Gets the cells with their proofs",
+				vec![block_number(), list_parameter("positions", true, string())],
+				vec![
+					("200", json_response("OK", schema_ref::<CellsResponse>())),
+					("400", bad_request()),
+					("404", not_found()),
+				],
+			),
+		),
+		(
+			"get",
+			"/v2/blocks/{block_number}/rows",
+			operation(
+				"Gets the rows of the extended matrix with their cell proofs",
+				vec![block_number(), list_parameter("rows", true, integer())],
+				vec![
+					("200", json_response("OK", schema_ref::<RowsResponse>())),
+					("400", bad_request()),
+					("404", not_found()),
+				],
+			),
+		),
+		(
+			"get",
+			"/v2/blocks/{block_number}/proof",
+			operation(
+				"Gets the cells with the application data and their proofs, needed to prove it",
+				vec![
+					block_number(),
+					query_parameter("app_id", true, integer()),
+					query_parameter("index", false, integer()),
+				],
+				vec![
+					("200", json_response("OK", schema_ref::<ProofResponse>())),
+					("400", bad_request()),
+					("404", not_found()),
+				],
+			),
+		),
+		("post", "/v2/submit", submit),
+		("post", "/v2/submit/estimate", estimate),
+		("post", "/v2/applications", applications),
+		(
+			"get",
+			"/v2/submissions/{hash}",
+			operation(
+				"Gets the status of the transaction submitted asynchronously",
+				vec![path_parameter("hash", hash())],
+				vec![
+					("200", json_response("OK", schema_ref::<Submission>())),
+					("404", not_found()),
+				],
+			),
+		),
+		("post", "/v2/subscriptions", subscriptions),
+		(
+			"delete",
+			"/v2/subscriptions/{subscription_id}",
+			operation(
+				"Removes the subscription",
+				vec![path_parameter("subscription_id", string())],
+				vec![
+					("204", json!({ "description": "No Content" })),
+					("404", not_found()),
+				],
+			),
+		),
+		(
+			"get",
+			"/v2/events",
+			operation(
+				"Streams messages published on the given topics",
+				vec![
+					list_parameter("topics", true, schema_ref::<Topic>()),
+					list_parameter("data_fields", false, schema_ref::<DataField>()),
+				],
+				vec![("200", events), ("400", bad_request())],
+			),
+		),
+		("get", "/v2/ws/{subscription_id}", ws),
+		("post", "/v2/rpc", jsonrpc),
+		("get", "/v2/rpc/ws", jsonrpc_ws),
+		(
+			"get",
+			"/v2/openapi.json",
+			operation(
+				"Gets the OpenAPI specification",
+				vec![],
+				vec![("200", json_response("OK", json!({ "type": "object" })))],
+			),
+		),
+	]
+}
+
+/// Builds the OpenAPI document, with the light client version as the API version
+pub fn document(version: &str) -> Value {
+	let mut paths = Map::new();
+	for (method, path, operation) in operations() {
+		let path = paths.entry(path).or_insert_with(|| json!({}));
+		path[method] = operation;
+	}
+
+	json!({
+		"openapi": OPENAPI_VERSION,
+		"info": {
+			"title": "Avail Light Client API",
+			"version": version,
+		},
+		"paths": paths,
+		"components": {
+			"schemas": schemas(),
+			"securitySchemes": {
+				"bearer": { "type": "http", "scheme": "bearer" },
+			},
+		},
+		// Authorization is required only if tokens or HMAC secret are configured
+		"security": [{}, { "bearer": [] }],
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{document, operations, schemas};
+	use crate::{
+		api::v2::types::{
+			Block, BlockStatus, BlocksResponse, DataResponse, EstimateResponse, Header,
+			PublishMessage, Request, Status, SubmitResponse, Subscription, Transaction, Version,
+			WsError, WsResponse,
+		},
+		types::Submission,
+	};
+	use schemars::JsonSchema;
+	use serde::{de::DeserializeOwned, Serialize};
+	use serde_json::{json, Map, Value};
+
+	/// Validates the value against the subset of JSON schema used in the specification
+	fn validate(schemas: &Map<String, Value>, schema: &Value, value: &Value) -> Result<(), String> {
+		if value.is_null() && schema["nullable"] == json!(true) {
+			return Ok(());
+		}
+		if let Some(reference) = schema["$ref"].as_str() {
+			let name = reference.trim_start_matches("#/components/schemas/");
+			let schema = schemas.get(name).ok_or(format!("Unknown schema {name}"))?;
+			validate(schemas, schema, value)?;
+		}
+		for schema in schema["allOf"].as_array().into_iter().flatten() {
+			validate(schemas, schema, value)?;
+		}
+		if let Some(schemas_any_of) = schema["anyOf"].as_array() {
+			if !schemas_any_of
+				.iter()
+				.any(|schema| validate(schemas, schema, value).is_ok())
+			{
+				return Err(format!("{value} matches none of the schemas"));
+			}
+		}
+		if let Some(schemas_one_of) = schema["oneOf"].as_array() {
+			let matching = schemas_one_of
+				.iter()
+				.filter(|schema| validate(schemas, schema, value).is_ok())
+				.count();
+			if matching != 1 {
+				return Err(format!("{value} matches {matching} schemas instead of one"));
+			}
+		}
+		if let Some(values) = schema["enum"].as_array() {
+			if !values.contains(value) {
+				return Err(format!("{value} is not one of {values:?}"));
+			}
+		}
+		match schema["type"].as_str() {
+			Some("object") => {
+				let object = value
+					.as_object()
+					.ok_or(format!("{value} is not an object"))?;
+				let properties = schema["properties"]
+					.as_object()
+					.cloned()
+					.unwrap_or_default();
+				for required in schema["required"].as_array().into_iter().flatten() {
+					let required = required.as_str().unwrap();
+					if !object.contains_key(required) {
+						return Err(format!("Missing {required} in {value}"));
+					}
+				}
+				for (name, value) in object {
+					match properties.get(name) {
+						Some(schema) => validate(schemas, schema, value)?,
+						None if schema["additionalProperties"] == json!(false) => {
+							return Err(format!("Unexpected property {name}"))
+						},
+						None => (),
+					}
+				}
+				Ok(())
+			},
+			Some("array") => value
+				.as_array()
+				.ok_or(format!("{value} is not an array"))?
+				.iter()
+				.try_for_each(|item| validate(schemas, &schema["items"], item)),
+			Some("string") => value
+				.is_string()
+				.then_some(())
+				.ok_or(format!("{value} is not a string")),
+			Some("integer") => value
+				.is_u64()
+				.then_some(())
+				.ok_or(format!("{value} is not an unsigned integer")),
+			Some("number") => value
+				.is_number()
+				.then_some(())
+				.ok_or(format!("{value} is not a number")),
+			Some("boolean") => value
+				.is_boolean()
+				.then_some(())
+				.ok_or(format!("{value} is not a boolean")),
+			_ => Ok(()),
+		}
+	}
+
+	/// Checks that the sample is valid request of the type, and that it matches the schema
+	fn assert_request<T: JsonSchema + DeserializeOwned>(sample: Value) {
+		serde_json::from_value::<T>(sample.clone()).unwrap();
+		let schemas = schemas();
+		let schema = &schemas[&T::schema_name()];
+		if let Err(error) = validate(&schemas, schema, &sample) {
+			panic!("Invalid {} sample: {error}", T::schema_name());
+		}
+	}
+
+	/// Checks that the sample is serialized response of the type, and that it matches the schema
+	fn assert_response<T: JsonSchema + Serialize + DeserializeOwned>(sample: Value) {
+		let value = serde_json::from_value::<T>(sample.clone()).unwrap();
+		assert_eq!(serde_json::to_value(value).unwrap(), sample);
+		assert_request::<T>(sample);
+	}
+
+	const HASH: &str = "0x0000000000000000000000000000000000000000000000000000000000000001";
+
+	fn header() -> Value {
+		json!({
+			"hash": HASH,
+			"parent_hash": HASH,
+			"number": 1,
+			"state_root": HASH,
+			"extrinsics_root": HASH,
+			"extension": {
+				"rows": 1,
+				"cols": 4,
+				"data_root": HASH,
+				"commitments": [],
+				"app_lookup": { "size": 0, "index": [] },
+			},
+		})
+	}
+
+	#[test]
+	fn schemas_match_types() {
+		assert_response::<Version>(json!({ "version": "1.7.3", "network_version": "1.8" }));
+		assert_response::<Status>(json!({
+			"modes": ["light", "app"],
+			"app_id": 1,
+			"genesis_hash": HASH,
+			"network": "{host}/{system_version}/{spec_name}/{spec_version}",
+			"blocks": {
+				"latest": 30,
+				"available": { "first": 20, "last": 29 },
+				"historical_sync": { "synced": false, "available": { "first": 10, "last": 19 } },
+				"finality_sync": {
+					"synced": false,
+					"latest": 10,
+					"target": 30,
+					"blocks_per_second": 1.5,
+					"eta": 13,
+				},
+			},
+			"partition": "1/10",
+		}));
+		assert_response::<Block>(json!({ "status": "finished", "confidence": 93.75 }));
+		assert_response::<Block>(json!({ "status": "pending", "confidence": null }));
+		assert_response::<Header>(header());
+		assert_response::<DataResponse>(json!({
+			"block_number": 1,
+			"data_transactions": [{ "data": "ZGF0YQ==", "extrinsic": "ZXh0cmluc2lj" }],
+		}));
+		assert_response::<BlocksResponse>(json!({
+			"blocks": [{
+				"block_number": 1,
+				"status": "finished",
+				"confidence": 93.75,
+				"header": header(),
+				"data_transactions": [{ "data": "ZGF0YQ==" }],
+			}],
+			"next_cursor": 2,
+		}));
+		assert_response::<SubmitResponse>(json!({ "block_hash": HASH, "hash": HASH, "index": 0 }));
+		assert_response::<Submission>(
+			json!({ "hash": HASH, "status": "in-block", "block_hash": HASH }),
+		);
+		assert_response::<EstimateResponse>(json!({
+			"fee": "124000000000000000",
+			"weight": { "ref_time": 1000, "proof_size": 0 },
+			"class": "normal",
+			"valid": true,
+		}));
+		assert_response::<EstimateResponse>(json!({
+			"fee": "124000000000000000",
+			"weight": { "ref_time": 1000, "proof_size": 0 },
+			"class": "normal",
+			"valid": null,
+			"error": "Dry run is unavailable: Method not found",
+		}));
+		assert_response::<Subscription>(json!({
+			"topics": ["data-verified"],
+			"data_fields": ["data"],
+			"from_block": 10,
+		}));
+		assert_response::<PublishMessage>(json!({
+			"topic": "confidence-achieved",
+			"message": { "block_number": 1, "confidence": 93.75 },
+		}));
+		assert_response::<PublishMessage>(json!({
+			"topic": "header-verified",
+			"message": { "block_number": 1, "header": header() },
+		}));
+		assert_response::<WsResponse>(json!({
+			"topic": "submission-status",
+			"request_id": "cae63f2b-1ba9-4a4a-a0e8-cd3b6bf4c83f",
+			"message": { "hash": HASH, "status": "submitted" },
+		}));
+		assert_response::<WsResponse>(json!({
+			"topic": "block",
+			"request_id": "cae63f2b-1ba9-4a4a-a0e8-cd3b6bf4c83f",
+			"message": { "status": "finished", "confidence": 93.75 },
+		}));
+		assert_response::<WsResponse>(json!({
+			"topic": "subscription",
+			"request_id": "cae63f2b-1ba9-4a4a-a0e8-cd3b6bf4c83f",
+			"message": { "topics": ["data-verified"], "data_fields": ["data"] },
+		}));
+		assert_response::<WsError>(json!({
+			"topic": "error",
+			"error_code": "bad-request",
+			"message": "Failed to parse request",
+		}));
+
+		assert_request::<Transaction>(json!({ "data": "ZGF0YQ==" }));
+		assert_request::<Transaction>(json!({ "extrinsic": "ZXh0cmluc2lj" }));
+		assert_request::<Request>(json!({
+			"type": "submit",
+			"request_id": "cae63f2b-1ba9-4a4a-a0e8-cd3b6bf4c83f",
+			"app_id": 2,
+			"message": { "data": "ZGF0YQ==" },
+		}));
+		assert_request::<Request>(json!({
+			"type": "version",
+			"request_id": "cae63f2b-1ba9-4a4a-a0e8-cd3b6bf4c83f",
+		}));
+		assert_request::<Request>(json!({
+			"type": "block-data",
+			"request_id": "cae63f2b-1ba9-4a4a-a0e8-cd3b6bf4c83f",
+			"message": { "block_number": 1, "fields": ["data"] },
+		}));
+		assert_request::<Request>(json!({
+			"type": "subscribe",
+			"request_id": "cae63f2b-1ba9-4a4a-a0e8-cd3b6bf4c83f",
+			"message": { "topics": ["header-verified"], "data_fields": ["extrinsic"] },
+		}));
+	}
+
+	#[test]
+	fn schema_rejects_unknown_values() {
+		let schemas = schemas();
+		let block_status = &schemas[&BlockStatus::schema_name()];
+		assert!(validate(&schemas, block_status, &json!("finalized")).is_err());
+		let version = &schemas[&Version::schema_name()];
+		assert!(validate(&schemas, version, &json!({ "version": "1.7.3" })).is_err());
+		let transaction = &schemas[&Transaction::schema_name()];
+		let transaction_value = json!({ "data": "ZGF0YQ==", "extrinsic": "ZXh0cmluc2lj" });
+		assert!(validate(&schemas, transaction, &transaction_value).is_err());
+	}
+
+	#[test]
+	fn references_are_defined() {
+		let document = document("1.7.3");
+		let schemas = document["components"]["schemas"].as_object().unwrap();
+
+		fn references(value: &Value, result: &mut Vec<String>) {
+			match value {
+				Value::Object(object) => {
+					if let Some(Value::String(reference)) = object.get("$ref") {
+						result.push(reference.clone());
+					}
+					object.values().for_each(|value| references(value, result));
+				},
+				Value::Array(array) => array.iter().for_each(|value| references(value, result)),
+				_ => (),
+			}
+		}
+
+		let mut result = vec![];
+		references(&document, &mut result);
+		assert!(!result.is_empty());
+		for reference in result {
+			let name = reference.trim_start_matches("#/components/schemas/");
+			assert!(schemas.contains_key(name), "Missing schema {name}");
+		}
+	}
+}
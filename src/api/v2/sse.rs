@@ -0,0 +1,59 @@
+use super::types::{DataField, Event, Topic};
+use futures::{future, stream, Stream, StreamExt};
+use std::{collections::HashSet, convert::Infallible, sync::Arc};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tracing::{debug, error};
+use warp::sse;
+
+fn sse_event(
+	event: &Event,
+	topics: &HashSet<Topic>,
+	data_fields: &HashSet<DataField>,
+) -> Option<sse::Event> {
+	if !topics.contains(&event.topic) {
+		return None;
+	}
+
+	let mut message = event.message.clone();
+	message.apply_filter(data_fields);
+
+	let topic = serde_json::to_value(event.topic)
+		.ok()
+		.and_then(|topic| topic.as_str().map(ToString::to_string))?;
+
+	match sse::Event::default()
+		.id(event.id.to_string())
+		.event(topic)
+		.json_data(&message)
+	{
+		Ok(sse_event) => Some(sse_event),
+		Err(error) => {
+			error!(%event.id, "Cannot serialize event: {error}");
+			None
+		},
+	}
+}
+
+/// Creates stream of buffered events, followed by newly published events.
+/// Stream ends if the client falls behind, so it can reconnect and resume from the last received event.
+pub fn events_stream(
+	buffered: Vec<Arc<Event>>,
+	receiver: broadcast::Receiver<Arc<Event>>,
+	topics: HashSet<Topic>,
+	data_fields: HashSet<DataField>,
+) -> impl Stream<Item = Result<sse::Event, Infallible>> + Send + 'static {
+	let received = BroadcastStream::new(receiver)
+		.take_while(|result| {
+			let is_lagged = matches!(result, Err(BroadcastStreamRecvError::Lagged(_)));
+			if is_lagged {
+				debug!("Server-sent events client lagged behind, closing the stream");
+			}
+			future::ready(!is_lagged)
+		})
+		.filter_map(|result| future::ready(result.ok()));
+
+	stream::iter(buffered)
+		.chain(received)
+		.filter_map(move |event| future::ready(sse_event(&event, &topics, &data_fields).map(Ok)))
+}
@@ -1,15 +1,83 @@
-use crate::{network::rpc, types::AvailSecretKey};
+use crate::{
+	data::Database,
+	keystore::{Keystore, DEFAULT_KEY},
+	network::rpc::{self, SubmitCall},
+	types::{AvailSecretKey, RuntimeConfig, Submission, SubmissionStatus},
+	utils::unix_timestamp,
+};
 
-use super::types::{SubmitResponse, Transaction};
+use super::types::{ApplicationKeyResponse, EstimateResponse, SubmitResponse, Transaction};
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
-use avail_subxt::{api, primitives::AvailExtrinsicParams, AvailConfig};
-use subxt::{ext::sp_core::sr25519::Pair, tx::PairSigner};
+use avail_subxt::{
+	api::{
+		self,
+		data_availability::events::ApplicationKeyCreated,
+		runtime_types::{
+			bounded_collections::bounded_vec::BoundedVec, da_control::pallet::Call,
+			da_runtime::RuntimeCall,
+		},
+	},
+	primitives::AvailExtrinsicParams,
+	AvailConfig,
+};
+use codec::Encode;
+use std::{
+	collections::{HashMap, VecDeque},
+	iter,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+};
+use subxt::{
+	ext::sp_core::sr25519::Pair,
+	tx::{PairSigner, TxProgress, TxStatus},
+	OnlineClient,
+};
+use tokio::{
+	sync::{broadcast, mpsc, oneshot, watch},
+	time::{self, Duration},
+};
+use tracing::{debug, error, warn};
 
+/// Submits transactions, signing data transactions with the named key, or with the default one if not set
 #[async_trait]
 pub trait Submit {
-	async fn submit(&self, transaction: Transaction) -> Result<SubmitResponse>;
-	fn has_signer(&self) -> bool;
+	async fn submit(
+		&self,
+		transaction: Transaction,
+		key: Option<&str>,
+		app_id: Option<u32>,
+	) -> Result<SubmitResponse>;
+	/// Submits transaction without waiting for finalization.
+	/// Transaction status is tracked in the background, and persisted on each change.
+	async fn submit_async(
+		&self,
+		transaction: Transaction,
+		key: Option<&str>,
+		app_id: Option<u32>,
+	) -> Result<Submission>;
+	/// Estimates the fee and weight of the transaction, and checks its validity with a dry run.
+	/// Data transaction is signed the same way as when submitted, but it is not submitted.
+	async fn estimate(
+		&self,
+		transaction: Transaction,
+		key: Option<&str>,
+		app_id: Option<u32>,
+	) -> Result<EstimateResponse>;
+	/// Registers the application key, returning the assigned application ID
+	async fn create_application_key(
+		&self,
+		name: Vec<u8>,
+		key: Option<&str>,
+	) -> Result<ApplicationKeyResponse>;
+	/// Checks if the signing key is loaded and allowed to be used
+	fn has_signer(&self, key: Option<&str>) -> bool;
+	/// Maximum size of the data blob in bytes
+	fn max_blob_size(&self) -> usize;
+	/// Application ID used for data transactions if it is not set in the request
+	fn default_app_id(&self) -> Option<u32>;
 }
 
 // TODO: Replace this with avail::PairSigner after implementing required traits in avail-subxt
@@ -21,45 +89,811 @@ impl From<AvailSecretKey> for AvailSigner {
 	}
 }
 
+type Progress = TxProgress<AvailConfig, OnlineClient<AvailConfig>>;
+
+/// Node errors returned when the transaction nonce doesn't match the account nonce
+const NONCE_ERRORS: [&str; 3] = [
+	"Transaction is outdated",
+	"Transaction will be valid in the future",
+	"Priority is too low",
+];
+
+fn is_nonce_error(error: &anyhow::Error) -> bool {
+	let error = format!("{error:#}");
+	NONCE_ERRORS.iter().any(|message| error.contains(message))
+}
+
+/// Data blob waiting in the submission queue
+struct Blob {
+	data: Vec<u8>,
+	app_id: u32,
+	is_async: bool,
+	response_sender: oneshot::Sender<Result<watch::Receiver<Submission>>>,
+}
+
+impl Blob {
+	/// Size of the blob encoded as `submit_data` call in the batch,
+	/// including the pallet and call indices
+	fn encoded_size(&self) -> usize {
+		2 + self.data.encoded_size()
+	}
+}
+
+/// Transaction waiting in the submission queue
+enum Queued {
+	Data(Blob),
+	CreateApplicationKey {
+		name: Vec<u8>,
+		response_sender: oneshot::Sender<Result<Progress>>,
+	},
+}
+
 #[derive(Clone)]
-pub struct Submitter {
-	pub node_client: rpc::Client,
-	pub app_id: u32,
-	pub pair_signer: Option<AvailSigner>,
+pub struct Submitter<T: Database> {
+	node_client: rpc::Client,
+	app_id: Option<u32>,
+	max_blob_size: usize,
+	tip: u64,
+	/// Submission queues of the allowed signing keys
+	queue_senders: HashMap<String, mpsc::Sender<Queued>>,
+	/// Allowed signing keys, used to sign transactions for the estimation
+	signers: HashMap<String, AvailSigner>,
+	tracker: Tracker<T>,
 }
 
-#[async_trait]
-impl Submit for Submitter {
-	async fn submit(&self, transaction: Transaction) -> Result<SubmitResponse> {
-		let tx_progress = match transaction {
+impl<T: Database> Submitter<T> {
+	/// Creates submitter, spawning the data submission queue for each allowed signing key
+	pub fn new(
+		node_client: rpc::Client,
+		keystore: &Keystore,
+		db: T,
+		submission_sender: broadcast::Sender<Submission>,
+		config: &RuntimeConfig,
+	) -> Self {
+		let tracker = Tracker {
+			db,
+			submission_sender,
+		};
+		if let Err(error) = tracker.drop_interrupted() {
+			error!("Cannot update submissions interrupted by restart: {error:#}");
+		}
+		tokio::spawn(
+			tracker
+				.clone()
+				.remove_expired_periodically(config.submit_retention),
+		);
+
+		for name in &config.submit_keys {
+			if keystore.get(name).is_none() {
+				warn!("Allowed signing key {name} is not loaded");
+			}
+		}
+
+		let allowed_keys = [DEFAULT_KEY.to_string()]
+			.into_iter()
+			.chain(config.submit_keys.iter().cloned())
+			.filter_map(|name| keystore.get(&name).cloned().map(|key| (name, key)));
+
+		let mut queue_senders = HashMap::new();
+		let mut signers = HashMap::new();
+		for (name, key) in allowed_keys {
+			let pair_signer = AvailSigner::from(key);
+			let (queue_sender, queue_receiver) = mpsc::channel(1 << 10);
+			let queue = Queue {
+				node_client: node_client.clone(),
+				pair_signer: pair_signer.clone(),
+				batch_size: config.submit_batch_size.max(1),
+				max_batch_bytes: config.submit_max_batch_bytes,
+				tip: config.submit_tip,
+				retries: config.submit_retries,
+				nonce: Nonce::default(),
+				tracker: tracker.clone(),
+			};
+			tokio::spawn(queue.run(queue_receiver));
+			queue_senders.insert(name.clone(), queue_sender);
+			signers.insert(name, pair_signer);
+		}
+
+		Submitter {
+			node_client,
+			app_id: config.app_id,
+			max_blob_size: config.submit_max_blob_size,
+			tip: config.submit_tip,
+			queue_senders,
+			signers,
+			tracker,
+		}
+	}
+
+	async fn enqueue(&self, key: Option<&str>, queued: Queued) -> Result<()> {
+		let key = key.unwrap_or(DEFAULT_KEY);
+		let Some(queue_sender) = self.queue_senders.get(key) else {
+			return Err(anyhow!("Signing key {key} is not configured"));
+		};
+		queue_sender
+			.send(queued)
+			.await
+			.context("Submission queue receiver not to be dropped")
+	}
+
+	async fn submit_and_watch(
+		&self,
+		transaction: Transaction,
+		key: Option<&str>,
+		app_id: Option<u32>,
+		is_async: bool,
+	) -> Result<watch::Receiver<Submission>> {
+		match transaction {
 			Transaction::Data(data) => {
-				let Some(pair_signer) = self.pair_signer.as_ref() else {
-					return Err(anyhow!("Pair signer is not configured"));
+				let app_id = app_id.or(self.app_id).context("App ID is not set")?;
+				let (response_sender, response_receiver) = oneshot::channel();
+				let blob = Blob {
+					data: data.into(),
+					app_id,
+					is_async,
+					response_sender,
 				};
-				let extrinsic = api::tx().data_availability().submit_data(data.into());
-				let params = AvailExtrinsicParams::new_with_app_id(self.app_id.into());
-				self.node_client
-					.submit_signed_and_watch(extrinsic, pair_signer.to_owned(), params)
-					.await?
+				self.enqueue(key, Queued::Data(blob)).await?;
+				response_receiver
+					.await
+					.context("Submission queue sender not to be dropped")?
 			},
 			Transaction::Extrinsic(extrinsic) => {
-				self.node_client
+				let tx_progress = self
+					.node_client
 					.submit_from_bytes_and_watch(extrinsic.into())
-					.await?
+					.await?;
+				self.tracker.watch(tx_progress, is_async, None)
+			},
+		}
+	}
+}
+
+/// Creates data submission call, packing multiple blobs with `utility.batch_all`
+fn submit_call(mut data: Vec<Vec<u8>>) -> SubmitCall {
+	if data.len() == 1 {
+		let data = data.remove(0);
+		return SubmitCall::Data(api::tx().data_availability().submit_data(BoundedVec(data)));
+	}
+
+	let calls = data
+		.into_iter()
+		.map(|data| {
+			RuntimeCall::DataAvailability(Call::submit_data {
+				data: BoundedVec(data),
+			})
+		})
+		.collect();
+	SubmitCall::BatchAll(api::tx().utility().batch_all(calls))
+}
+
+/// Takes queued blobs for the same application as the first one, up to the batch size
+/// and the maximum encoded size of the batch. Returns the batch and the rest of the queue.
+/// Once the batch is full, remaining blobs stay queued in order.
+fn batch(
+	first: Blob,
+	pending: VecDeque<Queued>,
+	batch_size: usize,
+	max_batch_bytes: usize,
+) -> (Vec<Blob>, VecDeque<Queued>) {
+	let app_id = first.app_id;
+	let mut batch_bytes = first.encoded_size();
+	let mut blobs = vec![first];
+	let mut is_full = blobs.len() >= batch_size;
+	let mut rest = VecDeque::new();
+	for queued in pending {
+		match queued {
+			Queued::Data(blob) if blob.app_id == app_id && !is_full => {
+				let size = blob.encoded_size();
+				if batch_bytes + size > max_batch_bytes {
+					is_full = true;
+					rest.push_back(Queued::Data(blob));
+					continue;
+				}
+				batch_bytes += size;
+				blobs.push(blob);
+				is_full = blobs.len() >= batch_size;
+			},
+			queued => rest.push_back(queued),
+		}
+	}
+	(blobs, rest)
+}
+
+/// Locally tracked account nonce, fetched from the chain again after it is reset
+#[derive(Default)]
+struct Nonce {
+	next: Option<u32>,
+	is_reset: Arc<AtomicBool>,
+}
+
+impl Nonce {
+	/// Returns the next nonce, if it is known and not reset in the meantime
+	fn next(&mut self) -> Option<u32> {
+		if self.is_reset.swap(false, Ordering::Relaxed) {
+			self.next = None;
+		}
+		self.next
+	}
+
+	fn set(&mut self, next: Option<u32>) {
+		self.next = next;
+	}
+
+	fn reset_handle(&self) -> NonceReset {
+		NonceReset(self.is_reset.clone())
+	}
+}
+
+/// Resets the tracked nonce when the submitted transaction leaves the pool without being included,
+/// since its nonce is not used and later transactions would wait for it in the future queue
+#[derive(Clone)]
+struct NonceReset(Arc<AtomicBool>);
+
+impl NonceReset {
+	fn on_status(&self, status: &SubmissionStatus) {
+		if matches!(
+			status,
+			SubmissionStatus::Dropped | SubmissionStatus::Invalid
+		) {
+			self.0.store(true, Ordering::Relaxed);
+		}
+	}
+}
+
+/// Submits queued transactions using the locally tracked account nonce.
+/// Data blobs for the same application queued in the meantime are packed into a single
+/// `utility.batch_all` transaction, up to the batch size and the maximum encoded size of the batch.
+struct Queue<T: Database> {
+	node_client: rpc::Client,
+	pair_signer: AvailSigner,
+	batch_size: usize,
+	max_batch_bytes: usize,
+	tip: u64,
+	retries: usize,
+	/// Next account nonce, fetched from the chain if not known
+	nonce: Nonce,
+	tracker: Tracker<T>,
+}
+
+impl<T: Database> Queue<T> {
+	async fn run(mut self, mut queue_receiver: mpsc::Receiver<Queued>) {
+		let mut pending = VecDeque::new();
+		loop {
+			pending.extend(iter::from_fn(|| queue_receiver.try_recv().ok()));
+			let queued = match pending.pop_front() {
+				Some(queued) => queued,
+				None => match queue_receiver.recv().await {
+					Some(queued) => queued,
+					None => return,
+				},
+			};
+
+			match queued {
+				Queued::CreateApplicationKey {
+					name,
+					response_sender,
+				} => {
+					let call = || {
+						let name = BoundedVec(name.clone());
+						SubmitCall::CreateApplicationKey(
+							api::tx().data_availability().create_application_key(name),
+						)
+					};
+					let result = self.submit(0, call).await;
+					// Sending fails only if the submitter stopped waiting for the response
+					let _ = response_sender.send(result);
+				},
+				Queued::Data(blob) => {
+					let app_id = blob.app_id;
+					let (blobs, rest) = batch(blob, pending, self.batch_size, self.max_batch_bytes);
+					pending = rest;
+					self.submit_blobs(app_id, blobs).await;
+				},
+			}
+		}
+	}
+
+	async fn submit_blobs(&mut self, app_id: u32, blobs: Vec<Blob>) {
+		let is_async = blobs.iter().any(|blob| blob.is_async);
+		let data = blobs
+			.iter()
+			.map(|blob| blob.data.clone())
+			.collect::<Vec<_>>();
+		let result = match self.submit(app_id, || submit_call(data.clone())).await {
+			Ok(tx_progress) => {
+				let nonce_reset = self.nonce.reset_handle();
+				self.tracker.watch(tx_progress, is_async, Some(nonce_reset))
 			},
+			Err(error) => Err(error),
 		};
-		tx_progress
+
+		for blob in blobs {
+			let result = match &result {
+				Ok(receiver) => Ok(receiver.clone()),
+				Err(error) => Err(anyhow!("{error:#}")),
+			};
+			// Sending fails only if the submitter stopped waiting for the response
+			let _ = blob.response_sender.send(result);
+		}
+	}
+
+	/// Submits transaction, resyncing the nonce from the chain and retrying if the nonce is rejected
+	async fn submit(&mut self, app_id: u32, call: impl Fn() -> SubmitCall) -> Result<Progress> {
+		let mut retries = 0;
+		loop {
+			let nonce = match self.nonce.next() {
+				Some(nonce) => nonce,
+				None => self
+					.node_client
+					.get_account_nonce(self.pair_signer.account_id().clone())
+					.await
+					.context("Cannot get account nonce")?,
+			};
+
+			let params = AvailExtrinsicParams {
+				tip: self.tip.into(),
+				..AvailExtrinsicParams::new_with_app_id(app_id.into())
+			};
+
+			let result = self
+				.node_client
+				.submit_signed_and_watch(call(), self.pair_signer.clone(), nonce, params)
+				.await;
+
+			match result {
+				Ok(tx_progress) => {
+					self.nonce.set(Some(nonce + 1));
+					return Ok(tx_progress);
+				},
+				Err(error) => {
+					// Nonce is fetched from the chain on the next submission
+					self.nonce.set(None);
+					if !is_nonce_error(&error) || retries >= self.retries {
+						return Err(error);
+					}
+					retries += 1;
+					warn!(
+						nonce,
+						retries, "Transaction nonce is rejected, retrying: {error:#}"
+					);
+				},
+			}
+		}
+	}
+}
+
+/// Updates submission according to the transaction status.
+/// Returns `false` if the submission is not changed.
+async fn update(
+	submission: &mut Submission,
+	status: TxStatus<AvailConfig, OnlineClient<AvailConfig>>,
+) -> bool {
+	let previous = submission.clone();
+	match status {
+		TxStatus::Future | TxStatus::Ready | TxStatus::Broadcast(_) => {
+			submission.status = SubmissionStatus::Ready;
+		},
+		TxStatus::Retracted(_) => {
+			submission.status = SubmissionStatus::Ready;
+			submission.block_hash = None;
+		},
+		TxStatus::InBlock(in_block) => {
+			submission.status = SubmissionStatus::InBlock;
+			submission.block_hash = Some(in_block.block_hash());
+		},
+		TxStatus::Finalized(in_block) => {
+			submission.status = SubmissionStatus::Finalized;
+			submission.block_hash = Some(in_block.block_hash());
+			match in_block.wait_for_success().await {
+				Ok(events) => submission.index = Some(events.extrinsic_index()),
+				Err(error) => submission.error = Some(format!("Transaction failed: {error}")),
+			}
+		},
+		TxStatus::FinalityTimeout(_) | TxStatus::Usurped(_) | TxStatus::Dropped => {
+			submission.status = SubmissionStatus::Dropped;
+		},
+		TxStatus::Invalid => submission.status = SubmissionStatus::Invalid,
+	}
+	*submission != previous
+}
+
+/// Interval of the removal of the submissions older than the configured retention
+const REMOVE_EXPIRED_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Watches submitted transactions, and persists and publishes status changes of the async ones
+#[derive(Clone)]
+struct Tracker<T: Database> {
+	db: T,
+	submission_sender: broadcast::Sender<Submission>,
+}
+
+impl<T: Database> Tracker<T> {
+	/// Marks submissions which were tracked before the restart as dropped,
+	/// since their status cannot be tracked anymore
+	fn drop_interrupted(&self) -> Result<()> {
+		for (_, mut submission) in self.db.get_submissions()? {
+			if submission.status.is_final() {
+				continue;
+			}
+			submission.status = SubmissionStatus::Dropped;
+			submission
+				.error
+				.get_or_insert_with(|| "Transaction status is unknown after restart".to_string());
+			self.db.store_submission(&submission)?;
+		}
+		Ok(())
+	}
+
+	/// Removes submissions which are no longer tracked, and weren't updated in the last
+	/// `retention` seconds before `now`
+	fn remove_expired(&self, now: u64, retention: u64) -> Result<usize> {
+		let mut removed = 0;
+		for (updated_at, submission) in self.db.get_submissions()? {
+			let is_tracked = !submission.status.is_final() && submission.error.is_none();
+			if is_tracked || updated_at.saturating_add(retention) > now {
+				continue;
+			}
+			self.db.remove_submission(submission.hash)?;
+			removed += 1;
+		}
+		Ok(removed)
+	}
+
+	async fn remove_expired_periodically(self, retention: u64) {
+		let mut interval = time::interval(REMOVE_EXPIRED_INTERVAL);
+		loop {
+			interval.tick().await;
+			match self.remove_expired(unix_timestamp(), retention) {
+				Ok(0) => (),
+				Ok(removed) => debug!("Removed {removed} expired submissions"),
+				Err(error) => error!("Cannot remove expired submissions: {error:#}"),
+			}
+		}
+	}
+
+	/// Tracks transaction status until it is final, sending each change to the returned receiver.
+	/// If `is_persisted` is set, changes are also stored and published.
+	/// If `nonce_reset` is set, the submission queue nonce is reset when transaction is dropped.
+	fn watch(
+		&self,
+		mut tx_progress: Progress,
+		is_persisted: bool,
+		nonce_reset: Option<NonceReset>,
+	) -> Result<watch::Receiver<Submission>> {
+		let mut submission = Submission::new(tx_progress.extrinsic_hash());
+		if is_persisted {
+			self.db
+				.store_submission(&submission)
+				.context("Cannot store submission")?;
+		}
+
+		let (sender, receiver) = watch::channel(submission.clone());
+		let Tracker {
+			db,
+			submission_sender,
+		} = self.clone();
+
+		tokio::spawn(async move {
+			let hash = submission.hash;
+			while let Some(result) = tx_progress.next_item().await {
+				let is_changed = match result {
+					Ok(status) => update(&mut submission, status).await,
+					Err(error) => {
+						submission.error =
+							Some(format!("Cannot track transaction status: {error}"));
+						true
+					},
+				};
+
+				if !is_changed {
+					continue;
+				}
+
+				debug!(?hash, status = ?submission.status, "Transaction status changed");
+				if let Some(nonce_reset) = nonce_reset.as_ref() {
+					nonce_reset.on_status(&submission.status);
+				}
+				if is_persisted {
+					if let Err(error) = db.store_submission(&submission) {
+						error!(?hash, "Cannot store submission: {error:#}");
+					}
+					// Sending fails only if there are no receivers
+					let _ = submission_sender.send(submission.clone());
+				}
+				// Sending fails only if the submission is not awaited
+				let _ = sender.send(submission.clone());
+
+				if submission.status.is_final() || submission.error.is_some() {
+					return;
+				}
+			}
+		});
+
+		Ok(receiver)
+	}
+}
+
+/// Waits until the transaction is finalized, and fails if it is not successful
+async fn wait_for_finalized_success(
+	mut receiver: watch::Receiver<Submission>,
+) -> Result<SubmitResponse> {
+	loop {
+		let submission = receiver.borrow_and_update().clone();
+		if let Some(error) = submission.error {
+			return Err(anyhow!(error));
+		}
+		match (submission.status, submission.block_hash, submission.index) {
+			(SubmissionStatus::Finalized, Some(block_hash), Some(index)) => {
+				return Ok(SubmitResponse {
+					block_hash,
+					hash: submission.hash,
+					index,
+				});
+			},
+			(status @ (SubmissionStatus::Dropped | SubmissionStatus::Invalid), ..) => {
+				return Err(anyhow!("Transaction is {status:?}"));
+			},
+			_ => (),
+		}
+		receiver
+			.changed()
+			.await
+			.context("Transaction status stream ended before finalization")?;
+	}
+}
+
+#[async_trait]
+impl<T: Database> Submit for Submitter<T> {
+	async fn submit(
+		&self,
+		transaction: Transaction,
+		key: Option<&str>,
+		app_id: Option<u32>,
+	) -> Result<SubmitResponse> {
+		let receiver = self
+			.submit_and_watch(transaction, key, app_id, false)
+			.await?;
+		wait_for_finalized_success(receiver)
+			.await
+			.context("Cannot sign and submit transaction")
+	}
+
+	async fn submit_async(
+		&self,
+		transaction: Transaction,
+		key: Option<&str>,
+		app_id: Option<u32>,
+	) -> Result<Submission> {
+		let receiver = self
+			.submit_and_watch(transaction, key, app_id, true)
+			.await?;
+		let submission = receiver.borrow().clone();
+		Ok(submission)
+	}
+
+	async fn estimate(
+		&self,
+		transaction: Transaction,
+		key: Option<&str>,
+		app_id: Option<u32>,
+	) -> Result<EstimateResponse> {
+		let (tx_bytes, tip) = match transaction {
+			Transaction::Data(data) => {
+				let app_id = app_id.or(self.app_id).context("App ID is not set")?;
+				let key = key.unwrap_or(DEFAULT_KEY);
+				let Some(pair_signer) = self.signers.get(key) else {
+					return Err(anyhow!("Signing key {key} is not configured"));
+				};
+				let nonce = self
+					.node_client
+					.get_account_nonce(pair_signer.account_id().clone())
+					.await
+					.context("Cannot get account nonce")?;
+				let params = AvailExtrinsicParams {
+					tip: self.tip.into(),
+					..AvailExtrinsicParams::new_with_app_id(app_id.into())
+				};
+				let call = submit_call(vec![data.into()]);
+				let tx_bytes = self
+					.node_client
+					.create_signed(call, pair_signer.clone(), nonce, params)
+					.await
+					.context("Cannot create signed transaction")?;
+				(tx_bytes, self.tip)
+			},
+			Transaction::Extrinsic(extrinsic) => (extrinsic.into(), 0),
+		};
+
+		let info = self
+			.node_client
+			.query_fee_info(tx_bytes.clone())
+			.await
+			.context("Cannot query transaction fee")?;
+		// Fee and weight are still returned if the dry run RPC is not enabled on the node
+		let outcome = self
+			.node_client
+			.dry_run(tx_bytes)
+			.await
+			.context("Cannot dry run transaction");
+		if let Err(error) = &outcome {
+			warn!("{error:#}");
+		}
+
+		Ok(EstimateResponse::new(info, outcome, tip.into()))
+	}
+
+	async fn create_application_key(
+		&self,
+		name: Vec<u8>,
+		key: Option<&str>,
+	) -> Result<ApplicationKeyResponse> {
+		let (response_sender, response_receiver) = oneshot::channel();
+		let queued = Queued::CreateApplicationKey {
+			name,
+			response_sender,
+		};
+		self.enqueue(key, queued).await?;
+
+		let events = response_receiver
+			.await
+			.context("Submission queue sender not to be dropped")??
 			.wait_for_finalized_success()
 			.await
-			.map(|event| SubmitResponse {
-				block_hash: event.block_hash(),
-				hash: event.extrinsic_hash(),
-				index: event.extrinsic_index(),
+			.context("Cannot create application key")?;
+
+		let event = events
+			.find_first::<ApplicationKeyCreated>()
+			.context("Cannot decode application key created event")?
+			.context("Application key created event is missing")?;
+
+		Ok(ApplicationKeyResponse {
+			app_id: event.id.0,
+			block_hash: events.block_hash(),
+			hash: events.extrinsic_hash(),
+		})
+	}
+
+	fn has_signer(&self, key: Option<&str>) -> bool {
+		self.queue_senders.contains_key(key.unwrap_or(DEFAULT_KEY))
+	}
+
+	fn max_blob_size(&self) -> usize {
+		self.max_blob_size
+	}
+
+	fn default_app_id(&self) -> Option<u32> {
+		self.app_id
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Blob, Nonce, Queued, Tracker};
+	use crate::{
+		data::{Database, MemoryDB},
+		types::{Submission, SubmissionStatus},
+	};
+	use anyhow::anyhow;
+	use avail_subxt::utils::H256;
+	use std::collections::VecDeque;
+	use test_case::test_case;
+	use tokio::sync::{broadcast, oneshot};
+
+	fn tracker(submissions: &[(u8, SubmissionStatus)]) -> Tracker<MemoryDB> {
+		let db = MemoryDB::default();
+		for &(hash, status) in submissions {
+			let submission = Submission {
+				status,
+				..Submission::new(H256::repeat_byte(hash))
+			};
+			db.store_submission(&submission).unwrap();
+		}
+		let (submission_sender, _) = broadcast::channel(1);
+		Tracker {
+			db,
+			submission_sender,
+		}
+	}
+
+	fn blob(app_id: u32, size: usize) -> Blob {
+		let (response_sender, _) = oneshot::channel();
+		Blob {
+			data: vec![0; size],
+			app_id,
+			is_async: false,
+			response_sender,
+		}
+	}
+
+	fn sizes(blobs: &[Blob]) -> Vec<(u32, usize)> {
+		blobs
+			.iter()
+			.map(|blob| (blob.app_id, blob.data.len()))
+			.collect()
+	}
+
+	// Encoded size of the 100 bytes blob is 104 bytes
+	#[test_case(10, 1000 => (vec![(1, 100), (1, 100), (1, 100)], vec![(2, 100)]) ; "Same application")]
+	#[test_case(2, 1000 => (vec![(1, 100), (1, 100)], vec![(2, 100), (1, 100)]) ; "Batch size")]
+	#[test_case(10, 208 => (vec![(1, 100), (1, 100)], vec![(2, 100), (1, 100)]) ; "Batch bytes")]
+	#[test_case(10, 50 => (vec![(1, 100)], vec![(1, 100), (2, 100), (1, 100)]) ; "Blob larger than batch bytes")]
+	fn batch(batch_size: usize, max_batch_bytes: usize) -> (Vec<(u32, usize)>, Vec<(u32, usize)>) {
+		let pending = [blob(1, 100), blob(2, 100), blob(1, 100)]
+			.into_iter()
+			.map(Queued::Data)
+			.collect::<VecDeque<_>>();
+		let (blobs, rest) = super::batch(blob(1, 100), pending, batch_size, max_batch_bytes);
+		let rest = rest
+			.into_iter()
+			.map(|queued| match queued {
+				Queued::Data(blob) => blob,
+				_ => panic!("Unexpected queued transaction"),
 			})
-			.context("Cannot sign and submit transaction")
+			.collect::<Vec<_>>();
+		(sizes(&blobs), sizes(&rest))
+	}
+
+	#[test_case("Invalid Transaction: Transaction is outdated" => true ; "Stale nonce")]
+	#[test_case("Invalid Transaction: Transaction will be valid in the future" => true ; "Future nonce")]
+	#[test_case("Priority is too low: (0 vs 0)" => true ; "Same nonce in pool")]
+	#[test_case("Invalid Transaction: Inability to pay some fees" => false ; "Other error")]
+	fn is_nonce_error(message: &str) -> bool {
+		let error = anyhow!("RPC error: {message}").context("Cannot submit transaction");
+		super::is_nonce_error(&error)
 	}
 
-	fn has_signer(&self) -> bool {
-		self.pair_signer.is_some()
+	#[test_case(SubmissionStatus::Ready => Some(5) ; "Ready")]
+	#[test_case(SubmissionStatus::Finalized => Some(5) ; "Finalized")]
+	#[test_case(SubmissionStatus::Dropped => None ; "Dropped")]
+	#[test_case(SubmissionStatus::Invalid => None ; "Invalid")]
+	fn nonce_reset(status: SubmissionStatus) -> Option<u32> {
+		let mut nonce = Nonce::default();
+		nonce.set(Some(5));
+		nonce.reset_handle().on_status(&status);
+		nonce.next()
+	}
+
+	#[test]
+	fn nonce_reset_once() {
+		let mut nonce = Nonce::default();
+		nonce.set(Some(5));
+		nonce.reset_handle().on_status(&SubmissionStatus::Dropped);
+		assert_eq!(nonce.next(), None);
+		nonce.set(Some(3));
+		assert_eq!(nonce.next(), Some(3));
+	}
+
+	#[test]
+	fn drop_interrupted() {
+		let tracker = tracker(&[
+			(1, SubmissionStatus::InBlock),
+			(2, SubmissionStatus::Finalized),
+		]);
+		tracker.drop_interrupted().unwrap();
+
+		let hash = H256::repeat_byte(1);
+		let interrupted = tracker.db.get_submission(hash).unwrap().unwrap();
+		assert_eq!(interrupted.status, SubmissionStatus::Dropped);
+		assert!(interrupted.error.is_some());
+		let finalized = tracker.db.get_submission(H256::repeat_byte(2)).unwrap();
+		assert_eq!(finalized.unwrap().status, SubmissionStatus::Finalized);
+	}
+
+	#[test_case(0 => 0 ; "Within retention")]
+	#[test_case(100 => 2 ; "After retention")]
+	fn remove_expired(elapsed: u64) -> usize {
+		let tracker = tracker(&[
+			(1, SubmissionStatus::Ready),
+			(2, SubmissionStatus::Finalized),
+			(3, SubmissionStatus::Invalid),
+		]);
+		let (updated_at, _) = tracker.db.get_submissions().unwrap()[0];
+		let removed = tracker.remove_expired(updated_at + elapsed, 60).unwrap();
+
+		let tracked = tracker.db.get_submission(H256::repeat_byte(1)).unwrap();
+		assert!(tracked.is_some());
+		assert_eq!(tracker.db.get_submissions().unwrap().len(), 3 - removed);
+		removed
 	}
 }
@@ -4,17 +4,37 @@ use avail_subxt::api::runtime_types::{
 	bounded_collections::bounded_vec::BoundedVec,
 };
 use base64::{engine::general_purpose, DecodeError, Engine};
-use codec::Encode;
+use codec::{Compact, Encode};
 use derive_more::From;
 use hyper::{http, StatusCode};
-use kate_recovery::{com::AppData, commitments, config, matrix::Partition};
+use kate_recovery::{
+	com::AppData,
+	commitments, config,
+	data::Cell,
+	matrix::{Partition, Position},
+};
+use schemars::{
+	gen::SchemaGenerator,
+	schema::{InstanceType, Schema, SchemaObject, StringValidation},
+	JsonSchema,
+};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use sp_core::{blake2_256, H256};
 use std::{
-	collections::{HashMap, HashSet},
+	collections::{HashMap, HashSet, VecDeque},
+	fmt,
+	hash::Hash,
+	str::FromStr,
 	sync::Arc,
 };
-use tokio::sync::{mpsc::UnboundedSender, RwLock};
+use tokio::{
+	sync::{
+		broadcast,
+		mpsc::{self, error::TrySendError},
+		oneshot, RwLock,
+	},
+	time::{Duration, Instant},
+};
 use uuid::Uuid;
 use warp::{
 	ws::{self, Message},
@@ -22,11 +42,14 @@ use warp::{
 };
 
 use crate::{
+	data::Database,
 	network::rpc::{Event as RpcEvent, Node},
 	types::{
-		self, block_matrix_partition_format, BlockVerified, OptionBlockRange, RuntimeConfig, State,
+		self, balance_format, block_matrix_partition_format, BlockState, BlockVerified, DataProof,
+		DryRunOutcome, OptionBlockRange, RuntimeConfig, RuntimeDispatchInfo, SlowClientPolicy,
+		State, Submission, Weight,
 	},
-	utils::decode_app_data,
+	utils::{calculate_confidence, decode_app_data, unix_timestamp},
 };
 
 #[derive(Debug)]
@@ -34,7 +57,7 @@ pub struct InternalServerError {}
 
 impl warp::reject::Reject for InternalServerError {}
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 pub struct Version {
 	pub version: String,
 	pub network_version: String,
@@ -46,7 +69,7 @@ impl Reply for Version {
 	}
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct BlockRange {
 	pub first: u32,
 	pub last: u32,
@@ -61,7 +84,7 @@ impl From<&types::BlockRange> for BlockRange {
 	}
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct HistoricalSync {
 	pub synced: bool,
 	#[serde(skip_serializing_if = "Option::is_none")]
@@ -70,7 +93,29 @@ pub struct HistoricalSync {
 	pub app_data: Option<BlockRange>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct FinalitySync {
+	pub synced: bool,
+	pub latest: u32,
+	pub target: u32,
+	pub blocks_per_second: f64,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub eta: Option<u64>,
+}
+
+impl FinalitySync {
+	fn new(synced: bool, progress: &types::FinalitySyncProgress) -> Self {
+		FinalitySync {
+			synced,
+			latest: progress.latest,
+			target: progress.target,
+			blocks_per_second: progress.blocks_per_second(),
+			eta: progress.eta().map(|eta| eta.as_secs()),
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct Blocks {
 	pub latest: u32,
 	#[serde(skip_serializing_if = "Option::is_none")]
@@ -79,9 +124,11 @@ pub struct Blocks {
 	pub app_data: Option<BlockRange>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub historical_sync: Option<HistoricalSync>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub finality_sync: Option<FinalitySync>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct Status {
 	pub modes: Vec<Mode>,
 	#[serde(skip_serializing_if = "Option::is_none")]
@@ -93,6 +140,7 @@ pub struct Status {
 		skip_serializing_if = "Option::is_none",
 		with = "block_matrix_partition_format"
 	)]
+	#[schemars(with = "Option<String>")]
 	pub partition: Option<Partition>,
 }
 
@@ -100,6 +148,25 @@ pub struct Status {
 #[serde(try_from = "String", into = "String")]
 pub struct Base64(pub Vec<u8>);
 
+impl JsonSchema for Base64 {
+	fn is_referenceable() -> bool {
+		false
+	}
+
+	fn schema_name() -> String {
+		"Base64".to_string()
+	}
+
+	fn json_schema(_: &mut SchemaGenerator) -> Schema {
+		SchemaObject {
+			instance_type: Some(InstanceType::String.into()),
+			format: Some("byte".to_string()),
+			..Default::default()
+		}
+		.into()
+	}
+}
+
 impl From<Base64> for BoundedVec<u8> {
 	fn from(val: Base64) -> Self {
 		BoundedVec(val.0)
@@ -126,7 +193,7 @@ impl From<Base64> for String {
 	}
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum Transaction {
 	Data(Base64),
@@ -142,9 +209,11 @@ impl Transaction {
 	}
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct SubmitResponse {
+	#[schemars(with = "String")]
 	pub block_hash: H256,
+	#[schemars(with = "String")]
 	pub hash: H256,
 	pub index: u32,
 }
@@ -155,6 +224,94 @@ impl Reply for SubmitResponse {
 	}
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct SubmitQuery {
+	/// Returns immediately after submission, without waiting for finalization
+	#[serde(rename = "async", default)]
+	pub is_async: bool,
+	/// Name of the key used to sign data transactions
+	pub key: Option<String>,
+	/// Application ID of the data transaction, configured one is used if not set
+	pub app_id: Option<u32>,
+}
+
+/// Estimated fee and weight of the transaction, and the outcome of its dry run
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct EstimateResponse {
+	/// Inclusion fee in the smallest unit, including the configured tip for the data transactions
+	#[serde(with = "balance_format")]
+	#[schemars(with = "String")]
+	pub fee: u128,
+	pub weight: Weight,
+	pub class: String,
+	/// Transaction would be accepted by the transaction pool, or `None` if dry run is unavailable
+	pub valid: Option<bool>,
+	/// Dispatch or validity error if transaction would fail, or the dry run error
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+impl EstimateResponse {
+	/// Creates response from the fee info and the outcome of the dry run, which can be unavailable
+	pub fn new(
+		info: RuntimeDispatchInfo,
+		outcome: anyhow::Result<DryRunOutcome>,
+		tip: u128,
+	) -> Self {
+		let (valid, error) = match outcome {
+			Ok(DryRunOutcome::Success) => (Some(true), None),
+			Ok(DryRunOutcome::DispatchError(error)) => (Some(true), Some(error)),
+			Ok(DryRunOutcome::Invalid) => (Some(false), Some("Invalid transaction".to_string())),
+			Err(error) => (None, Some(format!("Dry run is unavailable: {error:#}"))),
+		};
+		EstimateResponse {
+			fee: info.partial_fee + tip,
+			weight: info.weight,
+			class: info.class,
+			valid,
+			error,
+		}
+	}
+}
+
+impl Reply for EstimateResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ApplicationKeyRequest {
+	pub name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ApplicationKeyQuery {
+	/// Name of the key used to sign the transaction
+	pub key: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ApplicationKeyResponse {
+	pub app_id: u32,
+	#[schemars(with = "String")]
+	pub block_hash: H256,
+	#[schemars(with = "String")]
+	pub hash: H256,
+}
+
+impl Reply for ApplicationKeyResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+impl Reply for Submission {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
 impl Status {
 	pub fn new(config: &RuntimeConfig, node: &Node, state: &State) -> Self {
 		let historical_sync = state.synced.map(|synced| HistoricalSync {
@@ -163,11 +320,17 @@ impl Status {
 			app_data: state.sync_data_verified.as_ref().map(From::from),
 		});
 
+		let finality_sync = state
+			.finality_sync
+			.as_ref()
+			.map(|progress| FinalitySync::new(state.finality_synced, progress));
+
 		let blocks = Blocks {
 			latest: state.latest,
 			available: state.confidence_achieved.as_ref().map(From::from),
 			app_data: state.data_verified.as_ref().map(From::from),
 			historical_sync,
+			finality_sync,
 		};
 
 		Status {
@@ -181,7 +344,7 @@ impl Status {
 	}
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum Mode {
 	Light,
@@ -209,34 +372,38 @@ impl Reply for Status {
 	}
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum Topic {
 	HeaderVerified,
 	ConfidenceAchieved,
 	DataVerified,
+	SubmissionStatus,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum DataField {
 	Data,
 	Extrinsic,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Default)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, JsonSchema)]
 pub struct Subscription {
 	pub topics: HashSet<Topic>,
 	pub data_fields: HashSet<DataField>,
+	/// Block number to replay stored messages from, before switching to live messages
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub from_block: Option<u32>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct HeaderMessage {
-	block_number: u32,
-	header: Header,
+	pub block_number: u32,
+	pub header: Header,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum BlockStatus {
 	Unavailable,
@@ -247,15 +414,36 @@ pub enum BlockStatus {
 	Finished,
 }
 
+/// Derives block status from the persisted block state, if any of the verification steps is done
+fn stored_block_status(block_state: &BlockState) -> Option<BlockStatus> {
+	if block_state.data_verified {
+		return Some(BlockStatus::Finished);
+	}
+	if block_state.confidence_achieved {
+		return Some(BlockStatus::VerifyingData);
+	}
+	if block_state.header_verified {
+		return Some(BlockStatus::VerifyingConfidence);
+	}
+	None
+}
+
+/// Derives block status from the persisted block state if available,
+/// falling back to the processed block ranges of the current run
 pub fn block_status(
 	sync_start_block: &Option<u32>,
 	state: &State,
 	block_number: u32,
+	block_state: Option<&BlockState>,
 ) -> Option<BlockStatus> {
 	if block_number > state.latest {
 		return None;
 	}
 
+	if let Some(status) = block_state.and_then(stored_block_status) {
+		return Some(status);
+	}
+
 	let first_block = state.header_verified.first().unwrap_or(state.latest);
 	let first_sync_block = sync_start_block.unwrap_or(first_block);
 
@@ -295,15 +483,21 @@ pub fn block_status(
 	Some(BlockStatus::Pending)
 }
 
-#[derive(Serialize, Deserialize, PartialEq)]
+#[derive(Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct Block {
 	pub status: BlockStatus,
 	pub confidence: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub failure: Option<String>,
 }
 
 impl Block {
-	pub fn new(status: BlockStatus, confidence: Option<f64>) -> Self {
-		Self { status, confidence }
+	pub fn new(status: BlockStatus, confidence: Option<f64>, failure: Option<String>) -> Self {
+		Self {
+			status,
+			confidence,
+			failure,
+		}
 	}
 }
 
@@ -325,14 +519,18 @@ impl TryFrom<avail_subxt::primitives::Header> for HeaderMessage {
 	}
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Header {
-	hash: H256,
-	parent_hash: H256,
+	#[schemars(with = "String")]
+	pub hash: H256,
+	#[schemars(with = "String")]
+	pub parent_hash: H256,
 	pub number: u32,
-	state_root: H256,
-	extrinsics_root: H256,
-	extension: Extension,
+	#[schemars(with = "String")]
+	pub state_root: H256,
+	#[schemars(with = "String")]
+	pub extrinsics_root: H256,
+	pub extension: Extension,
 }
 
 impl Reply for Header {
@@ -342,7 +540,7 @@ impl Reply for Header {
 }
 
 #[derive(Debug, Clone)]
-struct Commitment([u8; config::COMMITMENT_SIZE]);
+pub struct Commitment(pub [u8; config::COMMITMENT_SIZE]);
 
 impl Serialize for Commitment {
 	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -380,13 +578,54 @@ impl<'de> Deserialize<'de> for Commitment {
 	}
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct Extension {
-	rows: u16,
-	cols: u16,
-	data_root: H256,
-	commitments: Vec<Commitment>,
-	app_lookup: CompactDataLookup,
+impl JsonSchema for Commitment {
+	fn is_referenceable() -> bool {
+		false
+	}
+
+	fn schema_name() -> String {
+		"Commitment".to_string()
+	}
+
+	fn json_schema(_: &mut SchemaGenerator) -> Schema {
+		let pattern = format!("^0x[0-9a-f]{{{}}}$", config::COMMITMENT_SIZE * 2);
+		SchemaObject {
+			instance_type: Some(InstanceType::String.into()),
+			string: Some(Box::new(StringValidation {
+				pattern: Some(pattern),
+				..Default::default()
+			})),
+			..Default::default()
+		}
+		.into()
+	}
+}
+
+/// Schema of the serialized application data lookup
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct DataLookup {
+	size: u32,
+	index: Vec<DataLookupItem>,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct DataLookupItem {
+	app_id: u32,
+	start: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(rename = "HeaderExtension")]
+pub struct Extension {
+	pub rows: u16,
+	pub cols: u16,
+	#[schemars(with = "String")]
+	pub data_root: H256,
+	pub commitments: Vec<Commitment>,
+	#[schemars(with = "DataLookup")]
+	pub app_lookup: CompactDataLookup,
 }
 
 impl TryFrom<avail_subxt::primitives::Header> for Header {
@@ -454,11 +693,17 @@ impl TryFrom<RpcEvent> for PublishMessage {
 	}
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ConfidenceMessage {
-	block_number: u32,
+	pub block_number: u32,
 	#[serde(skip_serializing_if = "Option::is_none")]
-	confidence: Option<f64>,
+	pub confidence: Option<f64>,
+}
+
+impl From<Submission> for PublishMessage {
+	fn from(value: Submission) -> Self {
+		PublishMessage::SubmissionStatus(value)
+	}
 }
 
 impl TryFrom<BlockVerified> for PublishMessage {
@@ -472,11 +717,22 @@ impl TryFrom<BlockVerified> for PublishMessage {
 	}
 }
 
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockField {
+	Header,
+	Data,
+	Extrinsic,
+}
+
 #[derive(Serialize, Deserialize)]
-#[serde(try_from = "String")]
-pub struct FieldsQueryParameter(pub HashSet<DataField>);
+#[serde(
+	try_from = "String",
+	bound(deserialize = "T: de::DeserializeOwned + Eq + Hash")
+)]
+pub struct FieldsQueryParameter<T: Eq + Hash>(pub HashSet<T>);
 
-impl TryFrom<String> for FieldsQueryParameter {
+impl<T: de::DeserializeOwned + Eq + Hash> TryFrom<String> for FieldsQueryParameter<T> {
 	type Error = anyhow::Error;
 
 	fn try_from(value: String) -> Result<Self, Self::Error> {
@@ -491,10 +747,43 @@ impl TryFrom<String> for FieldsQueryParameter {
 
 #[derive(Serialize, Deserialize)]
 pub struct DataQuery {
-	pub fields: Option<FieldsQueryParameter>,
+	pub fields: Option<FieldsQueryParameter<DataField>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct BlocksQuery {
+	pub from: u32,
+	pub to: Option<u32>,
+	pub fields: Option<FieldsQueryParameter<BlockField>>,
+	pub cursor: Option<u32>,
+	pub limit: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct BlockSummary {
+	pub block_number: u32,
+	pub status: BlockStatus,
+	pub confidence: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub header: Option<Header>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub data_transactions: Option<Vec<DataTransaction>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct BlocksResponse {
+	pub blocks: Vec<BlockSummary>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub next_cursor: Option<u32>,
+}
+
+impl Reply for BlocksResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct DataResponse {
 	pub block_number: u32,
 	pub data_transactions: Vec<DataTransaction>,
@@ -506,18 +795,238 @@ impl Reply for DataResponse {
 	}
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize)]
+#[serde(try_from = "String")]
+pub struct PositionsQueryParameter(pub Vec<Position>);
+
+impl TryFrom<String> for PositionsQueryParameter {
+	type Error = anyhow::Error;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		let mut positions = value
+			.split(',')
+			.map(|part| {
+				let (row, col) = part
+					.split_once(':')
+					.ok_or_else(|| anyhow!("Cannot parse position {part}"))?;
+				Ok((row.parse()?, col.parse()?))
+			})
+			.collect::<anyhow::Result<Vec<(u32, u16)>>>()?;
+		positions.sort();
+		positions.dedup();
+		Ok(PositionsQueryParameter(
+			positions
+				.into_iter()
+				.map(|(row, col)| Position { row, col })
+				.collect(),
+		))
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(try_from = "String")]
+pub struct RowsQueryParameter(pub Vec<u32>);
+
+impl TryFrom<String> for RowsQueryParameter {
+	type Error = anyhow::Error;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		let mut rows = value
+			.split(',')
+			.map(|part| part.parse().context("Cannot parse row"))
+			.collect::<anyhow::Result<Vec<u32>>>()?;
+		rows.sort();
+		rows.dedup();
+		Ok(RowsQueryParameter(rows))
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CellsQuery {
+	pub positions: PositionsQueryParameter,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RowsQuery {
+	pub rows: RowsQueryParameter,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(rename = "Cell")]
+pub struct CellResponse {
+	pub row: u32,
+	pub col: u16,
+	#[serde(with = "sp_core::bytes")]
+	#[schemars(with = "String")]
+	pub data: Vec<u8>,
+	#[serde(with = "sp_core::bytes")]
+	#[schemars(with = "String")]
+	pub proof: Vec<u8>,
+}
+
+impl From<Cell> for CellResponse {
+	fn from(cell: Cell) -> Self {
+		let (proof, data) = cell.content.split_at(config::COMMITMENT_SIZE);
+		CellResponse {
+			row: cell.position.row,
+			col: cell.position.col,
+			data: data.to_vec(),
+			proof: proof.to_vec(),
+		}
+	}
+}
+
+/// Row of the data matrix, with the proof of each cell in the row, in the column order
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(rename = "Row")]
+pub struct RowResponse {
+	pub row: u32,
+	#[serde(with = "sp_core::bytes")]
+	#[schemars(with = "String")]
+	pub data: Vec<u8>,
+	#[schemars(with = "Vec<String>")]
+	pub proofs: Vec<sp_core::Bytes>,
+}
+
+impl RowResponse {
+	/// Creates row from the cells of the row, or returns `None` if any of the cells is missing
+	pub fn from_cells(row: u32, cols: u16, cells: &HashMap<(u32, u16), Cell>) -> Option<Self> {
+		let mut data = vec![];
+		let mut proofs = vec![];
+		for col in 0..cols {
+			let cell = cells.get(&(row, col))?;
+			let (proof, cell_data) = cell.content.split_at(config::COMMITMENT_SIZE);
+			data.extend_from_slice(cell_data);
+			proofs.push(proof.to_vec().into());
+		}
+		Some(RowResponse { row, data, proofs })
+	}
+}
+
+/// Data matrix dimensions and header commitments, needed to verify cells and rows
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct Matrix {
+	rows: u16,
+	cols: u16,
+	commitments: Vec<Commitment>,
+}
+
+impl TryFrom<&avail_subxt::primitives::Header> for Matrix {
+	type Error = anyhow::Error;
+
+	fn try_from(header: &avail_subxt::primitives::Header) -> Result<Self, Self::Error> {
+		let Extension {
+			rows,
+			cols,
+			commitments,
+			..
+		} = header.extension.clone().try_into()?;
+		Ok(Matrix {
+			rows,
+			cols,
+			commitments,
+		})
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct CellsResponse {
+	pub block_number: u32,
+	pub matrix: Matrix,
+	pub cells: Vec<CellResponse>,
+}
+
+impl Reply for CellsResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct RowsResponse {
+	pub block_number: u32,
+	pub matrix: Matrix,
+	pub rows: Vec<RowResponse>,
+}
+
+impl Reply for RowsResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProofQuery {
+	pub app_id: u32,
+	/// Index of the data transaction in the application data, proves only its cells if set
+	pub index: Option<u32>,
+}
+
+/// Range of application data cells in the data matrix, with the end cell excluded
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct CellRange {
+	pub start: u32,
+	pub end: u32,
+}
+
+impl CellRange {
+	/// Number of cells in the range
+	pub fn count(&self) -> usize {
+		self.end.saturating_sub(self.start) as usize
+	}
+
+	/// Range of cells containing the encoded data transaction with the given index.
+	///
+	/// Application data is SCALE encoded as a vector of data transactions,
+	/// and stored in the cells starting at `start`, with `config::DATA_CHUNK_SIZE` bytes per cell.
+	pub fn of_data_transaction(start: u32, data: &[Vec<u8>], index: usize) -> Option<Self> {
+		let transaction = data.get(index)?;
+		let offset = Compact(data.len() as u32).encoded_size()
+			+ data[..index]
+				.iter()
+				.map(Encode::encoded_size)
+				.sum::<usize>();
+		let end = offset + transaction.encoded_size();
+		Some(CellRange {
+			start: start + (offset / config::DATA_CHUNK_SIZE) as u32,
+			end: start + end.div_ceil(config::DATA_CHUNK_SIZE) as u32,
+		})
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ProofResponse {
+	pub block_number: u32,
+	#[schemars(with = "String")]
+	pub block_hash: H256,
+	#[schemars(with = "String")]
+	pub data_root: H256,
+	pub app_id: u32,
+	pub range: CellRange,
+	pub matrix: Matrix,
+	pub cells: Vec<CellResponse>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub data_proof: Option<DataProof>,
+}
+
+impl Reply for ProofResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct DataMessage {
-	block_number: u32,
-	data_transactions: Vec<DataTransaction>,
+	pub block_number: u32,
+	pub data_transactions: Vec<DataTransaction>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct DataTransaction {
 	#[serde(skip_serializing_if = "Option::is_none")]
-	data: Option<Base64>,
+	pub data: Option<Base64>,
 	#[serde(skip_serializing_if = "Option::is_none")]
-	extrinsic: Option<Base64>,
+	pub extrinsic: Option<Base64>,
 }
 
 impl TryFrom<Vec<u8>> for DataTransaction {
@@ -559,22 +1068,79 @@ impl TryFrom<(u32, AppData)> for PublishMessage {
 	}
 }
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
 #[serde(tag = "topic", content = "message", rename_all = "kebab-case")]
 pub enum PublishMessage {
 	HeaderVerified(Box<HeaderMessage>),
 	ConfidenceAchieved(ConfidenceMessage),
 	DataVerified(DataMessage),
+	SubmissionStatus(Submission),
 }
 
 impl PublishMessage {
-	fn apply_filter(&mut self, fields: &HashSet<DataField>) {
+	pub fn apply_filter(&mut self, fields: &HashSet<DataField>) {
 		match self {
 			PublishMessage::HeaderVerified(_) => (),
 			PublishMessage::ConfidenceAchieved(_) => (),
 			PublishMessage::DataVerified(data) => {
 				filter_fields(&mut data.data_transactions, fields)
 			},
+			PublishMessage::SubmissionStatus(_) => (),
+		}
+	}
+
+	pub fn topic(&self) -> Topic {
+		match self {
+			PublishMessage::HeaderVerified(_) => Topic::HeaderVerified,
+			PublishMessage::ConfidenceAchieved(_) => Topic::ConfidenceAchieved,
+			PublishMessage::DataVerified(_) => Topic::DataVerified,
+			PublishMessage::SubmissionStatus(_) => Topic::SubmissionStatus,
+		}
+	}
+
+	/// Block number of the message, if message is related to a block
+	pub fn block_number(&self) -> Option<u32> {
+		match self {
+			PublishMessage::HeaderVerified(header) => Some(header.block_number),
+			PublishMessage::ConfidenceAchieved(confidence) => Some(confidence.block_number),
+			PublishMessage::DataVerified(data) => Some(data.block_number),
+			PublishMessage::SubmissionStatus(_) => None,
+		}
+	}
+
+	/// Loads message on the given topic from the database, if the block is stored.
+	/// Data verified message is loaded only if the application ID is given.
+	pub fn load(
+		db: &impl Database,
+		topic: Topic,
+		block_number: u32,
+		app_id: Option<u32>,
+	) -> anyhow::Result<Option<Self>> {
+		match topic {
+			Topic::HeaderVerified => db
+				.get_header(block_number)?
+				.map(|header| {
+					header
+						.try_into()
+						.map(Box::new)
+						.map(PublishMessage::HeaderVerified)
+				})
+				.transpose(),
+			Topic::ConfidenceAchieved => Ok(db.get_confidence(block_number)?.map(|count| {
+				PublishMessage::ConfidenceAchieved(ConfidenceMessage {
+					block_number,
+					confidence: Some(calculate_confidence(count)),
+				})
+			})),
+			Topic::DataVerified => {
+				let Some(app_id) = app_id else {
+					return Ok(None);
+				};
+				db.get_data(app_id, block_number)?
+					.map(|data| (block_number, data).try_into())
+					.transpose()
+			},
+			Topic::SubmissionStatus => Ok(None),
 		}
 	}
 }
@@ -588,11 +1154,53 @@ impl TryFrom<PublishMessage> for Message {
 	}
 }
 
-pub type Sender = UnboundedSender<Result<ws::Message, warp::Error>>;
+pub type Sender = mpsc::Sender<Result<ws::Message, warp::Error>>;
+
+/// Web socket clients limits (see [`RuntimeConfig`] for details)
+#[derive(Clone)]
+pub struct WsConfig {
+	pub max_subscriptions: usize,
+	pub subscription_ttl: Duration,
+	pub queue_size: usize,
+	pub slow_client_policy: SlowClientPolicy,
+	pub max_replay_blocks: u32,
+}
+
+impl WsConfig {
+	/// First block from which stored messages can be replayed, given the latest block
+	pub fn first_replay_block(&self, latest: u32) -> u32 {
+		latest.saturating_sub(self.max_replay_blocks)
+	}
+}
+
+impl From<&RuntimeConfig> for WsConfig {
+	fn from(config: &RuntimeConfig) -> Self {
+		WsConfig {
+			max_subscriptions: config.ws_max_subscriptions,
+			subscription_ttl: Duration::from_secs(config.ws_subscription_ttl),
+			queue_size: config.ws_client_queue_size.max(1),
+			slow_client_policy: config.ws_slow_client_policy,
+			max_replay_blocks: config.ws_max_replay_blocks,
+		}
+	}
+}
+
+impl Default for WsConfig {
+	fn default() -> Self {
+		(&RuntimeConfig::default()).into()
+	}
+}
 
 pub struct WsClient {
 	pub subscription: Subscription,
 	pub sender: Option<Sender>,
+	/// Live messages buffered while stored messages are replayed
+	pending: Option<Vec<PublishMessage>>,
+	/// Replayed messages, skipped if they are published after the replay is finished
+	replayed: HashSet<(Topic, u32)>,
+	/// Signals the connected web socket to close
+	disconnect: Option<oneshot::Sender<()>>,
+	created_at: Instant,
 }
 
 impl WsClient {
@@ -600,9 +1208,78 @@ impl WsClient {
 		WsClient {
 			subscription,
 			sender: None,
+			pending: None,
+			replayed: HashSet::new(),
+			disconnect: None,
+			created_at: Instant::now(),
+		}
+	}
+
+	fn send(
+		&mut self,
+		mut message: PublishMessage,
+		policy: SlowClientPolicy,
+	) -> anyhow::Result<()> {
+		let Some((sender, data_fields)) = self.sender_with_data_fields() else {
+			return Ok(());
+		};
+		message.apply_filter(data_fields);
+		let message: ws::Message = message.try_into().context("Cannot convert to ws message")?;
+
+		match sender.try_send(Ok(message)) {
+			Ok(()) => Ok(()),
+			Err(TrySendError::Full(_)) if policy == SlowClientPolicy::Disconnect => {
+				self.disconnect();
+				Err(anyhow!("Client queue is full, client is disconnected"))
+			},
+			Err(TrySendError::Full(_)) => Err(anyhow!("Client queue is full, message is dropped")),
+			Err(TrySendError::Closed(_)) => Err(anyhow!("Send failed")),
 		}
 	}
 
+	/// Returns `true` if the message is already replayed, and forgets it, since it is sent only once
+	fn take_replayed(&mut self, message: &PublishMessage) -> bool {
+		let topic = message.topic();
+		message
+			.block_number()
+			.is_some_and(|block_number| self.replayed.remove(&(topic, block_number)))
+	}
+
+	/// Buffers live message while stored messages are replayed, or sends it if there is no replay.
+	/// Buffer is limited to the client queue size, and the slow client policy applies once it is full.
+	fn send_or_buffer(&mut self, message: PublishMessage, config: &WsConfig) -> anyhow::Result<()> {
+		let Some(pending) = self.pending.as_mut() else {
+			if self.take_replayed(&message) {
+				return Ok(());
+			}
+			return self.send(message, config.slow_client_policy);
+		};
+		if pending.len() < config.queue_size {
+			pending.push(message);
+			return Ok(());
+		}
+		if config.slow_client_policy == SlowClientPolicy::Disconnect {
+			self.disconnect();
+			return Err(anyhow!("Client buffer is full, client is disconnected"));
+		}
+		Err(anyhow!("Client buffer is full, message is dropped"))
+	}
+
+	/// Drops the sender and signals the connected web socket to close
+	fn disconnect(&mut self) {
+		self.sender = None;
+		self.pending = None;
+		if let Some(disconnect) = self.disconnect.take() {
+			// Sending fails only if the connection is already closed
+			let _ = disconnect.send(());
+		}
+	}
+
+	/// Subscription is expired if the web socket is not connected within the given time
+	fn is_expired(&self, ttl: Duration) -> bool {
+		self.sender.is_none() && self.created_at.elapsed() > ttl
+	}
+
 	fn is_subscribed(&self, topic: &Topic) -> bool {
 		self.subscription.topics.contains(topic)
 	}
@@ -615,25 +1292,163 @@ impl WsClient {
 }
 
 #[derive(Clone)]
-pub struct WsClients(pub Arc<RwLock<HashMap<String, WsClient>>>);
+pub struct WsClients {
+	clients: Arc<RwLock<HashMap<String, WsClient>>>,
+	pub config: WsConfig,
+}
 
 impl WsClients {
-	pub async fn set_sender(&self, subscription_id: &str, sender: Sender) -> anyhow::Result<()> {
-		let mut clients = self.0.write().await;
-		let Some(client) = clients.get_mut(subscription_id) else {
+	pub fn new(config: WsConfig) -> Self {
+		WsClients {
+			clients: Arc::new(RwLock::new(HashMap::new())),
+			config,
+		}
+	}
+
+	/// Sets the sender of the connected client, replacing the previous connection if any.
+	/// If the subscription replays from a block, live messages are buffered until
+	/// [`WsClients::finish_replay`] is called.
+	///
+	/// Returns receiver which is notified when the connection should be closed.
+	pub async fn set_sender(
+		&self,
+		subscription_id: &str,
+		sender: Sender,
+	) -> anyhow::Result<oneshot::Receiver<()>> {
+		let mut clients = self.clients.write().await;
+		let Some(client) = clients
+			.get_mut(subscription_id)
+			.filter(|client| !client.is_expired(self.config.subscription_ttl))
+		else {
 			return Err(anyhow!("Client is not subscribed"));
 		};
+		let (disconnect, disconnected) = oneshot::channel();
+		client.disconnect();
 		client.sender = Some(sender);
+		client.pending = client.subscription.from_block.map(|_| vec![]);
+		client.replayed.clear();
+		client.disconnect = Some(disconnect);
+		Ok(disconnected)
+	}
+
+	/// Removes the subscription once its web socket is closed, unless another connection replaced it
+	pub async fn remove_sender(&self, subscription_id: &str, sender: &Sender) {
+		let mut clients = self.clients.write().await;
+		let is_connected = clients
+			.get(subscription_id)
+			.and_then(|client| client.sender.as_ref())
+			.is_some_and(|client_sender| client_sender.same_channel(sender));
+		if is_connected {
+			clients.remove(subscription_id);
+		}
+	}
+
+	/// Sends replayed message to the client, regardless of the buffered live messages.
+	/// Slow client policy applies if the client queue is full.
+	///
+	/// Returns `false` if the message is dropped, and fails if the client is not connected.
+	pub async fn send_replayed(
+		&self,
+		subscription_id: &str,
+		message: PublishMessage,
+	) -> anyhow::Result<bool> {
+		let mut clients = self.clients.write().await;
+		let Some(client) = clients
+			.get_mut(subscription_id)
+			.filter(|client| client.sender.is_some())
+		else {
+			return Err(anyhow!("Client is not connected"));
+		};
+		match client.send(message, self.config.slow_client_policy) {
+			Ok(()) => Ok(true),
+			Err(_) if client.sender.is_some() => Ok(false),
+			Err(error) => {
+				clients.remove(subscription_id);
+				Err(error)
+			},
+		}
+	}
+
+	/// Sends buffered live messages, skipping the already replayed ones, and switches to live messages.
+	/// Replayed messages which are published later are skipped as well.
+	/// Replay block is cleared, so reconnecting client doesn't receive replayed messages again.
+	pub async fn finish_replay(
+		&self,
+		subscription_id: &str,
+		replayed: HashSet<(Topic, u32)>,
+	) -> anyhow::Result<()> {
+		let mut clients = self.clients.write().await;
+		let Some(client) = clients.get_mut(subscription_id) else {
+			return Err(anyhow!("Client is not subscribed"));
+		};
+		client.subscription.from_block = None;
+		client.replayed = replayed;
+		for message in client.pending.take().unwrap_or_default() {
+			if client.take_replayed(&message) {
+				continue;
+			}
+			if let Err(error) = client.send(message, self.config.slow_client_policy) {
+				if client.sender.is_none() {
+					clients.remove(subscription_id);
+				}
+				return Err(error);
+			}
+		}
 		Ok(())
 	}
 
+	pub async fn subscription(&self, subscription_id: &str) -> Option<Subscription> {
+		let clients = self.clients.read().await;
+		clients
+			.get(subscription_id)
+			.map(|client| client.subscription.clone())
+	}
+
+	/// Updates subscription topics and data fields, returning the updated subscription.
+	/// Returns `None` if subscription doesn't exist.
+	pub async fn update_subscription(
+		&self,
+		subscription_id: &str,
+		update: impl FnOnce(&mut Subscription),
+	) -> Option<Subscription> {
+		let mut clients = self.clients.write().await;
+		let client = clients.get_mut(subscription_id)?;
+		update(&mut client.subscription);
+		Some(client.subscription.clone())
+	}
+
 	pub async fn has_subscription(&self, subscription_id: &str) -> bool {
-		self.0.read().await.contains_key(subscription_id)
+		let clients = self.clients.read().await;
+		clients
+			.get(subscription_id)
+			.is_some_and(|client| !client.is_expired(self.config.subscription_ttl))
 	}
 
-	pub async fn subscribe(&self, subscription_id: &str, subscription: Subscription) {
-		let mut clients = self.0.write().await;
+	/// Adds subscription, after removing the expired ones.
+	/// Fails if the maximum number of subscriptions is reached.
+	pub async fn subscribe(
+		&self,
+		subscription_id: &str,
+		subscription: Subscription,
+	) -> anyhow::Result<()> {
+		let mut clients = self.clients.write().await;
+		clients.retain(|_, client| !client.is_expired(self.config.subscription_ttl));
+		if clients.len() >= self.config.max_subscriptions {
+			return Err(anyhow!("Maximum number of subscriptions is reached"));
+		}
 		clients.insert(subscription_id.to_string(), WsClient::new(subscription));
+		Ok(())
+	}
+
+	/// Removes subscription and closes its web socket, if connected.
+	/// Returns `false` if subscription doesn't exist.
+	pub async fn unsubscribe(&self, subscription_id: &str) -> bool {
+		let mut clients = self.clients.write().await;
+		let Some(mut client) = clients.remove(subscription_id) else {
+			return false;
+		};
+		client.disconnect();
+		true
 	}
 
 	pub async fn publish(
@@ -641,32 +1456,170 @@ impl WsClients {
 		topic: &Topic,
 		message: PublishMessage,
 	) -> anyhow::Result<Vec<anyhow::Result<()>>> {
-		let clients = self.0.read().await;
-		Ok(clients
-			.iter()
-			.filter(|(_, client)| client.is_subscribed(topic))
-			.flat_map(|(_, client)| client.sender_with_data_fields())
-			.map(|(sender, data_fields)| {
-				let mut message = message.clone();
-				message.apply_filter(data_fields);
-				message
-					.try_into()
-					.context("Cannot convert to ws message")
-					.and_then(|message: warp::ws::Message| {
-						sender.send(Ok(message)).context("Send failed")
-					})
+		let mut clients = self.clients.write().await;
+		let mut disconnected = vec![];
+		let results = clients
+			.iter_mut()
+			.filter(|(_, client)| client.is_subscribed(topic) && client.sender.is_some())
+			.map(|(subscription_id, client)| {
+				let result = client.send_or_buffer(message.clone(), &self.config);
+				if client.sender.is_none() {
+					disconnected.push(subscription_id.clone());
+				}
+				result
 			})
-			.collect::<Vec<_>>())
+			.collect::<Vec<_>>();
+		// Disconnected clients are removed, since their subscriptions are not usable anymore
+		for subscription_id in disconnected {
+			clients.remove(&subscription_id);
+		}
+		Ok(results)
 	}
 }
 
 impl Default for WsClients {
 	fn default() -> Self {
-		Self(Arc::new(RwLock::new(HashMap::new())))
+		Self::new(WsConfig::default())
+	}
+}
+
+/// Event ID, formatted as `{epoch}-{sequence}`.
+/// Epoch is the events buffer creation time, so IDs from the previous runs are not mistaken for the current ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventId {
+	pub epoch: u64,
+	pub sequence: u64,
+}
+
+impl fmt::Display for EventId {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}-{}", self.epoch, self.sequence)
+	}
+}
+
+impl FromStr for EventId {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (epoch, sequence) = s
+			.split_once('-')
+			.ok_or_else(|| anyhow!("Invalid event ID {s}"))?;
+		Ok(EventId {
+			epoch: epoch.parse().context("Invalid event ID epoch")?,
+			sequence: sequence.parse().context("Invalid event ID sequence")?,
+		})
+	}
+}
+
+/// Published message with the sequential event ID, used by the server-sent events clients to resume
+pub struct Event {
+	pub id: EventId,
+	pub topic: Topic,
+	pub message: PublishMessage,
+}
+
+struct EventsBuffer {
+	next_sequence: u64,
+	events: VecDeque<Arc<Event>>,
+}
+
+/// Recently published messages, kept in the ring buffer of the fixed size
+#[derive(Clone)]
+pub struct Events {
+	epoch: u64,
+	buffer: Arc<RwLock<EventsBuffer>>,
+	sender: broadcast::Sender<Arc<Event>>,
+	capacity: usize,
+}
+
+impl Events {
+	pub fn new(capacity: usize) -> Self {
+		Self::with_epoch(capacity, unix_timestamp())
+	}
+
+	fn with_epoch(capacity: usize, epoch: u64) -> Self {
+		let (sender, _) = broadcast::channel(capacity.max(1));
+		Events {
+			epoch,
+			buffer: Arc::new(RwLock::new(EventsBuffer {
+				next_sequence: 1,
+				events: VecDeque::with_capacity(capacity),
+			})),
+			sender,
+			capacity,
+		}
+	}
+
+	pub async fn publish(&self, topic: Topic, message: PublishMessage) {
+		let mut buffer = self.buffer.write().await;
+		let event = Arc::new(Event {
+			id: EventId {
+				epoch: self.epoch,
+				sequence: buffer.next_sequence,
+			},
+			topic,
+			message,
+		});
+		buffer.next_sequence += 1;
+		if buffer.events.len() == self.capacity {
+			buffer.events.pop_front();
+		}
+		buffer.events.push_back(event.clone());
+		// Sending fails only if there are no receivers
+		let _ = self.sender.send(event);
+	}
+
+	/// Subscribes to new events, returning buffered events published after the given event ID.
+	/// Returns `None` if events after the given event ID are no longer in the buffer,
+	/// or if the event ID is from the previous run.
+	pub async fn subscribe(
+		&self,
+		last_event_id: Option<EventId>,
+	) -> Option<(Vec<Arc<Event>>, broadcast::Receiver<Arc<Event>>)> {
+		// Write lock prevents publishing between buffer read and subscription
+		let buffer = self.buffer.write().await;
+		let receiver = self.sender.subscribe();
+		let Some(last_event_id) = last_event_id else {
+			return Some((vec![], receiver));
+		};
+
+		if last_event_id.epoch != self.epoch {
+			return None;
+		}
+
+		let first_sequence = buffer
+			.events
+			.front()
+			.map_or(buffer.next_sequence, |event| event.id.sequence);
+		if last_event_id.sequence.saturating_add(1) < first_sequence
+			|| last_event_id.sequence >= buffer.next_sequence
+		{
+			return None;
+		}
+
+		let events = buffer
+			.events
+			.iter()
+			.filter(|event| event.id.sequence > last_event_id.sequence)
+			.cloned()
+			.collect();
+		Some((events, receiver))
+	}
+}
+
+impl Default for Events {
+	fn default() -> Self {
+		Self::new(1024)
 	}
 }
 
 #[derive(Serialize, Deserialize)]
+pub struct EventsQuery {
+	pub topics: FieldsQueryParameter<Topic>,
+	pub data_fields: Option<FieldsQueryParameter<DataField>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct SubscriptionId {
 	pub subscription_id: String,
 }
@@ -677,22 +1630,57 @@ impl Reply for SubscriptionId {
 	}
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
+pub struct BlockRequest {
+	pub block_number: u32,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct BlockDataRequest {
+	pub block_number: u32,
+	/// Data transaction fields to include, all fields are included if not set
+	pub fields: Option<HashSet<DataField>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct SubscribeRequest {
+	pub topics: HashSet<Topic>,
+	/// Replaces subscribed data fields, if set
+	pub data_fields: Option<HashSet<DataField>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct UnsubscribeRequest {
+	pub topics: HashSet<Topic>,
+}
+
+#[derive(Deserialize, JsonSchema)]
 #[serde(tag = "type", content = "message", rename_all = "kebab-case")]
 pub enum Payload {
 	Version,
 	Status,
+	Block(BlockRequest),
+	BlockHeader(BlockRequest),
+	BlockData(BlockDataRequest),
 	Submit(Transaction),
+	SubmitAsync(Transaction),
+	Subscribe(SubscribeRequest),
+	Unsubscribe(UnsubscribeRequest),
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
+#[schemars(rename = "WsRequest")]
 pub struct Request {
 	#[serde(flatten)]
 	pub payload: Payload,
 	pub request_id: Uuid,
+	/// Name of the key used to sign submitted data transactions
+	pub key: Option<String>,
+	/// Application ID of submitted data transactions, configured one is used if not set
+	pub app_id: Option<u32>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct Response<T> {
 	pub request_id: Uuid,
 	pub message: T,
@@ -715,15 +1703,17 @@ impl TryFrom<ws::Message> for Request {
 	}
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum ErrorCode {
 	NotFound,
 	BadRequest,
+	Forbidden,
 	InternalServerError,
+	ServiceUnavailable,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct Error {
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub request_id: Option<Uuid>,
@@ -769,11 +1759,32 @@ impl Error {
 		Self::new(Some(request_id), None, ErrorCode::BadRequest, message)
 	}
 
+	pub fn forbidden_unknown(message: &str) -> Self {
+		Self::new(None, None, ErrorCode::Forbidden, message)
+	}
+
+	pub fn forbidden(request_id: Uuid, message: &str) -> Self {
+		Self::new(Some(request_id), None, ErrorCode::Forbidden, message)
+	}
+
+	pub fn service_unavailable(message: &str) -> Self {
+		Self::new(None, None, ErrorCode::ServiceUnavailable, message)
+	}
+
+	pub fn with_request_id(self, request_id: Uuid) -> Self {
+		Error {
+			request_id: Some(request_id),
+			..self
+		}
+	}
+
 	fn status(&self) -> StatusCode {
 		match self.error_code {
 			ErrorCode::NotFound => StatusCode::NOT_FOUND,
 			ErrorCode::BadRequest => StatusCode::BAD_REQUEST,
+			ErrorCode::Forbidden => StatusCode::FORBIDDEN,
 			ErrorCode::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
+			ErrorCode::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
 		}
 	}
 }
@@ -794,15 +1805,20 @@ impl From<Error> for String {
 	}
 }
 
-#[derive(Serialize, Deserialize, From)]
+#[derive(Serialize, Deserialize, From, JsonSchema)]
 #[serde(tag = "topic", rename_all = "kebab-case")]
 pub enum WsResponse {
 	Version(Response<Version>),
 	Status(Response<Status>),
+	Block(Response<Block>),
+	BlockHeader(Response<Header>),
+	BlockData(Response<DataResponse>),
 	DataTransactionSubmitted(Response<SubmitResponse>),
+	SubmissionStatus(Response<Submission>),
+	Subscription(Response<Subscription>),
 }
 
-#[derive(Serialize, Deserialize, From)]
+#[derive(Serialize, Deserialize, From, JsonSchema)]
 #[serde(tag = "topic", rename_all = "kebab-case")]
 pub enum WsError {
 	Error(Error),
@@ -814,22 +1830,28 @@ mod tests {
 
 	use avail_subxt::api::runtime_types::avail_core::data_lookup::compact::CompactDataLookup;
 	use sp_core::H256;
+	use test_case::test_case;
 	use tokio::sync::mpsc;
 
 	use crate::{
 		api::v2::types::{BlockStatus, Header, HeaderMessage, PublishMessage},
-		types::{OptionBlockRange, State},
+		types::{
+			BlockState, DryRunOutcome, OptionBlockRange, RuntimeDispatchInfo, SlowClientPolicy,
+			State, Weight,
+		},
 	};
+	use anyhow::anyhow;
 
 	use super::{
-		block_status, Base64, ConfidenceMessage, DataField, DataMessage, DataTransaction,
-		Subscription, Topic, WsClients,
+		block_status, Base64, CellRange, ConfidenceMessage, DataField, DataMessage,
+		DataTransaction, EstimateResponse, Events, Subscription, Topic, WsClients, WsConfig,
 	};
 
 	fn subscription(topics: Vec<Topic>, fields: Vec<DataField>) -> Subscription {
 		Subscription {
 			topics: topics.into_iter().collect(),
 			data_fields: fields.into_iter().collect(),
+			from_block: None,
 		}
 	}
 
@@ -888,10 +1910,10 @@ mod tests {
 			vec![Topic::ConfidenceAchieved, Topic::DataVerified],
 			vec![DataField::Data],
 		);
-		let (sender_1, mut receiver_1) = mpsc::unbounded_channel();
-		let (sender_2, mut receiver_2) = mpsc::unbounded_channel();
-		clients.subscribe("1", subscription_1).await;
-		clients.subscribe("2", subscription_2).await;
+		let (sender_1, mut receiver_1) = mpsc::channel(10);
+		let (sender_2, mut receiver_2) = mpsc::channel(10);
+		clients.subscribe("1", subscription_1).await.unwrap();
+		clients.subscribe("2", subscription_2).await.unwrap();
 		clients.set_sender("1", sender_1).await.unwrap();
 		clients.set_sender("2", sender_2).await.unwrap();
 
@@ -943,13 +1965,253 @@ mod tests {
 		};
 	}
 
+	#[tokio::test]
+	async fn clients_finish_replay() {
+		let clients = WsClients::default();
+		let mut subscription = subscription(
+			vec![
+				Topic::HeaderVerified,
+				Topic::ConfidenceAchieved,
+				Topic::DataVerified,
+			],
+			vec![DataField::Data],
+		);
+		subscription.from_block = Some(1);
+		let (sender, mut receiver) = mpsc::channel(10);
+		clients.subscribe("1", subscription).await.unwrap();
+		clients.set_sender("1", sender).await.unwrap();
+
+		clients
+			.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+			.await
+			.unwrap();
+		clients
+			.publish(&Topic::DataVerified, data_verified())
+			.await
+			.unwrap();
+		assert!(receiver.try_recv().is_err());
+
+		let replayed = [(Topic::HeaderVerified, 1), (Topic::ConfidenceAchieved, 1)]
+			.into_iter()
+			.collect();
+		clients.finish_replay("1", replayed).await.unwrap();
+		let subscription = clients.subscription("1").await.unwrap();
+		assert_eq!(subscription.from_block, None);
+
+		let message = receiver.try_recv().unwrap().unwrap();
+		let message: PublishMessage = serde_json::from_slice(message.as_bytes()).unwrap();
+		assert!(matches!(message, PublishMessage::DataVerified(_)));
+		assert!(receiver.try_recv().is_err());
+
+		// Replayed message published after the replay is skipped only once
+		for expected in [false, true] {
+			clients
+				.publish(&Topic::HeaderVerified, header_verified())
+				.await
+				.unwrap();
+			assert_eq!(receiver.try_recv().is_ok(), expected);
+		}
+
+		clients
+			.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+			.await
+			.unwrap();
+		assert!(receiver.try_recv().is_ok());
+	}
+
+	fn ws_config(slow_client_policy: SlowClientPolicy) -> WsConfig {
+		WsConfig {
+			max_subscriptions: 1,
+			subscription_ttl: Duration::from_millis(10),
+			queue_size: 1,
+			slow_client_policy,
+			max_replay_blocks: 1,
+		}
+	}
+
+	#[test_case(SlowClientPolicy::Drop, false ; "Drop")]
+	#[test_case(SlowClientPolicy::Disconnect, true ; "Disconnect")]
+	#[tokio::test]
+	async fn clients_replay_buffer_limit(policy: SlowClientPolicy, is_disconnected: bool) {
+		let clients = WsClients::new(ws_config(policy));
+		let mut subscription = subscription(vec![Topic::ConfidenceAchieved], vec![]);
+		subscription.from_block = Some(1);
+		let (sender, _receiver) = mpsc::channel(1);
+		clients.subscribe("1", subscription).await.unwrap();
+		let mut disconnected = clients.set_sender("1", sender).await.unwrap();
+
+		for expected in [true, false] {
+			let results = clients
+				.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+				.await
+				.unwrap();
+			assert_eq!(results[0].is_ok(), expected);
+		}
+		assert_eq!(disconnected.try_recv().is_ok(), is_disconnected);
+		assert_eq!(clients.has_subscription("1").await, !is_disconnected);
+	}
+
+	#[test_case(SlowClientPolicy::Drop, false ; "Drop")]
+	#[test_case(SlowClientPolicy::Disconnect, true ; "Disconnect")]
+	#[tokio::test]
+	async fn clients_send_replayed_queue_limit(policy: SlowClientPolicy, is_disconnected: bool) {
+		let clients = WsClients::new(ws_config(policy));
+		let mut subscription = subscription(vec![Topic::ConfidenceAchieved], vec![]);
+		subscription.from_block = Some(1);
+		let (sender, _receiver) = mpsc::channel(1);
+		clients.subscribe("1", subscription).await.unwrap();
+		let mut disconnected = clients.set_sender("1", sender).await.unwrap();
+
+		let sent = clients.send_replayed("1", confidence_achieved()).await;
+		assert!(sent.unwrap());
+		let sent = clients.send_replayed("1", confidence_achieved()).await;
+		assert_eq!(sent.is_err(), is_disconnected);
+		assert_eq!(disconnected.try_recv().is_ok(), is_disconnected);
+		assert_eq!(clients.has_subscription("1").await, !is_disconnected);
+	}
+
+	#[tokio::test]
+	async fn clients_max_subscriptions() {
+		let clients = WsClients::new(ws_config(SlowClientPolicy::Drop));
+		let subscription = || subscription(vec![Topic::HeaderVerified], vec![]);
+		clients.subscribe("1", subscription()).await.unwrap();
+		assert!(clients.subscribe("2", subscription()).await.is_err());
+
+		assert!(clients.unsubscribe("1").await);
+		assert!(!clients.unsubscribe("1").await);
+		clients.subscribe("2", subscription()).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn clients_subscription_ttl() {
+		let clients = WsClients::new(ws_config(SlowClientPolicy::Drop));
+		let subscription = || subscription(vec![Topic::HeaderVerified], vec![]);
+		clients.subscribe("1", subscription()).await.unwrap();
+		assert!(clients.has_subscription("1").await);
+
+		tokio::time::sleep(Duration::from_millis(20)).await;
+		assert!(!clients.has_subscription("1").await);
+		let (sender, _receiver) = mpsc::channel(1);
+		assert!(clients.set_sender("1", sender).await.is_err());
+		clients.subscribe("2", subscription()).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn clients_slow_client_drop() {
+		let clients = WsClients::new(ws_config(SlowClientPolicy::Drop));
+		let subscription = subscription(vec![Topic::ConfidenceAchieved], vec![]);
+		let (sender, mut receiver) = mpsc::channel(1);
+		clients.subscribe("1", subscription).await.unwrap();
+		let mut disconnected = clients.set_sender("1", sender).await.unwrap();
+
+		for expected in [true, false] {
+			let results = clients
+				.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+				.await
+				.unwrap();
+			assert_eq!(results[0].is_ok(), expected);
+		}
+		assert!(disconnected.try_recv().is_err());
+
+		assert!(receiver.recv().await.is_some());
+		let results = clients
+			.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+			.await
+			.unwrap();
+		assert!(results[0].is_ok());
+	}
+
+	#[tokio::test]
+	async fn clients_slow_client_disconnect() {
+		let clients = WsClients::new(ws_config(SlowClientPolicy::Disconnect));
+		let subscription = subscription(vec![Topic::ConfidenceAchieved], vec![]);
+		let (sender, mut receiver) = mpsc::channel(1);
+		clients.subscribe("1", subscription).await.unwrap();
+		let disconnected = clients.set_sender("1", sender).await.unwrap();
+
+		for expected in [true, false] {
+			let results = clients
+				.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+				.await
+				.unwrap();
+			assert_eq!(results[0].is_ok(), expected);
+		}
+		assert!(disconnected.await.is_ok());
+		assert!(!clients.has_subscription("1").await);
+
+		assert!(receiver.recv().await.is_some());
+		let results = clients
+			.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+			.await
+			.unwrap();
+		assert!(results.is_empty());
+	}
+
+	#[tokio::test]
+	async fn events_subscribe() {
+		let events = Events::new(2);
+		let (buffered, mut receiver) = events.subscribe(None).await.unwrap();
+		assert!(buffered.is_empty());
+
+		events
+			.publish(Topic::HeaderVerified, header_verified())
+			.await;
+		let event = receiver.recv().await.unwrap();
+		assert_eq!(event.id.to_string(), format!("{}-1", events.epoch));
+		assert_eq!(event.topic, Topic::HeaderVerified);
+	}
+
+	#[tokio::test]
+	async fn events_resume() {
+		let events = Events::with_epoch(2, 10);
+		for (topic, message) in [
+			(Topic::HeaderVerified, header_verified()),
+			(Topic::ConfidenceAchieved, confidence_achieved()),
+			(Topic::DataVerified, data_verified()),
+		] {
+			events.publish(topic, message).await;
+		}
+
+		let event_id = |id: &str| Some(id.parse::<EventId>().unwrap());
+		let sequences = |events: Vec<Arc<Event>>| {
+			events
+				.iter()
+				.map(|event| event.id.sequence)
+				.collect::<Vec<_>>()
+		};
+
+		let (buffered, _) = events.subscribe(event_id("10-2")).await.unwrap();
+		assert_eq!(sequences(buffered), vec![3]);
+
+		let (buffered, _) = events.subscribe(event_id("10-1")).await.unwrap();
+		assert_eq!(sequences(buffered), vec![2, 3]);
+
+		let (buffered, _) = events.subscribe(event_id("10-3")).await.unwrap();
+		assert!(buffered.is_empty());
+
+		assert!(events.subscribe(event_id("10-0")).await.is_none());
+		assert!(events.subscribe(event_id("10-4")).await.is_none());
+		assert!(events.subscribe(event_id("9-2")).await.is_none());
+	}
+
+	#[test_case("1-2", Some(EventId { epoch: 1, sequence: 2 }) ; "Valid")]
+	#[test_case("1", None ; "Missing sequence")]
+	#[test_case("1-", None ; "Empty sequence")]
+	#[test_case("a-2", None ; "Invalid epoch")]
+	fn event_id_parse(value: &str, expected: Option<EventId>) {
+		assert_eq!(value.parse::<EventId>().ok(), expected);
+		if let Some(event_id) = expected {
+			assert_eq!(event_id.to_string(), value);
+		}
+	}
+
 	#[test]
 	fn block_status_none() {
 		let mut state = State::default();
-		assert_eq!(block_status(&None, &state, 1), None);
+		assert_eq!(block_status(&None, &state, 1, None), None);
 		state.latest = 10;
-		assert_ne!(block_status(&None, &state, 1), None);
-		assert_eq!(block_status(&None, &state, 11), None);
+		assert_ne!(block_status(&None, &state, 1, None), None);
+		assert_eq!(block_status(&None, &state, 11, None), None);
 	}
 
 	#[test]
@@ -959,10 +2221,10 @@ mod tests {
 			..Default::default()
 		};
 		let unavailable = Some(BlockStatus::Unavailable);
-		assert_eq!(block_status(&Some(1), &state, 0), unavailable);
-		assert_eq!(block_status(&Some(10), &state, 0), unavailable);
-		assert_eq!(block_status(&Some(10), &state, 9), unavailable);
-		assert_ne!(block_status(&Some(9), &state, 9), unavailable);
+		assert_eq!(block_status(&Some(1), &state, 0, None), unavailable);
+		assert_eq!(block_status(&Some(10), &state, 0, None), unavailable);
+		assert_eq!(block_status(&Some(10), &state, 9, None), unavailable);
+		assert_ne!(block_status(&Some(9), &state, 9, None), unavailable);
 	}
 
 	#[test]
@@ -972,33 +2234,33 @@ mod tests {
 			..Default::default()
 		};
 		let pending = Some(BlockStatus::Pending);
-		assert_eq!(block_status(&Some(0), &state, 0), pending);
-		assert_eq!(block_status(&Some(0), &state, 1), pending);
-		assert_eq!(block_status(&Some(0), &state, 4), pending);
-		assert_ne!(block_status(&Some(0), &state, 5), pending);
+		assert_eq!(block_status(&Some(0), &state, 0, None), pending);
+		assert_eq!(block_status(&Some(0), &state, 1, None), pending);
+		assert_eq!(block_status(&Some(0), &state, 4, None), pending);
+		assert_ne!(block_status(&Some(0), &state, 5, None), pending);
 	}
 
 	#[test]
 	fn block_status_verifying_header() {
 		let mut state = State::default();
 		let verifying_header = Some(BlockStatus::VerifyingHeader);
-		assert_eq!(block_status(&Some(0), &state, 0), verifying_header);
+		assert_eq!(block_status(&Some(0), &state, 0, None), verifying_header);
 		state.latest = 1;
-		assert_eq!(block_status(&Some(0), &state, 1), verifying_header);
+		assert_eq!(block_status(&Some(0), &state, 1, None), verifying_header);
 		state.latest = 10;
-		assert_eq!(block_status(&Some(0), &state, 10), verifying_header);
+		assert_eq!(block_status(&Some(0), &state, 10, None), verifying_header);
 		state.latest = 11;
-		assert_ne!(block_status(&Some(10), &state, 10), verifying_header);
+		assert_ne!(block_status(&Some(10), &state, 10, None), verifying_header);
 
 		let mut state = State {
 			latest: 5,
 			sync_latest: Some(1),
 			..Default::default()
 		};
-		assert_eq!(block_status(&Some(1), &state, 1), verifying_header);
+		assert_eq!(block_status(&Some(1), &state, 1, None), verifying_header);
 		state.sync_latest = Some(2);
-		assert_eq!(block_status(&Some(1), &state, 2), verifying_header);
-		assert_ne!(block_status(&Some(1), &state, 3), verifying_header);
+		assert_eq!(block_status(&Some(1), &state, 2, None), verifying_header);
+		assert_ne!(block_status(&Some(1), &state, 3, None), verifying_header);
 	}
 
 	#[test]
@@ -1007,26 +2269,38 @@ mod tests {
 		let verifying_confidence = Some(BlockStatus::VerifyingConfidence);
 		state.latest = 10;
 		state.header_verified.set(1);
-		assert_eq!(block_status(&None, &state, 1), verifying_confidence);
+		assert_eq!(block_status(&None, &state, 1, None), verifying_confidence);
 		state.confidence_achieved.set(1);
 		state.header_verified.set(5);
 		state.confidence_achieved.set(4);
-		assert_eq!(block_status(&None, &state, 5), verifying_confidence);
-		assert_ne!(block_status(&None, &state, 4), verifying_confidence);
-		assert_ne!(block_status(&None, &state, 6), verifying_confidence);
+		assert_eq!(block_status(&None, &state, 5, None), verifying_confidence);
+		assert_ne!(block_status(&None, &state, 4, None), verifying_confidence);
+		assert_ne!(block_status(&None, &state, 6, None), verifying_confidence);
 
 		let mut state = State {
 			latest: 10,
 			..Default::default()
 		};
 		state.sync_header_verified.set(1);
-		assert_eq!(block_status(&Some(1), &state, 1), verifying_confidence);
+		assert_eq!(
+			block_status(&Some(1), &state, 1, None),
+			verifying_confidence
+		);
 		state.sync_confidence_achieved.set(1);
 		state.sync_header_verified.set(5);
 		state.sync_confidence_achieved.set(4);
-		assert_eq!(block_status(&Some(1), &state, 5), verifying_confidence);
-		assert_ne!(block_status(&Some(1), &state, 4), verifying_confidence);
-		assert_ne!(block_status(&Some(1), &state, 6), verifying_confidence);
+		assert_eq!(
+			block_status(&Some(1), &state, 5, None),
+			verifying_confidence
+		);
+		assert_ne!(
+			block_status(&Some(1), &state, 4, None),
+			verifying_confidence
+		);
+		assert_ne!(
+			block_status(&Some(1), &state, 6, None),
+			verifying_confidence
+		);
 	}
 
 	#[test]
@@ -1036,14 +2310,14 @@ mod tests {
 		state.latest = 10;
 		state.header_verified.set(1);
 		state.confidence_achieved.set(1);
-		assert_eq!(block_status(&None, &state, 1), verifying_data);
+		assert_eq!(block_status(&None, &state, 1, None), verifying_data);
 		state.data_verified.set(1);
 		state.header_verified.set(5);
 		state.confidence_achieved.set(5);
 		state.data_verified.set(4);
-		assert_eq!(block_status(&None, &state, 5), verifying_data);
-		assert_ne!(block_status(&None, &state, 4), verifying_data);
-		assert_ne!(block_status(&None, &state, 6), verifying_data);
+		assert_eq!(block_status(&None, &state, 5, None), verifying_data);
+		assert_ne!(block_status(&None, &state, 4, None), verifying_data);
+		assert_ne!(block_status(&None, &state, 6, None), verifying_data);
 
 		let mut state = State {
 			latest: 10,
@@ -1051,14 +2325,14 @@ mod tests {
 		};
 		state.sync_header_verified.set(1);
 		state.sync_confidence_achieved.set(1);
-		assert_eq!(block_status(&Some(1), &state, 1), verifying_data);
+		assert_eq!(block_status(&Some(1), &state, 1, None), verifying_data);
 		state.sync_data_verified.set(1);
 		state.sync_header_verified.set(5);
 		state.sync_confidence_achieved.set(5);
 		state.sync_data_verified.set(4);
-		assert_eq!(block_status(&Some(1), &state, 5), verifying_data);
-		assert_ne!(block_status(&Some(1), &state, 4), verifying_data);
-		assert_ne!(block_status(&Some(1), &state, 6), verifying_data);
+		assert_eq!(block_status(&Some(1), &state, 5, None), verifying_data);
+		assert_ne!(block_status(&Some(1), &state, 4, None), verifying_data);
+		assert_ne!(block_status(&Some(1), &state, 6, None), verifying_data);
 	}
 
 	#[test]
@@ -1068,12 +2342,12 @@ mod tests {
 		state.latest = 10;
 		state.header_verified.set(1);
 		state.data_verified.set(1);
-		assert_eq!(block_status(&None, &state, 1), finished);
+		assert_eq!(block_status(&None, &state, 1, None), finished);
 		state.header_verified.set(5);
 		state.data_verified.set(5);
-		assert_eq!(block_status(&None, &state, 4), finished);
-		assert_eq!(block_status(&None, &state, 5), finished);
-		assert_ne!(block_status(&None, &state, 6), finished);
+		assert_eq!(block_status(&None, &state, 4, None), finished);
+		assert_eq!(block_status(&None, &state, 5, None), finished);
+		assert_ne!(block_status(&None, &state, 6, None), finished);
 
 		let mut state = State {
 			latest: 10,
@@ -1081,11 +2355,79 @@ mod tests {
 		};
 		state.sync_header_verified.set(1);
 		state.sync_data_verified.set(1);
-		assert_eq!(block_status(&Some(1), &state, 1), finished);
+		assert_eq!(block_status(&Some(1), &state, 1, None), finished);
 		state.sync_header_verified.set(5);
 		state.sync_data_verified.set(5);
-		assert_eq!(block_status(&Some(1), &state, 4), finished);
-		assert_eq!(block_status(&Some(1), &state, 5), finished);
-		assert_ne!(block_status(&Some(1), &state, 6), finished);
+		assert_eq!(block_status(&Some(1), &state, 4, None), finished);
+		assert_eq!(block_status(&Some(1), &state, 5, None), finished);
+		assert_ne!(block_status(&Some(1), &state, 6, None), finished);
+	}
+
+	#[test]
+	fn block_status_stored() {
+		let state = State {
+			latest: 10,
+			..Default::default()
+		};
+		let mut block_state = BlockState {
+			failure: Some("Failed to fetch cells".to_string()),
+			..Default::default()
+		};
+		let pending = Some(BlockStatus::Pending);
+		let unavailable = Some(BlockStatus::Unavailable);
+		assert_eq!(
+			block_status(&Some(5), &state, 5, Some(&block_state)),
+			pending
+		);
+		assert_eq!(
+			block_status(&Some(5), &state, 1, Some(&block_state)),
+			unavailable
+		);
+
+		block_state.header_verified = true;
+		let verifying_confidence = Some(BlockStatus::VerifyingConfidence);
+		assert_eq!(
+			block_status(&Some(5), &state, 1, Some(&block_state)),
+			verifying_confidence
+		);
+
+		block_state.confidence_achieved = true;
+		let verifying_data = Some(BlockStatus::VerifyingData);
+		assert_eq!(
+			block_status(&Some(5), &state, 1, Some(&block_state)),
+			verifying_data
+		);
+
+		block_state.data_verified = true;
+		let finished = Some(BlockStatus::Finished);
+		assert_eq!(block_status(&None, &state, 1, Some(&block_state)), finished);
+		assert_eq!(block_status(&None, &state, 11, Some(&block_state)), None);
+	}
+
+	#[test_case(0 => Some((4, 5)) ; "First transaction")]
+	#[test_case(1 => Some((4, 6)) ; "Transaction across cells")]
+	#[test_case(2 => Some((5, 9)) ; "Transaction with two bytes length")]
+	#[test_case(3 => None ; "Missing transaction")]
+	fn cell_range_of_data_transaction(index: usize) -> Option<(u32, u32)> {
+		// Encoded transactions are at bytes [1, 12), [12, 53) and [53, 155)
+		let data = vec![vec![1; 10], vec![2; 40], vec![3; 100]];
+		CellRange::of_data_transaction(4, &data, index).map(|range| (range.start, range.end))
+	}
+
+	#[test_case(Ok(DryRunOutcome::Success) => (Some(true), None) ; "Success")]
+	#[test_case(Ok(DryRunOutcome::Invalid) => (Some(false), Some("Invalid transaction".to_string())) ; "Invalid")]
+	#[test_case(Err(anyhow!("Method not found")) => (None, Some("Dry run is unavailable: Method not found".to_string())) ; "Dry run unavailable")]
+	fn estimate_response(outcome: anyhow::Result<DryRunOutcome>) -> (Option<bool>, Option<String>) {
+		let info = RuntimeDispatchInfo {
+			weight: Weight {
+				ref_time: 1_000,
+				proof_size: 0,
+			},
+			class: "normal".to_string(),
+			partial_fee: 10,
+		};
+		let response = EstimateResponse::new(info, outcome, 1);
+		assert_eq!(response.fee, 11);
+		(response.valid, response.error)
 	}
 }
@@ -1,41 +1,55 @@
 use super::{
-	transactions,
+	handlers, transactions,
 	types::{
-		Payload, Request, Response, Status, Transaction, Version, WsClients, WsError, WsResponse,
+		DataQuery, FieldsQueryParameter, Payload, PublishMessage, Request, Response, Status,
+		SubmitQuery, Topic, Version, WsClients, WsError, WsResponse,
 	},
 };
 use crate::{
-	api::v2::types::{Error, Sender},
+	api::{
+		auth::Permissions,
+		v2::types::{Error, Sender},
+	},
+	data::Database,
 	network::rpc::Node,
-	types::{RuntimeConfig, State},
+	types::{ApiScope, RuntimeConfig, State},
 };
 use anyhow::Context;
 use futures::{FutureExt, StreamExt};
 use serde::Serialize;
-use std::sync::{Arc, Mutex};
+use std::{
+	collections::HashSet,
+	ops::RangeInclusive,
+	sync::{Arc, Mutex},
+};
 use tokio::sync::mpsc;
-use tokio_stream::wrappers::UnboundedReceiverStream;
-use tracing::{error, log::warn};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error, log::warn};
 use warp::ws::{self, Message, WebSocket};
 
 #[allow(clippy::too_many_arguments)]
 pub async fn connect(
 	subscription_id: String,
 	web_socket: WebSocket,
+	permissions: Permissions,
 	clients: WsClients,
 	version: Version,
 	config: RuntimeConfig,
 	node: Node,
 	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync + 'static>>,
 	state: Arc<Mutex<State>>,
+	db: impl Database,
 ) {
 	let (web_socket_sender, mut web_socket_receiver) = web_socket.split();
-	let (sender, receiver) = mpsc::unbounded_channel();
-	let receiver_stream = UnboundedReceiverStream::new(receiver);
+	let (sender, receiver) = mpsc::channel(clients.config.queue_size);
+	let receiver_stream = ReceiverStream::new(receiver);
 
-	if let Err(error) = clients.set_sender(&subscription_id, sender.clone()).await {
-		error!("Cannot set sender: {error}");
-		return;
+	let mut disconnected = match clients.set_sender(&subscription_id, sender.clone()).await {
+		Ok(disconnected) => disconnected,
+		Err(error) => {
+			error!("Cannot set sender: {error}");
+			return;
+		},
 	};
 
 	tokio::task::spawn(receiver_stream.forward(web_socket_sender).map(|result| {
@@ -44,17 +58,67 @@ pub async fn connect(
 		}
 	}));
 
-	fn send<T: Serialize>(sender: Sender, message: T) -> anyhow::Result<()> {
+	let subscription = clients
+		.subscription(&subscription_id)
+		.await
+		.unwrap_or_default();
+	if let Some(from_block) = subscription.from_block {
+		let mut replayed = HashSet::new();
+		let mut next_block = from_block;
+		// Replay is repeated until it catches up with the latest block,
+		// so live messages buffered in the meantime are mostly skipped
+		loop {
+			let latest = state.lock().expect("State lock can be acquired").latest;
+			let first_block = next_block.max(clients.config.first_replay_block(latest));
+			if first_block > latest {
+				break;
+			}
+			let replay_result = replay(
+				&subscription_id,
+				&subscription.topics,
+				first_block..=latest,
+				&clients,
+				&config,
+				&db,
+				&mut replayed,
+			)
+			.await;
+			if let Err(error) = replay_result {
+				error!("Cannot replay stored messages: {error:#}");
+				break;
+			}
+			next_block = latest + 1;
+		}
+		debug!(from_block, "Replayed {} messages", replayed.len());
+
+		if let Err(error) = clients.finish_replay(&subscription_id, replayed).await {
+			error!("Cannot send buffered messages: {error:#}");
+		}
+	}
+
+	async fn send<T: Serialize>(sender: Sender, message: T) -> anyhow::Result<()> {
 		let ws_message = serde_json::to_string(&message)
 			.map(ws::Message::text)
 			.context("Failed to serialize message")?;
 
 		sender
 			.send(Ok(ws_message))
+			.await
 			.context("Failed to send message")
 	}
 
-	while let Some(result) = web_socket_receiver.next().await {
+	loop {
+		let result = tokio::select! {
+			_ = &mut disconnected => {
+				debug!(subscription_id, "Closing web socket connection");
+				break;
+			},
+			result = web_socket_receiver.next() => match result {
+				Some(result) => result,
+				None => break,
+			},
+		};
+
 		let message = match result {
 			Err(error) => {
 				error!("Error receiving client message: {error}");
@@ -67,36 +131,100 @@ pub async fn connect(
 		let submitter = submitter.clone();
 		let state = state.clone();
 
-		let send_result =
-			match handle_request(message, &version, &config, &node, submitter, state).await {
-				Ok(response) => send(sender.clone(), response),
-				Err(error) => {
-					if let Some(cause) = error.cause.as_ref() {
-						error!("Failed to handle request: {cause:#}");
-					};
-					send::<WsError>(sender.clone(), error.into())
-				},
-			};
+		let send_result = match handle_request(
+			message,
+			&subscription_id,
+			&permissions,
+			&clients,
+			&version,
+			&config,
+			&node,
+			submitter,
+			state,
+			db.clone(),
+		)
+		.await
+		{
+			Ok(response) => send(sender.clone(), response).await,
+			Err(error) => {
+				if let Some(cause) = error.cause.as_ref() {
+					error!("Failed to handle request: {cause:#}");
+				};
+				send::<WsError>(sender.clone(), error.into()).await
+			},
+		};
 
 		if let Err(error) = send_result {
 			warn!("Error sending message: {error:#}");
 		}
 	}
+
+	clients.remove_sender(&subscription_id, &sender).await;
 }
 
+/// Sends stored messages on the subscribed topics in the given block range.
+/// Live messages are buffered in the meantime, and the replayed ones are skipped when they are sent.
+/// Dropped messages are not marked as replayed, so their buffered live copies are sent instead.
+async fn replay(
+	subscription_id: &str,
+	topics: &HashSet<Topic>,
+	blocks: RangeInclusive<u32>,
+	clients: &WsClients,
+	config: &RuntimeConfig,
+	db: &impl Database,
+	replayed: &mut HashSet<(Topic, u32)>,
+) -> anyhow::Result<()> {
+	let topics = [
+		Topic::HeaderVerified,
+		Topic::ConfidenceAchieved,
+		Topic::DataVerified,
+	]
+	.into_iter()
+	.filter(|topic| topics.contains(topic))
+	.collect::<Vec<_>>();
+
+	for block_number in blocks {
+		for &topic in &topics {
+			let Some(message) = PublishMessage::load(db, topic, block_number, config.app_id)?
+			else {
+				continue;
+			};
+			if clients.send_replayed(subscription_id, message).await? {
+				replayed.insert((topic, block_number));
+			}
+		}
+	}
+
+	Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn handle_request(
 	message: Message,
+	subscription_id: &str,
+	permissions: &Permissions,
+	clients: &WsClients,
 	version: &Version,
 	config: &RuntimeConfig,
 	node: &Node,
 	submitter: Option<Arc<impl transactions::Submit>>,
 	state: Arc<Mutex<State>>,
+	db: impl Database,
 ) -> Result<WsResponse, Error> {
 	let request = Request::try_from(message).map_err(|error| {
 		Error::bad_request_unknown(&format!("Failed to parse request: {error}"))
 	})?;
 
 	let request_id = request.request_id;
+	let is_async = matches!(request.payload, Payload::SubmitAsync(_));
+	let is_block_query = matches!(
+		request.payload,
+		Payload::Block(_) | Payload::BlockHeader(_) | Payload::BlockData(_)
+	);
+	if is_block_query && !permissions.has_scope(ApiScope::Read) {
+		return Err(Error::forbidden(request_id, "Block query is not allowed."));
+	}
+
 	match request.payload {
 		Payload::Version => Ok(Response::new(request_id, version.clone()).into()),
 		Payload::Status => {
@@ -104,22 +232,69 @@ async fn handle_request(
 			let status = Status::new(config, node, &state);
 			Ok(Response::new(request_id, status).into())
 		},
-		Payload::Submit(transaction) => {
-			let Some(submitter) = submitter else {
-				return Err(Error::bad_request(request_id, "Submit is not configured."));
+		Payload::Block(block) => handlers::block(block.block_number, config.clone(), state, db)
+			.await
+			.map(|block| Response::new(request_id, block).into())
+			.map_err(|error| error.with_request_id(request_id)),
+		Payload::BlockHeader(block) => {
+			handlers::block_header(block.block_number, config.clone(), state, db)
+				.await
+				.map(|header| Response::new(request_id, header).into())
+				.map_err(|error| error.with_request_id(request_id))
+		},
+		Payload::BlockData(block) => {
+			let query = DataQuery {
+				fields: block.fields.map(FieldsQueryParameter),
 			};
-			if transaction.is_empty() {
-				return Err(Error::bad_request(request_id, "Transaction is empty."));
+			handlers::block_data(block.block_number, query, config.clone(), state, db)
+				.await
+				.map(|data| Response::new(request_id, data).into())
+				.map_err(|error| error.with_request_id(request_id))
+		},
+		Payload::Subscribe(subscribe) => clients
+			.update_subscription(subscription_id, |subscription| {
+				subscription.topics.extend(subscribe.topics);
+				if let Some(data_fields) = subscribe.data_fields {
+					subscription.data_fields = data_fields;
+				}
+			})
+			.await
+			.map(|subscription| Response::new(request_id, subscription).into())
+			.ok_or_else(|| Error::bad_request(request_id, "Subscription is not found.")),
+		Payload::Unsubscribe(unsubscribe) => clients
+			.update_subscription(subscription_id, |subscription| {
+				subscription
+					.topics
+					.retain(|topic| !unsubscribe.topics.contains(topic));
+			})
+			.await
+			.map(|subscription| Response::new(request_id, subscription).into())
+			.ok_or_else(|| Error::bad_request(request_id, "Subscription is not found.")),
+		Payload::Submit(transaction) | Payload::SubmitAsync(transaction) => {
+			if !permissions.has_scope(ApiScope::Submit) {
+				return Err(Error::forbidden(request_id, "Submit is not allowed."));
 			}
-			if matches!(transaction, Transaction::Data(_)) && !submitter.has_signer() {
-				return Err(Error::bad_request(request_id, "Signer is not configured."));
+			let Some(submitter) = submitter else {
+				return Err(Error::not_found().with_request_id(request_id));
+			};
+			let query = SubmitQuery {
+				is_async,
+				key: request.key,
+				app_id: request.app_id,
 			};
+			let permissions = permissions.clone();
+
+			if is_async {
+				return handlers::submit_async(submitter, permissions, query, transaction)
+					.await
+					.map(|submission| Response::new(request_id, submission).into())
+					.map_err(|error| error.with_request_id(request_id));
+			}
 
-			submitter
-				.submit(transaction)
+			handlers::submit_and_wait(submitter, permissions, query, transaction)
 				.await
 				.map(|response| Response::new(request_id, response).into())
-				.map_err(Error::internal_server_error)
+				.map_err(|error| error.with_request_id(request_id))
 		},
 	}
 }
@@ -16,7 +16,6 @@ use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use avail_core::AppId;
 use avail_subxt::utils::H256;
-use codec::Encode;
 use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
 use kate_recovery::{
 	com::{
@@ -31,7 +30,6 @@ use kate_recovery::{
 use mockall::automock;
 use rand::SeedableRng as _;
 use rand_chacha::ChaChaRng;
-use rocksdb::DB;
 use std::{
 	collections::{HashMap, HashSet},
 	ops::Range,
@@ -41,7 +39,7 @@ use tokio::sync::{broadcast, mpsc::Sender};
 use tracing::{debug, error, info, instrument};
 
 use crate::{
-	data::store_encoded_data_in_db,
+	data::Database,
 	network::{p2p::Client as P2pClient, rpc::Client as RpcClient},
 	proof,
 	types::{AppClientConfig, BlockVerified, OptionBlockRange, State},
@@ -72,18 +70,10 @@ trait AppClient {
 		dimensions: Dimensions,
 		block_hash: H256,
 	) -> Result<Vec<Option<Vec<u8>>>>;
-
-	fn store_encoded_data_in_db<T: Encode + 'static>(
-		&self,
-		app_id: AppId,
-		block_number: u32,
-		data: &T,
-	) -> Result<()>;
 }
 
 #[derive(Clone)]
 struct AppClientImpl {
-	db: Arc<DB>,
 	p2p_client: P2pClient,
 	rpc_client: RpcClient,
 }
@@ -212,16 +202,6 @@ impl AppClient for AppClientImpl {
 		}
 		Ok(result)
 	}
-
-	fn store_encoded_data_in_db<T: Encode + 'static>(
-		&self,
-		app_id: AppId,
-		block_number: u32,
-		data: &T,
-	) -> Result<()> {
-		store_encoded_data_in_db(self.db.clone(), app_id, block_number, &data)
-			.context("Failed to store data into database")
-	}
 }
 
 fn new_data_cell(row: usize, col: usize, data: &[u8]) -> Result<DataCell> {
@@ -292,6 +272,7 @@ async fn fetch_verified(
 #[instrument(skip_all, fields(block = block.block_num), level = "trace")]
 async fn process_block(
 	app_client: impl AppClient,
+	db: &impl Database,
 	cfg: &AppClientConfig,
 	app_id: AppId,
 	block: &BlockVerified,
@@ -406,8 +387,7 @@ async fn process_block(
 		.context("Failed to decode app extrinsics")?;
 
 	debug!(block_number, "Storing data into database");
-	app_client
-		.store_encoded_data_in_db(app_id, block_number, &data)
+	db.store_data(app_id.0, block_number, &data)
 		.context("Failed to store data into database")?;
 
 	let bytes_count = data.iter().fold(0usize, |acc, x| acc + x.len());
@@ -421,7 +401,7 @@ async fn process_block(
 /// # Arguments
 ///
 /// * `cfg` - Application client configuration
-/// * `db` - Database to store data inot DB
+/// * `db` - Database to store data into
 /// * `network_client` - Reference to a libp2p custom network client
 /// * `rpc_client` - Node's RPC subxt client for fetching data unavailable in DHT (if configured)
 /// * `app_id` - Application ID
@@ -430,7 +410,7 @@ async fn process_block(
 #[allow(clippy::too_many_arguments)]
 pub async fn run(
 	cfg: AppClientConfig,
-	db: Arc<DB>,
+	db: impl Database,
 	network_client: P2pClient,
 	rpc_client: RpcClient,
 	app_id: AppId,
@@ -444,10 +424,18 @@ pub async fn run(
 	info!("Starting for app {app_id}...");
 
 	fn set_data_verified_state(
+		db: &impl Database,
 		state: Arc<Mutex<State>>,
 		sync_range: &Range<u32>,
 		block_number: u32,
 	) {
+		if let Err(error) = db.update_block_state(block_number, |block_state| {
+			block_state.data_verified = true;
+			block_state.failure = None;
+		}) {
+			error!(block_number, "Cannot store block state: {error:#}");
+		}
+
 		let mut state = state.lock().expect("State lock can be acquired");
 		match sync_range.contains(&block_number) {
 			true => state.sync_data_verified.set(block_number),
@@ -480,27 +468,28 @@ pub async fn run(
 				block_number,
 				"Skipping block with no cells for app {app_id}"
 			);
-			set_data_verified_state(state.clone(), &sync_range, block_number);
+			set_data_verified_state(&db, state.clone(), &sync_range, block_number);
 			continue;
 		}
 
-		let db_clone = db.clone();
 		let app_client = AppClientImpl {
-			db: db_clone,
 			p2p_client: network_client.clone(),
 			rpc_client: rpc_client.clone(),
 		};
-		let data = match process_block(app_client, &cfg, app_id, &block, pp.clone()).await {
+		let data = match process_block(app_client, &db, &cfg, app_id, &block, pp.clone()).await {
 			Ok(data) => data,
 			Err(error) => {
 				error!(block_number, "Cannot process block: {error}");
+				if let Err(error) = db.store_block_failure(block_number, format!("{error:#}")) {
+					error!(block_number, "Cannot store block state: {error:#}");
+				}
 				if let Err(error) = error_sender.send(error).await {
 					error!("Cannot send error message: {error}");
 				}
 				return;
 			},
 		};
-		set_data_verified_state(state.clone(), &sync_range, block_number);
+		set_data_verified_state(&db, state.clone(), &sync_range, block_number);
 		if let Err(error) = data_verified_sender.send((block_number, data)) {
 			error!("Cannot send data verified message: {error}");
 			if let Err(error) = error_sender.send(error.into()).await {
@@ -515,7 +504,10 @@ pub async fn run(
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::types::{AppClientConfig, RuntimeConfig};
+	use crate::{
+		data::MemoryDB,
+		types::{AppClientConfig, RuntimeConfig},
+	};
 	use avail_core::DataLookup;
 	use hex_literal::hex;
 	use kate_recovery::{matrix::Dimensions, testnet};
@@ -568,12 +560,11 @@ mod tests {
 		mock_client
 			.expect_reconstruct_rows_from_dht()
 			.returning(|_, _, _, _, _| Box::pin(async move { Ok(vec![]) }));
-		mock_client
-			.expect_store_encoded_data_in_db()
-			.returning(|_, _, _: &Vec<Vec<u8>>| Ok(()));
-		process_block(mock_client, &cfg, AppId(1), &block, pp)
+		let db = MemoryDB::default();
+		let data = process_block(mock_client, &db, &cfg, AppId(1), &block, pp)
 			.await
 			.unwrap();
+		assert_eq!(db.get_data(1, block.block_num).unwrap(), Some(data));
 	}
 
 	#[tokio::test]
@@ -632,11 +623,10 @@ mod tests {
 		mock_client
 			.expect_reconstruct_rows_from_dht()
 			.returning(|_, _, _, _, _| Box::pin(async move { Ok(vec![]) }));
-		mock_client
-			.expect_store_encoded_data_in_db()
-			.returning(|_, _, _: &Vec<Vec<u8>>| Ok(()));
-		process_block(mock_client, &cfg, AppId(1), &block, pp)
+		let db = MemoryDB::default();
+		let data = process_block(mock_client, &db, &cfg, AppId(1), &block, pp)
 			.await
 			.unwrap();
+		assert_eq!(db.get_data(1, block.block_num).unwrap(), Some(data));
 	}
 }
@@ -0,0 +1,684 @@
+//! Export and import of verified data as portable archives.
+//!
+//! Archive is a JSON lines file. The first line is an archive header with the schema version
+//! and the genesis hash of the network, followed by one line per block with the verified header,
+//! confidence and decoded application data (if exported), in ascending block order.
+//!
+//! Archived blocks form a contiguous chain, which is imported only if it is anchored
+//! to a finality verified header already stored in the database.
+//! On a fresh machine, the light client has to run until it verifies finality of the block
+//! at which (or right before which) the archive ends.
+
+use anyhow::{anyhow, bail, Context, Result};
+use avail_subxt::{primitives::Header as DaHeader, utils::H256};
+use base64::{engine::general_purpose, Engine};
+use codec::Encode;
+use kate_recovery::com::AppData;
+use serde::{Deserialize, Serialize};
+use sp_core::blake2_256;
+use std::io::{BufRead, Seek, Write};
+use tracing::info;
+
+use crate::data::Database;
+
+/// Version of the archive format, incremented on incompatible changes
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Maximum number of blocks held in memory on import, before they are written into the database
+const IMPORT_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Record {
+	Archive(ArchiveHeader),
+	Block(Block),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveHeader {
+	schema_version: u32,
+	genesis_hash: H256,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	app_id: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Block {
+	block_number: u32,
+	header: DaHeader,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	confidence: Option<u32>,
+	/// Base64 encoded application data transactions
+	#[serde(skip_serializing_if = "Option::is_none")]
+	app_data: Option<Vec<String>>,
+}
+
+/// Decodes archived application data, which can be imported only for the configured application
+fn decode_app_data(
+	block: &Block,
+	archive_app_id: Option<u32>,
+	app_id: Option<u32>,
+) -> Result<Option<AppData>> {
+	let Some(app_data) = block.app_data.as_ref() else {
+		return Ok(None);
+	};
+	if archive_app_id.is_none() || archive_app_id != app_id {
+		bail!(
+			"Archived app data of block {} doesn't belong to the configured app",
+			block.block_number
+		);
+	}
+	app_data
+		.iter()
+		.map(|transaction| general_purpose::STANDARD.decode(transaction))
+		.collect::<Result<_, _>>()
+		.map(Some)
+		.with_context(|| format!("Failed to decode app data of block {}", block.block_number))
+}
+
+fn hash(header: &DaHeader) -> H256 {
+	Encode::using_encoded(header, blake2_256).into()
+}
+
+fn write_record(writer: &mut impl Write, record: &Record) -> Result<()> {
+	serde_json::to_writer(&mut *writer, record).context("Failed to serialize archive record")?;
+	writeln!(writer).context("Failed to write archive record")
+}
+
+/// Exports blocks with verified headers in the given range into the archive.
+/// Application data is exported only if `app_id` is given.
+/// Fails if any header in the range is missing, since archive has to be a contiguous chain.
+///
+/// Returns number of exported blocks.
+pub fn export(
+	db: &impl Database,
+	mut writer: impl Write,
+	from: u32,
+	to: u32,
+	app_id: Option<u32>,
+) -> Result<usize> {
+	if from > to {
+		bail!("Invalid block range {from}..{to}");
+	}
+
+	let genesis_hash = db
+		.get_genesis_hash()?
+		.context("Genesis hash is not found in the database")?;
+
+	let archive_header = ArchiveHeader {
+		schema_version: SCHEMA_VERSION,
+		genesis_hash,
+		app_id,
+	};
+	write_record(&mut writer, &Record::Archive(archive_header))?;
+
+	let mut exported = 0;
+	for block_number in from..=to {
+		let header = db
+			.get_header(block_number)?
+			.with_context(|| format!("Header {block_number} is not found in the database"))?;
+
+		let app_data = match app_id {
+			Some(app_id) => db.get_data(app_id, block_number)?,
+			None => None,
+		};
+
+		let block = Block {
+			block_number,
+			header,
+			confidence: db.get_confidence(block_number)?,
+			app_data: app_data.map(|data| {
+				data.iter()
+					.map(|transaction| general_purpose::STANDARD.encode(transaction))
+					.collect()
+			}),
+		};
+		write_record(&mut writer, &Record::Block(block))?;
+		exported += 1;
+	}
+
+	writer.flush().context("Failed to flush archive")?;
+	info!("Exported {exported} blocks in range {from}..{to}");
+	Ok(exported)
+}
+
+/// Reads the archive header, and returns it with the iterator over the archived blocks,
+/// which reads one block at a time, so the archive doesn't have to fit into memory
+fn read_records(
+	reader: impl BufRead,
+) -> Result<(ArchiveHeader, impl Iterator<Item = Result<Block>>)> {
+	let mut lines = reader.lines().enumerate();
+
+	let (_, first) = lines.next().context("Archive is empty")?;
+	let Record::Archive(archive_header) =
+		serde_json::from_str(&first?).context("Failed to deserialize archive header")?
+	else {
+		bail!("Archive header is missing");
+	};
+
+	let blocks = lines.filter_map(|(index, line)| {
+		let line = match line.context("Failed to read archive") {
+			Ok(line) => line,
+			Err(error) => return Some(Err(error)),
+		};
+		if line.trim().is_empty() {
+			return None;
+		}
+		let record = serde_json::from_str(&line)
+			.with_context(|| format!("Failed to deserialize archive line {}", index + 1));
+		Some(record.and_then(|record| match record {
+			Record::Block(block) => Ok(block),
+			Record::Archive(_) => Err(anyhow!("Unexpected archive header at line {}", index + 1)),
+		}))
+	});
+
+	Ok((archive_header, blocks))
+}
+
+/// Checks that blocks form a contiguous chain as they are read, and that the first header
+/// is linked to its parent, if the parent is already in the database.
+#[derive(Default)]
+struct Linkage {
+	previous: Option<DaHeader>,
+}
+
+impl Linkage {
+	fn verify(&mut self, db: &impl Database, block: &Block) -> Result<()> {
+		let header = &block.header;
+		if header.number != block.block_number {
+			bail!(
+				"Header number {} doesn't match block number {}",
+				header.number,
+				block.block_number
+			);
+		}
+
+		let parent = match self.previous.take() {
+			Some(previous) if previous.number.checked_add(1) == Some(header.number) => {
+				Some(previous)
+			},
+			Some(previous) => bail!(
+				"Block {} doesn't follow block {}, archive is not contiguous",
+				header.number,
+				previous.number
+			),
+			None if header.number == 0 => None,
+			None => db.get_header(header.number - 1)?,
+		};
+
+		if let Some(parent) = parent {
+			if hash(&parent) != header.parent_hash {
+				bail!(
+					"Header {} is not linked to its parent {}",
+					header.number,
+					parent.number
+				);
+			}
+		}
+
+		self.previous = Some(header.clone());
+		Ok(())
+	}
+}
+
+/// Headers fetched from the RPC are marked as verified without any checks,
+/// so only headers with verified finality can be trusted.
+fn is_finality_verified(db: &impl Database, block_number: u32) -> Result<bool> {
+	Ok(db
+		.get_block_state(block_number)?
+		.is_some_and(|block_state| block_state.finality_verified))
+}
+
+/// Checks that the last archived header is anchored to a finality verified header stored
+/// in the database, either by being that header, or by being the parent of it.
+/// Since parent hashes only prove ancestry, archived chain can be anchored only backwards,
+/// from the verified descendant.
+fn verify_anchor(db: &impl Database, last: &Block) -> Result<()> {
+	let last_hash = hash(&last.header);
+
+	if is_finality_verified(db, last.block_number)? {
+		if let Some(header) = db.get_header(last.block_number)? {
+			if hash(&header) != last_hash {
+				bail!(
+					"Header {} doesn't match the verified one",
+					last.block_number
+				);
+			}
+			return Ok(());
+		}
+	}
+
+	if let Some(child_number) = last.block_number.checked_add(1) {
+		if is_finality_verified(db, child_number)? {
+			if let Some(child) = db.get_header(child_number)? {
+				if child.parent_hash != last_hash {
+					bail!(
+						"Header {} is not linked to the verified header {child_number}",
+						last.block_number
+					);
+				}
+				return Ok(());
+			}
+		}
+	}
+
+	bail!(
+		"Archive is not anchored, finality of header {} or its child has to be verified first, \
+		by running the light client until it reaches that block",
+		last.block_number
+	)
+}
+
+/// Writes verified blocks into the database, emptying the batch
+fn write_blocks(
+	db: &impl Database,
+	app_id: Option<u32>,
+	batch: &mut Vec<(Block, Option<AppData>)>,
+) -> Result<()> {
+	for (block, block_app_data) in batch.drain(..) {
+		let block_number = block.block_number;
+		db.store_header(block_number, &block.header)?;
+		if let Some(confidence) = block.confidence {
+			db.store_confidence(block_number, confidence)?;
+		}
+		if let (Some(app_id), Some(app_data)) = (app_id, block_app_data.as_ref()) {
+			db.store_data(app_id, block_number, app_data)?;
+		}
+		db.update_block_state(block_number, |block_state| {
+			block_state.header_verified = true;
+			block_state.finality_verified = true;
+			block_state.confidence_achieved |= block.confidence.is_some();
+			block_state.data_verified |= block_app_data.is_some();
+		})?;
+	}
+	Ok(())
+}
+
+/// Imports archived blocks into the database and marks their headers as finality verified,
+/// after checking the schema version, the genesis hash, the header linkage, and that
+/// the archived chain is anchored to a finality verified header stored in the database.
+///
+/// Archived confidence and application data are imported as verified by the exporting
+/// light client. Application data is imported only if it belongs to the configured `app_id`,
+/// otherwise the import fails.
+///
+/// Archive is read twice, so it doesn't have to fit into memory: first to verify it,
+/// in which case nothing is written if any of the checks fails, and then to write it
+/// in batches of [`IMPORT_BATCH_SIZE`] blocks. Archive must not change in the meantime.
+///
+/// Returns number of imported blocks.
+pub fn import(
+	db: &impl Database,
+	mut reader: impl BufRead + Seek,
+	app_id: Option<u32>,
+) -> Result<usize> {
+	let (archive_header, blocks) = read_records(&mut reader)?;
+
+	if archive_header.schema_version != SCHEMA_VERSION {
+		bail!(
+			"Unsupported archive schema version {} (expected {SCHEMA_VERSION})",
+			archive_header.schema_version
+		);
+	}
+
+	let genesis_hash = db.get_genesis_hash()?.context(
+		"Genesis hash is not found in the database, the light client has to be started first",
+	)?;
+	if genesis_hash != archive_header.genesis_hash {
+		bail!(
+			"Archive genesis hash {:?} doesn't match the stored one {genesis_hash:?}",
+			archive_header.genesis_hash
+		);
+	}
+
+	let mut linkage = Linkage::default();
+	let mut last = None;
+	for block in blocks {
+		let block = block?;
+		linkage.verify(db, &block)?;
+		decode_app_data(&block, archive_header.app_id, app_id)?;
+		last = Some(block);
+	}
+	let Some(last) = last else {
+		info!("Imported 0 blocks");
+		return Ok(0);
+	};
+	verify_anchor(db, &last)?;
+	let last_hash = hash(&last.header);
+
+	reader.rewind().context("Failed to rewind archive")?;
+	let (_, blocks) = read_records(&mut reader)?;
+
+	// Linkage is verified again, and the last batch is written only if it ends with the anchored header
+	let mut linkage = Linkage::default();
+	let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+	let mut imported = 0;
+	for block in blocks {
+		let block = block?;
+		linkage.verify(db, &block)?;
+		let block_app_data = decode_app_data(&block, archive_header.app_id, app_id)?;
+		batch.push((block, block_app_data));
+		if batch.len() == IMPORT_BATCH_SIZE {
+			imported += batch.len();
+			write_blocks(db, app_id, &mut batch)?;
+			info!("Imported {imported} blocks");
+		}
+	}
+
+	if linkage.previous.as_ref().map(hash) != Some(last_hash) {
+		bail!("Archive has changed during the import");
+	}
+	imported += batch.len();
+	write_blocks(db, app_id, &mut batch)?;
+
+	info!("Imported {imported} blocks");
+	Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{export, hash, import, IMPORT_BATCH_SIZE};
+	use crate::{
+		data::{Database, MemoryDB},
+		types::BlockState,
+		utils::test_utils::header,
+	};
+	use sp_core::H256;
+	use std::io::Cursor;
+
+	fn db_with_chain(length: u32) -> MemoryDB {
+		let db = MemoryDB::default();
+		db.store_genesis_hash(H256::repeat_byte(1)).unwrap();
+		let mut parent_hash = H256::default();
+		for number in 1..=length {
+			let header = header(number, parent_hash);
+			parent_hash = hash(&header);
+			db.store_header(number, &header).unwrap();
+			db.store_confidence(number, 10).unwrap();
+			db.store_data(1, number, &vec![vec![number as u8]]).unwrap();
+		}
+		db
+	}
+
+	/// Database of the light client which has verified finality of the given block
+	fn verified_db(source: &MemoryDB, number: u32) -> MemoryDB {
+		let db = MemoryDB::default();
+		db.store_genesis_hash(H256::repeat_byte(1)).unwrap();
+		let header = source.get_header(number).unwrap().unwrap();
+		db.store_header(number, &header).unwrap();
+		db.update_block_state(number, |block_state| {
+			block_state.header_verified = true;
+			block_state.finality_verified = true;
+		})
+		.unwrap();
+		db
+	}
+
+	#[test]
+	fn export_and_import() {
+		let source = db_with_chain(5);
+		let mut archive = vec![];
+		assert_eq!(export(&source, &mut archive, 2, 4, Some(1)).unwrap(), 3);
+
+		let target = verified_db(&source, 5);
+		assert_eq!(import(&target, Cursor::new(&archive), Some(1)).unwrap(), 3);
+
+		for number in 2..=4 {
+			assert_eq!(
+				target.get_header(number).unwrap(),
+				source.get_header(number).unwrap()
+			);
+			assert_eq!(target.get_confidence(number).unwrap(), Some(10));
+			assert_eq!(
+				target.get_data(1, number).unwrap(),
+				source.get_data(1, number).unwrap()
+			);
+			let block_state = BlockState {
+				header_verified: true,
+				finality_verified: true,
+				confidence_achieved: true,
+				data_verified: true,
+				..Default::default()
+			};
+			assert_eq!(target.get_block_state(number).unwrap(), Some(block_state));
+		}
+		assert!(!target.has_header(1).unwrap());
+	}
+
+	#[test]
+	fn import_app_data_of_other_app() {
+		let source = db_with_chain(4);
+		let mut archive = vec![];
+		export(&source, &mut archive, 2, 4, Some(1)).unwrap();
+
+		let target = verified_db(&source, 4);
+
+		for app_id in [None, Some(2)] {
+			assert!(import(&target, Cursor::new(&archive), app_id).is_err());
+			assert!(!target.has_header(2).unwrap());
+			assert_eq!(target.get_confidence(2).unwrap(), None);
+		}
+	}
+
+	#[test]
+	fn import_anchored_to_last_header() {
+		let source = db_with_chain(4);
+		let mut archive = vec![];
+		export(&source, &mut archive, 2, 4, None).unwrap();
+
+		let target = verified_db(&source, 4);
+		assert_eq!(import(&target, Cursor::new(&archive), None).unwrap(), 3);
+		assert!(target.has_header(2).unwrap());
+	}
+
+	#[test]
+	fn import_not_anchored() {
+		let source = db_with_chain(5);
+		let mut archive = vec![];
+		export(&source, &mut archive, 2, 4, None).unwrap();
+
+		let target = MemoryDB::default();
+		target.store_genesis_hash(H256::repeat_byte(1)).unwrap();
+
+		assert!(import(&target, Cursor::new(&archive), None).is_err());
+		assert!(!target.has_header(2).unwrap());
+	}
+
+	#[test]
+	fn import_descendants_of_verified_header() {
+		let source = db_with_chain(4);
+		let mut archive = vec![];
+		export(&source, &mut archive, 2, 4, None).unwrap();
+
+		// Verified parent doesn't prove that its children are on the finalized chain
+		let target = verified_db(&source, 1);
+
+		assert!(import(&target, Cursor::new(&archive), None).is_err());
+		assert!(!target.has_header(2).unwrap());
+	}
+
+	#[test]
+	fn import_not_verified_child() {
+		let source = db_with_chain(4);
+		let mut archive = vec![];
+		export(&source, &mut archive, 2, 3, None).unwrap();
+
+		let target = MemoryDB::default();
+		target.store_genesis_hash(H256::repeat_byte(1)).unwrap();
+		let header = source.get_header(4).unwrap().unwrap();
+		target.store_header(4, &header).unwrap();
+
+		assert!(import(&target, Cursor::new(&archive), None).is_err());
+		assert!(!target.has_header(3).unwrap());
+	}
+
+	#[test]
+	fn import_not_linked_to_verified_child() {
+		let source = db_with_chain(3);
+		let mut archive = vec![];
+		export(&source, &mut archive, 1, 2, None).unwrap();
+
+		let target = MemoryDB::default();
+		target.store_genesis_hash(H256::repeat_byte(1)).unwrap();
+		target
+			.store_header(3, &header(3, H256::repeat_byte(3)))
+			.unwrap();
+		target
+			.update_block_state(3, |block_state| block_state.finality_verified = true)
+			.unwrap();
+
+		assert!(import(&target, Cursor::new(&archive), None).is_err());
+		assert!(!target.has_header(2).unwrap());
+	}
+
+	#[test]
+	fn import_anchored_to_header_without_verified_finality() {
+		let source = db_with_chain(4);
+		let mut archive = vec![];
+		export(&source, &mut archive, 2, 4, None).unwrap();
+
+		// Header synced from the RPC is marked as verified, but its finality is not verified
+		let target = MemoryDB::default();
+		target.store_genesis_hash(H256::repeat_byte(1)).unwrap();
+		let header = source.get_header(4).unwrap().unwrap();
+		target.store_header(4, &header).unwrap();
+		target
+			.update_block_state(4, |block_state| block_state.header_verified = true)
+			.unwrap();
+
+		assert!(import(&target, Cursor::new(&archive), None).is_err());
+		assert!(!target.has_header(2).unwrap());
+	}
+
+	#[test]
+	fn import_into_fresh_db() {
+		let source = db_with_chain(5);
+		let mut archive = vec![];
+		export(&source, &mut archive, 1, 4, None).unwrap();
+
+		let target = MemoryDB::default();
+		assert!(import(&target, Cursor::new(&archive), None).is_err());
+
+		// Light client stores the genesis hash on start, and the headers with verified finality
+		target.store_genesis_hash(H256::repeat_byte(1)).unwrap();
+		assert!(import(&target, Cursor::new(&archive), None).is_err());
+
+		let header = source.get_header(5).unwrap().unwrap();
+		target.store_header(5, &header).unwrap();
+		target
+			.update_block_state(5, |block_state| {
+				block_state.header_verified = true;
+				block_state.finality_verified = true;
+			})
+			.unwrap();
+		assert_eq!(import(&target, Cursor::new(&archive), None).unwrap(), 4);
+		assert!(target.has_header(1).unwrap());
+	}
+
+	#[test]
+	fn import_not_contiguous() {
+		let source = db_with_chain(4);
+		let mut archive = vec![];
+		export(&source, &mut archive, 2, 4, None).unwrap();
+		let archive = String::from_utf8(archive).unwrap();
+		// Removes the block 3, after the archive header and the block 2
+		let archive = archive
+			.lines()
+			.enumerate()
+			.filter(|&(index, _)| index != 2)
+			.map(|(_, line)| format!("{line}\n"))
+			.collect::<String>();
+
+		let target = verified_db(&source, 4);
+
+		assert!(import(&target, Cursor::new(&archive), None).is_err());
+		assert!(!target.has_header(2).unwrap());
+	}
+
+	#[test]
+	fn export_missing_header() {
+		let source = db_with_chain(3);
+		let mut archive = vec![];
+		assert!(export(&source, &mut archive, 2, 4, None).is_err());
+	}
+
+	#[test]
+	fn import_genesis_hash_mismatch() {
+		let source = db_with_chain(3);
+		let mut archive = vec![];
+		export(&source, &mut archive, 1, 3, None).unwrap();
+
+		let target = verified_db(&source, 3);
+		target.store_genesis_hash(H256::repeat_byte(2)).unwrap();
+
+		assert!(import(&target, Cursor::new(&archive), None).is_err());
+		assert!(!target.has_header(1).unwrap());
+	}
+
+	#[test]
+	fn import_broken_linkage() {
+		let source = db_with_chain(3);
+		let target = verified_db(&source, 3);
+		source
+			.store_header(2, &header(2, H256::repeat_byte(3)))
+			.unwrap();
+		let mut archive = vec![];
+		export(&source, &mut archive, 1, 3, None).unwrap();
+
+		assert!(import(&target, Cursor::new(&archive), None).is_err());
+		assert!(!target.has_header(1).unwrap());
+	}
+
+	#[test]
+	fn import_not_linked_to_stored_parent() {
+		let source = db_with_chain(3);
+		let mut archive = vec![];
+		export(&source, &mut archive, 3, 3, None).unwrap();
+
+		let target = verified_db(&source, 3);
+		target
+			.store_header(2, &header(2, H256::repeat_byte(3)))
+			.unwrap();
+
+		assert!(import(&target, Cursor::new(&archive), None).is_err());
+	}
+
+	#[test]
+	fn import_in_batches() {
+		let last = IMPORT_BATCH_SIZE as u32 + 2;
+		let source = db_with_chain(last);
+		let mut archive = vec![];
+		export(&source, &mut archive, 1, last - 1, None).unwrap();
+
+		let target = verified_db(&source, last);
+		let imported = import(&target, Cursor::new(&archive), None).unwrap();
+		assert_eq!(imported, IMPORT_BATCH_SIZE + 1);
+		assert!(target.has_header(1).unwrap());
+		assert!(target.has_header(last - 1).unwrap());
+	}
+
+	#[test]
+	fn import_broken_linkage_after_first_batch() {
+		let last = IMPORT_BATCH_SIZE as u32 + 2;
+		let source = db_with_chain(last);
+		let target = verified_db(&source, last);
+		source
+			.store_header(last - 1, &header(last - 1, H256::repeat_byte(3)))
+			.unwrap();
+		let mut archive = vec![];
+		export(&source, &mut archive, 1, last - 1, None).unwrap();
+
+		assert!(import(&target, Cursor::new(&archive), None).is_err());
+		assert!(!target.has_header(1).unwrap());
+	}
+
+	#[test]
+	fn import_unsupported_schema_version() {
+		let archive = format!(
+			r#"{{"type":"archive","schema_version":0,"genesis_hash":"{:?}"}}"#,
+			H256::repeat_byte(1)
+		);
+
+		assert!(import(&MemoryDB::default(), Cursor::new(&archive), None).is_err());
+	}
+}
@@ -26,7 +26,7 @@ async fn main() -> Result<()> {
 	let command_args = CommandArgs::parse();
 	println!("Using URL: {}", command_args.url);
 	println!("Using Path: {}", command_args.avail_path);
-	let db = data::init_db(&command_args.avail_path)?;
+	let db = data::RocksDB::open(&command_args.avail_path)?;
 	let state = Arc::new(Mutex::new(State::default()));
 
 	let (rpc_client, _, event_loop) = rpc::init(db, state, &[command_args.url]);
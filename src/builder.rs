@@ -0,0 +1,465 @@
+//! Light client builder, used to embed the light client into other applications.
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! use avail_light::{types::RuntimeConfig, LightClientBuilder};
+//!
+//! let config = RuntimeConfig {
+//! 	app_id: Some(1),
+//! 	..Default::default()
+//! };
+//! let mut handle = LightClientBuilder::new(config).build().await?;
+//! let mut confidence = handle.confidence().expect("Confidence is published in app mode");
+//! while let Ok(block) = confidence.recv().await {
+//! 	println!("Confidence achieved for block {}", block.block_num);
+//! }
+//! handle.shutdown();
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use avail_core::AppId;
+use futures::Future;
+use kate_recovery::com::AppData;
+use libp2p::{multiaddr::Protocol, Multiaddr};
+use std::{
+	net::Ipv4Addr,
+	sync::{Arc, Mutex},
+};
+use tokio::{
+	sync::{broadcast, mpsc},
+	task::JoinHandle,
+};
+use tracing::{error, info, trace, warn};
+
+#[cfg(feature = "network-analysis")]
+use crate::network::p2p::analyzer;
+use crate::{
+	api, app_client,
+	consts::EXPECTED_NETWORK_VERSION,
+	data::{Database, RocksDB},
+	keystore::Keystore,
+	light_client,
+	network::{p2p, rpc},
+	sync_client, sync_finality,
+	telemetry::{otlp, Metrics},
+	types::{BlockVerified, Mode, RuntimeConfig, State, Submission},
+};
+
+const CLIENT_ROLE: &str = if cfg!(feature = "crawl") {
+	"crawler"
+} else {
+	"lightnode"
+};
+
+type DatabaseInit<D> = Box<dyn FnOnce(&RuntimeConfig) -> Result<D> + Send>;
+type MetricsInit<M> = Box<dyn FnOnce(&RuntimeConfig, String) -> Result<Arc<M>> + Send>;
+
+/// Builds and starts the light client with all of its services.
+///
+/// Database and metrics are created from the configuration, unless provided.
+pub struct LightClientBuilder<M, D> {
+	cfg: RuntimeConfig,
+	db: DatabaseInit<D>,
+	metrics: MetricsInit<M>,
+}
+
+impl LightClientBuilder<otlp::Metrics, RocksDB> {
+	pub fn new(cfg: RuntimeConfig) -> Self {
+		LightClientBuilder {
+			cfg,
+			db: Box::new(|cfg| {
+				RocksDB::open(&cfg.avail_path).context("Cannot initialize database")
+			}),
+			metrics: Box::new(|cfg, peer_id| {
+				otlp::initialize(
+					cfg.ot_collector_endpoint.clone(),
+					peer_id,
+					CLIENT_ROLE.into(),
+				)
+				.map(Arc::new)
+				.context("Unable to initialize OpenTelemetry service")
+			}),
+		}
+	}
+}
+
+impl<M: Metrics + Send + Sync + 'static, D: Database> LightClientBuilder<M, D> {
+	/// Uses provided database instead of opening RocksDB at the configured `avail_path`
+	pub fn with_database<E: Database>(self, db: E) -> LightClientBuilder<M, E> {
+		LightClientBuilder {
+			cfg: self.cfg,
+			db: Box::new(move |_| Ok(db)),
+			metrics: self.metrics,
+		}
+	}
+
+	/// Uses provided metrics instead of the OpenTelemetry metrics
+	pub fn with_metrics<N: Metrics + Send + Sync + 'static>(
+		self,
+		metrics: Arc<N>,
+	) -> LightClientBuilder<N, D> {
+		LightClientBuilder {
+			cfg: self.cfg,
+			db: self.db,
+			metrics: Box::new(move |_, _| Ok(metrics)),
+		}
+	}
+
+	/// Starts the light client services and returns the handle used to observe and stop them
+	pub async fn build(self) -> Result<LightClientHandle> {
+		let LightClientBuilder { cfg, db, metrics } = self;
+		let mut tasks = Tasks::default();
+		let (error_sender, error_receiver) = mpsc::channel::<anyhow::Error>(1);
+
+		if cfg.bootstraps.is_empty() {
+			Err(anyhow!("Bootstrap node list must not be empty. Either use a '--network' flag or add a list of bootstrap nodes in the configuration file"))?
+		}
+
+		let db = db(&cfg)?;
+
+		// If in fat client mode, enable deleting local Kademlia records
+		// This is a fat client memory optimization
+		let kad_remove_local_record = cfg.block_matrix_partition.is_some();
+		if kad_remove_local_record {
+			info!("Fat client mode");
+		}
+
+		let (id_keys, peer_id) = p2p::keypair((&cfg).into())?;
+
+		let ot_metrics = metrics(&cfg, peer_id)?;
+
+		// raise new P2P Network Client and Event Loop
+		let (p2p_client, p2p_event_loop) = p2p::init(
+			(&cfg).into(),
+			cfg.dht_parallelization_limit,
+			cfg.kad_record_ttl,
+			cfg.put_batch_size,
+			kad_remove_local_record,
+			id_keys,
+		)
+		.context("Failed to init Network Service")?;
+
+		// spawn the P2P Network task for Event Loop run in the background
+		tasks.spawn(p2p_event_loop.run());
+
+		// Start listening on provided port
+		let port = cfg.port;
+		info!("Listening on port: {port}");
+
+		// always listen on UDP to prioritize QUIC
+		p2p_client
+			.start_listening(
+				Multiaddr::empty()
+					.with(Protocol::from(Ipv4Addr::UNSPECIFIED))
+					.with(Protocol::Udp(port))
+					.with(Protocol::QuicV1),
+			)
+			.await
+			.context("Listening on UDP not to fail.")?;
+
+		// wait here for bootstrap to finish
+		info!("Bootstraping the DHT with bootstrap nodes...");
+		p2p_client
+			.bootstrap(cfg.clone().bootstraps.iter().map(Into::into).collect())
+			.await?;
+
+		#[cfg(feature = "network-analysis")]
+		tasks.spawn(analyzer::start_traffic_analyzer(cfg.port, 10));
+
+		let pp = Arc::new(kate_recovery::couscous::public_params());
+		let raw_pp = pp.to_raw_var_bytes();
+		let public_params_hash = hex::encode(sp_core::blake2_128(&raw_pp));
+		let public_params_len = hex::encode(raw_pp).len();
+		trace!("Public params ({public_params_len}): hash: {public_params_hash}");
+
+		let state = Arc::new(Mutex::new(State::default()));
+		let (rpc_client, rpc_events, rpc_event_loop) =
+			rpc::init(db.clone(), state.clone(), &cfg.full_node_ws);
+
+		let publish_rpc_event_receiver = rpc_events.subscribe();
+		let lc_rpc_event_receiver = rpc_events.subscribe();
+		let first_header_rpc_event_receiver = rpc_events.subscribe();
+		#[cfg(feature = "crawl")]
+		let crawler_rpc_event_receiver = rpc_events.subscribe();
+
+		// spawn the RPC Network task for Event Loop to run in the background
+		tasks.spawn(rpc_event_loop.run(EXPECTED_NETWORK_VERSION));
+
+		let node = rpc_client.get_connected_node().await?;
+		info!("Genesis hash: {:?}", node.genesis_hash);
+		if let Some(stored_genesis_hash) = db.get_genesis_hash()? {
+			if !node.genesis_hash.eq(&stored_genesis_hash) {
+				Err(anyhow!(
+					"Genesis hash doesn't match the stored one! Clear the db or change nodes."
+				))?
+			}
+		} else {
+			info!("No genesis hash is found in the db, storing the new hash now.");
+			db.store_genesis_hash(node.genesis_hash)?;
+		}
+
+		info!("Waiting for first finalized header...");
+		let block_header =
+			rpc::wait_for_finalized_header(first_header_rpc_event_receiver, 60).await?;
+
+		let sync_range = cfg.sync_range(block_header.number);
+		let block_states = match sync_range.clone().last() {
+			Some(sync_end) => db.get_block_states(sync_range.start, sync_end)?,
+			None => vec![],
+		};
+		{
+			let mut state = state.lock().unwrap();
+			state.latest = block_header.number;
+			state.restore_sync(sync_range.start, &block_states);
+		}
+
+		let ws_clients = api::v2::types::WsClients::new((&cfg).into());
+		let events = api::v2::types::Events::default();
+		let (submission_sender, _) = broadcast::channel::<Submission>(1 << 7);
+		let keystore = Keystore::load(&cfg).context("Cannot load signing keys")?;
+		info!("Loaded signing keys: {keystore:?}");
+
+		#[cfg(feature = "grpc")]
+		if let Some(port) = cfg.grpc.grpc_server_port {
+			let grpc_server = api::v2::grpc::run(
+				port,
+				cfg.clone(),
+				node.clone(),
+				state.clone(),
+				db.clone(),
+				events.clone(),
+			);
+			tasks.spawn_fallible(grpc_server, error_sender.clone());
+		}
+
+		// Spawn tokio task which runs one http server for handling RPC
+		let server = api::server::Server {
+			db: db.clone(),
+			cfg: cfg.clone(),
+			state: state.clone(),
+			version: format!("v{}", clap::crate_version!()),
+			network_version: EXPECTED_NETWORK_VERSION.to_string(),
+			node,
+			node_client: rpc_client.clone(),
+			p2p_client: p2p_client.clone(),
+			ws_clients: ws_clients.clone(),
+			events: events.clone(),
+			submission_sender: submission_sender.clone(),
+			keystore,
+			pp: pp.clone(),
+		};
+
+		tasks.spawn_fallible(server.run(), error_sender.clone());
+
+		let app_mode = Mode::from(cfg.app_id);
+		let (block_tx, data_tx) = if let Mode::AppClient(app_id) = app_mode {
+			// communication channels being established for talking to
+			// libp2p backed application client
+			let (block_tx, _) = broadcast::channel::<BlockVerified>(1 << 7);
+			let (data_tx, _) = broadcast::channel::<(u32, AppData)>(1 << 7);
+			tasks.spawn(app_client::run(
+				(&cfg).into(),
+				db.clone(),
+				p2p_client.clone(),
+				rpc_client.clone(),
+				AppId(app_id),
+				block_tx.subscribe(),
+				pp.clone(),
+				state.clone(),
+				sync_range.clone(),
+				data_tx.clone(),
+				error_sender.clone(),
+			));
+			(Some(block_tx), Some(data_tx))
+		} else {
+			(None, None)
+		};
+
+		tasks.spawn(api::v2::publish(
+			api::v2::types::Topic::HeaderVerified,
+			publish_rpc_event_receiver,
+			ws_clients.clone(),
+			events.clone(),
+		));
+
+		if let Some(block_tx) = block_tx.as_ref() {
+			tasks.spawn(api::v2::publish(
+				api::v2::types::Topic::ConfidenceAchieved,
+				block_tx.subscribe(),
+				ws_clients.clone(),
+				events.clone(),
+			));
+		}
+
+		tasks.spawn(api::v2::publish(
+			api::v2::types::Topic::SubmissionStatus,
+			submission_sender.subscribe(),
+			ws_clients.clone(),
+			events.clone(),
+		));
+
+		if let Some(data_tx) = data_tx.as_ref() {
+			tasks.spawn(api::v2::publish(
+				api::v2::types::Topic::DataVerified,
+				data_tx.subscribe(),
+				ws_clients,
+				events,
+			));
+		}
+
+		#[cfg(feature = "crawl")]
+		if cfg.crawl.crawl_block {
+			tasks.spawn(crate::crawl_client::run(
+				crawler_rpc_event_receiver,
+				p2p_client.clone(),
+				cfg.crawl.crawl_block_delay,
+				ot_metrics.clone(),
+				cfg.crawl.crawl_block_mode,
+			));
+		}
+
+		let sync_client = sync_client::new(p2p_client.clone(), rpc_client.clone());
+
+		if cfg.sync_start_block.is_some() {
+			// historical blocks are sent only to the application client, in app mode
+			state.lock().unwrap().synced.replace(false);
+			tasks.spawn(sync_client::run(
+				sync_client,
+				db.clone(),
+				(&cfg).into(),
+				sync_range,
+				pp.clone(),
+				block_tx.clone(),
+				state.clone(),
+			));
+		}
+
+		if cfg.sync_finality_enable {
+			let sync_finality = sync_finality::new(rpc_client.clone());
+			tasks.spawn(sync_finality::run(
+				sync_finality,
+				db.clone(),
+				(&cfg).into(),
+				error_sender.clone(),
+				state.clone(),
+				block_header.clone(),
+			));
+		} else {
+			let mut s = state
+				.lock()
+				.map_err(|e| anyhow!("State mutex is poisoned: {e:#}"))?;
+			warn!("Finality sync is disabled! Implicitly, blocks before LC startup will be considered verified as final");
+			s.finality_synced = true;
+		}
+
+		let light_client = light_client::new(p2p_client.clone(), rpc_client.clone());
+
+		let lc_channels = light_client::Channels {
+			block_sender: block_tx.clone(),
+			rpc_event_receiver: lc_rpc_event_receiver,
+			error_sender,
+		};
+
+		tasks.spawn(light_client::run(
+			light_client,
+			db,
+			(&cfg).into(),
+			pp,
+			ot_metrics,
+			state.clone(),
+			lc_channels,
+		));
+
+		Ok(LightClientHandle {
+			state,
+			rpc_client,
+			p2p_client,
+			header_sender: rpc_events,
+			block_sender: block_tx,
+			data_sender: data_tx,
+			error_receiver,
+			tasks,
+		})
+	}
+}
+
+/// Spawned light client services, aborted when dropped
+#[derive(Default)]
+struct Tasks(Vec<JoinHandle<()>>);
+
+impl Tasks {
+	fn spawn<F>(&mut self, task: F)
+	where
+		F: Future + Send + 'static,
+		F::Output: Send,
+	{
+		self.0.push(tokio::spawn(async move {
+			_ = task.await;
+		}));
+	}
+
+	/// Spawns the service which fails on startup or while running, reporting the failure as fatal
+	fn spawn_fallible<F>(&mut self, task: F, error_sender: mpsc::Sender<anyhow::Error>)
+	where
+		F: Future<Output = Result<()>> + Send + 'static,
+	{
+		self.spawn(async move {
+			if let Err(error) = task.await {
+				error!("{error:#}");
+				// Sending fails only if the light client handle is dropped
+				let _ = error_sender.send(error).await;
+			}
+		});
+	}
+}
+
+impl Drop for Tasks {
+	fn drop(&mut self) {
+		for task in &self.0 {
+			task.abort();
+		}
+	}
+}
+
+/// Handle of the running light client.
+///
+/// Light client services are stopped when the handle is dropped or [`LightClientHandle::shutdown`] is called.
+pub struct LightClientHandle {
+	pub state: Arc<Mutex<State>>,
+	pub rpc_client: rpc::Client,
+	pub p2p_client: p2p::Client,
+	header_sender: broadcast::Sender<rpc::Event>,
+	block_sender: Option<broadcast::Sender<BlockVerified>>,
+	data_sender: Option<broadcast::Sender<(u32, AppData)>>,
+	error_receiver: mpsc::Receiver<anyhow::Error>,
+	tasks: Tasks,
+}
+
+impl LightClientHandle {
+	/// Subscribes to headers with verified finality
+	pub fn verified_headers(&self) -> broadcast::Receiver<rpc::Event> {
+		self.header_sender.subscribe()
+	}
+
+	/// Subscribes to blocks with achieved confidence, if light client is started in app mode
+	pub fn confidence(&self) -> Option<broadcast::Receiver<BlockVerified>> {
+		self.block_sender.as_ref().map(broadcast::Sender::subscribe)
+	}
+
+	/// Subscribes to verified application data, if light client is started in app mode
+	pub fn app_data(&self) -> Option<broadcast::Receiver<(u32, AppData)>> {
+		self.data_sender.as_ref().map(broadcast::Sender::subscribe)
+	}
+
+	/// Waits for the fatal error reported by one of the light client services
+	pub async fn error(&mut self) -> Option<anyhow::Error> {
+		self.error_receiver.recv().await
+	}
+
+	/// Stops all light client services
+	pub fn shutdown(self) {
+		drop(self.tasks);
+	}
+}
@@ -14,6 +14,12 @@ pub const APP_DATA_CF: &str = "avail_light_app_data_cf";
 /// Column family for state
 pub const STATE_CF: &str = "avail_light_state_cf";
 
+/// Column family for block state
+pub const BLOCK_STATE_CF: &str = "avail_light_block_state_cf";
+
+/// Column family for submitted transactions
+pub const SUBMISSIONS_CF: &str = "avail_light_submissions_cf";
+
 /// Expected network version
 pub const EXPECTED_NETWORK_VERSION: ExpectedVersion = ExpectedVersion {
 	version: "1.8",
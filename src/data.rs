@@ -1,16 +1,24 @@
-//! Persistence to RocksDB.
+//! Persistence to RocksDB or memory.
 
 use anyhow::{anyhow, Context, Result};
 use avail_core::AppId;
 use avail_subxt::{primitives::Header as DaHeader, utils::H256};
 use codec::{Decode, Encode};
 use kate_recovery::com::AppData;
-use rocksdb::{ColumnFamilyDescriptor, Options, DB};
-use std::sync::Arc;
+use rocksdb::{ColumnFamilyDescriptor, Direction, IteratorMode, Options, DB};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	sync::{Arc, Mutex, MutexGuard},
+};
 
 use crate::{
-	consts::{APP_DATA_CF, BLOCK_HEADER_CF, CONFIDENCE_FACTOR_CF, STATE_CF},
-	types::FinalitySyncCheckpoint,
+	consts::{
+		APP_DATA_CF, BLOCK_HEADER_CF, BLOCK_STATE_CF, CONFIDENCE_FACTOR_CF, STATE_CF,
+		SUBMISSIONS_CF,
+	},
+	types::{BlockState, FinalitySyncCheckpoint, Submission},
+	utils::unix_timestamp,
 };
 
 const GENESIS_HASH_KEY: &str = "genesis_hash";
@@ -50,11 +58,19 @@ pub fn init_db(path: &str) -> Result<Arc<DB>> {
 	let mut state_cf_opts = Options::default();
 	state_cf_opts.set_max_write_buffer_number(16);
 
+	let mut block_state_cf_opts = Options::default();
+	block_state_cf_opts.set_max_write_buffer_number(16);
+
+	let mut submissions_cf_opts = Options::default();
+	submissions_cf_opts.set_max_write_buffer_number(16);
+
 	let cf_opts = vec![
 		ColumnFamilyDescriptor::new(CONFIDENCE_FACTOR_CF, confidence_cf_opts),
 		ColumnFamilyDescriptor::new(BLOCK_HEADER_CF, block_header_cf_opts),
 		ColumnFamilyDescriptor::new(APP_DATA_CF, app_data_cf_opts),
 		ColumnFamilyDescriptor::new(STATE_CF, state_cf_opts),
+		ColumnFamilyDescriptor::new(BLOCK_STATE_CF, block_state_cf_opts),
+		ColumnFamilyDescriptor::new(SUBMISSIONS_CF, submissions_cf_opts),
 	];
 
 	let mut db_opts = Options::default();
@@ -129,6 +145,54 @@ pub fn store_block_header_in_db(db: Arc<DB>, block_number: u32, header: &DaHeade
 	.context("Failed to write block header")
 }
 
+fn block_number_from_key(key: &[u8]) -> Result<u32> {
+	key.try_into()
+		.map(u32::from_be_bytes)
+		.map_err(|_| anyhow!("Invalid block number key"))
+}
+
+/// Iterates over values in column family with block number keys in the given (inclusive) range
+fn iterate_blocks_in_db<T>(
+	db: Arc<DB>,
+	column_family: &str,
+	from: u32,
+	to: u32,
+	decode: impl Fn(&[u8]) -> Result<T>,
+) -> Result<Vec<(u32, T)>> {
+	let handle = db
+		.cf_handle(column_family)
+		.context("Failed to get cf handle")?;
+
+	let mut values = vec![];
+	let mode = IteratorMode::From(&from.to_be_bytes(), Direction::Forward);
+	for item in db.iterator_cf(&handle, mode) {
+		let (key, value) = item.context("Failed to iterate over database")?;
+		let block_number = block_number_from_key(&key)?;
+		if block_number > to {
+			break;
+		}
+		values.push((block_number, decode(&value)?));
+	}
+	Ok(values)
+}
+
+/// Gets block headers from database in the given (inclusive) block range
+pub fn get_block_headers_from_db(db: Arc<DB>, from: u32, to: u32) -> Result<Vec<(u32, DaHeader)>> {
+	iterate_blocks_in_db(db, BLOCK_HEADER_CF, from, to, |value| {
+		serde_json::from_slice(value).context("Failed to deserialize header")
+	})
+}
+
+/// Gets confidence factors from database in the given (inclusive) block range
+pub fn get_confidences_from_db(db: Arc<DB>, from: u32, to: u32) -> Result<Vec<(u32, u32)>> {
+	iterate_blocks_in_db(db, CONFIDENCE_FACTOR_CF, from, to, |value| {
+		value
+			.try_into()
+			.map(u32::from_be_bytes)
+			.map_err(|_| anyhow!("Unable to convert confidence (wrong number of bytes)"))
+	})
+}
+
 /// Checks if confidence factor for given block number is in database
 pub fn is_confidence_in_db(db: Arc<DB>, block_number: u32) -> Result<bool> {
 	let handle = db
@@ -140,26 +204,431 @@ pub fn is_confidence_in_db(db: Arc<DB>, block_number: u32) -> Result<bool> {
 		.map(|value| value.is_some())
 }
 
-pub trait Database: Clone + Send {
+/// Gets block state from database for given block number
+pub fn get_block_state_from_db(db: Arc<DB>, block_number: u32) -> Result<Option<BlockState>> {
+	let handle = db
+		.cf_handle(BLOCK_STATE_CF)
+		.context("Failed to get cf handle")?;
+
+	db.get_cf(&handle, block_number.to_be_bytes())
+		.context("Failed to get block state")?
+		.map(|value| serde_json::from_slice(&value).context("Failed to deserialize block state"))
+		.transpose()
+}
+
+/// Gets block states from database in the given (inclusive) block range
+pub fn get_block_states_from_db(db: Arc<DB>, from: u32, to: u32) -> Result<Vec<(u32, BlockState)>> {
+	iterate_blocks_in_db(db, BLOCK_STATE_CF, from, to, |value| {
+		serde_json::from_slice(value).context("Failed to deserialize block state")
+	})
+}
+
+/// Stores block state into database under the given block number key
+pub fn store_block_state_in_db(
+	db: Arc<DB>,
+	block_number: u32,
+	block_state: &BlockState,
+) -> Result<()> {
+	let handle = db
+		.cf_handle(BLOCK_STATE_CF)
+		.context("Failed to get cf handle")?;
+
+	db.put_cf(
+		&handle,
+		block_number.to_be_bytes(),
+		serde_json::to_string(block_state)?.as_bytes(),
+	)
+	.context("Failed to write block state")
+}
+
+/// Submitted transaction, persisted with the Unix timestamp of its last update
+#[derive(Serialize, Deserialize)]
+struct StoredSubmission {
+	#[serde(flatten)]
+	submission: Submission,
+	/// Submissions stored without the timestamp are treated as expired
+	#[serde(default)]
+	updated_at: u64,
+}
+
+fn decode_submission(value: &[u8]) -> Result<StoredSubmission> {
+	serde_json::from_slice(value).context("Failed to deserialize submission")
+}
+
+/// Gets submitted transaction from database for given transaction hash
+pub fn get_submission_from_db(db: Arc<DB>, hash: H256) -> Result<Option<Submission>> {
+	let handle = db
+		.cf_handle(SUBMISSIONS_CF)
+		.context("Failed to get cf handle")?;
+
+	db.get_cf(&handle, hash.as_bytes())
+		.context("Failed to get submission")?
+		.map(|value| decode_submission(&value).map(|stored| stored.submission))
+		.transpose()
+}
+
+/// Gets all submitted transactions from database, with the Unix timestamps of their last update
+pub fn get_submissions_from_db(db: Arc<DB>) -> Result<Vec<(u64, Submission)>> {
+	let handle = db
+		.cf_handle(SUBMISSIONS_CF)
+		.context("Failed to get cf handle")?;
+
+	db.iterator_cf(&handle, IteratorMode::Start)
+		.map(|item| {
+			let (_, value) = item.context("Failed to iterate over database")?;
+			decode_submission(&value).map(|stored| (stored.updated_at, stored.submission))
+		})
+		.collect()
+}
+
+/// Stores submitted transaction into database under the transaction hash key
+pub fn store_submission_in_db(db: Arc<DB>, submission: &Submission, updated_at: u64) -> Result<()> {
+	let handle = db
+		.cf_handle(SUBMISSIONS_CF)
+		.context("Failed to get cf handle")?;
+
+	let stored = StoredSubmission {
+		submission: submission.clone(),
+		updated_at,
+	};
+	db.put_cf(
+		&handle,
+		submission.hash.as_bytes(),
+		serde_json::to_string(&stored)?.as_bytes(),
+	)
+	.context("Failed to write submission")
+}
+
+/// Removes submitted transaction from database
+pub fn remove_submission_from_db(db: Arc<DB>, hash: H256) -> Result<()> {
+	let handle = db
+		.cf_handle(SUBMISSIONS_CF)
+		.context("Failed to get cf handle")?;
+
+	db.delete_cf(&handle, hash.as_bytes())
+		.context("Failed to remove submission")
+}
+
+/// Light client storage, implemented by [`RocksDB`] and in-memory [`MemoryDB`]
+pub trait Database: Clone + Send + Sync + 'static {
 	fn get_confidence(&self, block_number: u32) -> Result<Option<u32>>;
+	fn has_confidence(&self, block_number: u32) -> Result<bool>;
+	fn store_confidence(&self, block_number: u32, count: u32) -> Result<()>;
+	/// Gets stored confidence factors in the given (inclusive) block range, in ascending order
+	fn get_confidences(&self, from: u32, to: u32) -> Result<Vec<(u32, u32)>>;
 	fn get_header(&self, block_number: u32) -> Result<Option<DaHeader>>;
+	/// Gets stored headers in the given (inclusive) block range, in ascending order
+	fn get_headers(&self, from: u32, to: u32) -> Result<Vec<(u32, DaHeader)>>;
+	fn has_header(&self, block_number: u32) -> Result<bool>;
+	fn store_header(&self, block_number: u32, header: &DaHeader) -> Result<()>;
 	fn get_data(&self, app_id: u32, block_number: u32) -> Result<Option<AppData>>;
+	fn store_data(&self, app_id: u32, block_number: u32, data: &AppData) -> Result<()>;
+	fn get_genesis_hash(&self) -> Result<Option<H256>>;
+	fn store_genesis_hash(&self, genesis_hash: H256) -> Result<()>;
+	fn get_finality_sync_checkpoint(&self) -> Result<Option<FinalitySyncCheckpoint>>;
+	fn store_finality_sync_checkpoint(&self, checkpoint: FinalitySyncCheckpoint) -> Result<()>;
+	fn get_block_state(&self, block_number: u32) -> Result<Option<BlockState>>;
+	/// Gets stored block states in the given (inclusive) block range, in ascending order
+	fn get_block_states(&self, from: u32, to: u32) -> Result<Vec<(u32, BlockState)>>;
+	fn store_block_state(&self, block_number: u32, block_state: &BlockState) -> Result<()>;
+	fn get_submission(&self, hash: H256) -> Result<Option<Submission>>;
+	/// Gets all stored submissions, with the Unix timestamps of their last update
+	fn get_submissions(&self) -> Result<Vec<(u64, Submission)>>;
+	/// Stores submission, with the current time as the time of its last update
+	fn store_submission(&self, submission: &Submission) -> Result<()>;
+	fn remove_submission(&self, hash: H256) -> Result<()>;
+
+	/// Updates stored block state, starting from the default state if block state is not stored.
+	/// Concurrent updates are serialized, so none of them is lost.
+	fn update_block_state(
+		&self,
+		block_number: u32,
+		update: impl FnOnce(&mut BlockState),
+	) -> Result<()>;
+
+	/// Stores reason of the block processing failure into the block state
+	fn store_block_failure(&self, block_number: u32, failure: String) -> Result<()> {
+		self.update_block_state(block_number, |block_state| {
+			block_state.failure = Some(failure);
+		})
+	}
 }
 
 #[derive(Clone)]
-pub struct RocksDB(pub Arc<DB>);
+pub struct RocksDB {
+	db: Arc<DB>,
+	/// Serializes read-modify-write updates of the block states
+	block_state_lock: Arc<Mutex<()>>,
+}
+
+impl RocksDB {
+	/// Opens or creates Rocks Database at the given path
+	pub fn open(path: &str) -> Result<Self> {
+		Ok(RocksDB {
+			db: init_db(path)?,
+			block_state_lock: Arc::new(Mutex::new(())),
+		})
+	}
+}
 
 impl Database for RocksDB {
 	fn get_confidence(&self, block_number: u32) -> Result<Option<u32>> {
-		get_confidence_from_db(self.0.clone(), block_number)
+		get_confidence_from_db(self.db.clone(), block_number)
+	}
+
+	fn has_confidence(&self, block_number: u32) -> Result<bool> {
+		is_confidence_in_db(self.db.clone(), block_number)
+	}
+
+	fn store_confidence(&self, block_number: u32, count: u32) -> Result<()> {
+		store_confidence_in_db(self.db.clone(), block_number, count)
+	}
+
+	fn get_confidences(&self, from: u32, to: u32) -> Result<Vec<(u32, u32)>> {
+		get_confidences_from_db(self.db.clone(), from, to)
 	}
 
 	fn get_header(&self, block_number: u32) -> Result<Option<DaHeader>> {
-		get_block_header_from_db(self.0.clone(), block_number)
+		get_block_header_from_db(self.db.clone(), block_number)
+	}
+
+	fn get_headers(&self, from: u32, to: u32) -> Result<Vec<(u32, DaHeader)>> {
+		get_block_headers_from_db(self.db.clone(), from, to)
+	}
+
+	fn has_header(&self, block_number: u32) -> Result<bool> {
+		is_block_header_in_db(self.db.clone(), block_number)
+	}
+
+	fn store_header(&self, block_number: u32, header: &DaHeader) -> Result<()> {
+		store_block_header_in_db(self.db.clone(), block_number, header)
+	}
+
+	fn get_data(&self, app_id: u32, block_number: u32) -> Result<Option<AppData>> {
+		get_decoded_data_from_db(self.db.clone(), app_id, block_number)
+	}
+
+	fn store_data(&self, app_id: u32, block_number: u32, data: &AppData) -> Result<()> {
+		store_encoded_data_in_db(self.db.clone(), AppId(app_id), block_number, data)
+	}
+
+	fn get_genesis_hash(&self) -> Result<Option<H256>> {
+		get_genesis_hash(self.db.clone())
+	}
+
+	fn store_genesis_hash(&self, genesis_hash: H256) -> Result<()> {
+		store_genesis_hash(self.db.clone(), genesis_hash)
+	}
+
+	fn get_finality_sync_checkpoint(&self) -> Result<Option<FinalitySyncCheckpoint>> {
+		get_finality_sync_checkpoint(self.db.clone())
+	}
+
+	fn store_finality_sync_checkpoint(&self, checkpoint: FinalitySyncCheckpoint) -> Result<()> {
+		store_finality_sync_checkpoint(self.db.clone(), checkpoint)
+	}
+
+	fn get_block_state(&self, block_number: u32) -> Result<Option<BlockState>> {
+		get_block_state_from_db(self.db.clone(), block_number)
+	}
+
+	fn get_block_states(&self, from: u32, to: u32) -> Result<Vec<(u32, BlockState)>> {
+		get_block_states_from_db(self.db.clone(), from, to)
+	}
+
+	fn store_block_state(&self, block_number: u32, block_state: &BlockState) -> Result<()> {
+		store_block_state_in_db(self.db.clone(), block_number, block_state)
+	}
+
+	fn get_submission(&self, hash: H256) -> Result<Option<Submission>> {
+		get_submission_from_db(self.db.clone(), hash)
+	}
+
+	fn get_submissions(&self) -> Result<Vec<(u64, Submission)>> {
+		get_submissions_from_db(self.db.clone())
+	}
+
+	fn store_submission(&self, submission: &Submission) -> Result<()> {
+		store_submission_in_db(self.db.clone(), submission, unix_timestamp())
+	}
+
+	fn remove_submission(&self, hash: H256) -> Result<()> {
+		remove_submission_from_db(self.db.clone(), hash)
+	}
+
+	fn update_block_state(
+		&self,
+		block_number: u32,
+		update: impl FnOnce(&mut BlockState),
+	) -> Result<()> {
+		let _lock = self
+			.block_state_lock
+			.lock()
+			.map_err(|error| anyhow!("Block state mutex is poisoned: {error}"))?;
+		let mut block_state = self.get_block_state(block_number)?.unwrap_or_default();
+		update(&mut block_state);
+		self.store_block_state(block_number, &block_state)
+	}
+}
+
+#[derive(Default)]
+struct MemoryStore {
+	confidence: BTreeMap<u32, u32>,
+	headers: BTreeMap<u32, DaHeader>,
+	app_data: BTreeMap<(u32, u32), AppData>,
+	genesis_hash: Option<H256>,
+	finality_sync_checkpoint: Option<FinalitySyncCheckpoint>,
+	block_states: BTreeMap<u32, BlockState>,
+	/// Submissions with the Unix timestamps of their last update
+	submissions: BTreeMap<H256, (u64, Submission)>,
+}
+
+/// In-memory database, used for testing and ephemeral deployments
+#[derive(Clone, Default)]
+pub struct MemoryDB(Arc<Mutex<MemoryStore>>);
+
+impl MemoryDB {
+	fn store(&self) -> Result<MutexGuard<MemoryStore>> {
+		self.0
+			.lock()
+			.map_err(|error| anyhow!("Memory database mutex is poisoned: {error}"))
+	}
+}
+
+impl Database for MemoryDB {
+	fn get_confidence(&self, block_number: u32) -> Result<Option<u32>> {
+		Ok(self.store()?.confidence.get(&block_number).copied())
+	}
+
+	fn has_confidence(&self, block_number: u32) -> Result<bool> {
+		Ok(self.store()?.confidence.contains_key(&block_number))
+	}
+
+	fn store_confidence(&self, block_number: u32, count: u32) -> Result<()> {
+		self.store()?.confidence.insert(block_number, count);
+		Ok(())
+	}
+
+	fn get_confidences(&self, from: u32, to: u32) -> Result<Vec<(u32, u32)>> {
+		if from > to {
+			return Ok(vec![]);
+		}
+		let store = self.store()?;
+		Ok(store
+			.confidence
+			.range(from..=to)
+			.map(|(&block_number, &count)| (block_number, count))
+			.collect())
+	}
+
+	fn get_header(&self, block_number: u32) -> Result<Option<DaHeader>> {
+		Ok(self.store()?.headers.get(&block_number).cloned())
+	}
+
+	fn get_headers(&self, from: u32, to: u32) -> Result<Vec<(u32, DaHeader)>> {
+		if from > to {
+			return Ok(vec![]);
+		}
+		let store = self.store()?;
+		Ok(store
+			.headers
+			.range(from..=to)
+			.map(|(&block_number, header)| (block_number, header.clone()))
+			.collect())
+	}
+
+	fn has_header(&self, block_number: u32) -> Result<bool> {
+		Ok(self.store()?.headers.contains_key(&block_number))
+	}
+
+	fn store_header(&self, block_number: u32, header: &DaHeader) -> Result<()> {
+		self.store()?.headers.insert(block_number, header.clone());
+		Ok(())
 	}
 
 	fn get_data(&self, app_id: u32, block_number: u32) -> Result<Option<AppData>> {
-		get_decoded_data_from_db(self.0.clone(), app_id, block_number)
+		Ok(self.store()?.app_data.get(&(app_id, block_number)).cloned())
+	}
+
+	fn store_data(&self, app_id: u32, block_number: u32, data: &AppData) -> Result<()> {
+		self.store()?
+			.app_data
+			.insert((app_id, block_number), data.clone());
+		Ok(())
+	}
+
+	fn get_genesis_hash(&self) -> Result<Option<H256>> {
+		Ok(self.store()?.genesis_hash)
+	}
+
+	fn store_genesis_hash(&self, genesis_hash: H256) -> Result<()> {
+		self.store()?.genesis_hash = Some(genesis_hash);
+		Ok(())
+	}
+
+	fn get_finality_sync_checkpoint(&self) -> Result<Option<FinalitySyncCheckpoint>> {
+		Ok(self.store()?.finality_sync_checkpoint.clone())
+	}
+
+	fn store_finality_sync_checkpoint(&self, checkpoint: FinalitySyncCheckpoint) -> Result<()> {
+		self.store()?.finality_sync_checkpoint = Some(checkpoint);
+		Ok(())
+	}
+
+	fn get_block_state(&self, block_number: u32) -> Result<Option<BlockState>> {
+		Ok(self.store()?.block_states.get(&block_number).cloned())
+	}
+
+	fn get_block_states(&self, from: u32, to: u32) -> Result<Vec<(u32, BlockState)>> {
+		if from > to {
+			return Ok(vec![]);
+		}
+		let store = self.store()?;
+		Ok(store
+			.block_states
+			.range(from..=to)
+			.map(|(&block_number, block_state)| (block_number, block_state.clone()))
+			.collect())
+	}
+
+	fn store_block_state(&self, block_number: u32, block_state: &BlockState) -> Result<()> {
+		self.store()?
+			.block_states
+			.insert(block_number, block_state.clone());
+		Ok(())
+	}
+
+	fn get_submission(&self, hash: H256) -> Result<Option<Submission>> {
+		let store = self.store()?;
+		Ok(store
+			.submissions
+			.get(&hash)
+			.map(|(_, submission)| submission.clone()))
+	}
+
+	fn get_submissions(&self) -> Result<Vec<(u64, Submission)>> {
+		Ok(self.store()?.submissions.values().cloned().collect())
+	}
+
+	fn store_submission(&self, submission: &Submission) -> Result<()> {
+		self.store()?
+			.submissions
+			.insert(submission.hash, (unix_timestamp(), submission.clone()));
+		Ok(())
+	}
+
+	fn remove_submission(&self, hash: H256) -> Result<()> {
+		self.store()?.submissions.remove(&hash);
+		Ok(())
+	}
+
+	fn update_block_state(
+		&self,
+		block_number: u32,
+		update: impl FnOnce(&mut BlockState),
+	) -> Result<()> {
+		update(self.store()?.block_states.entry(block_number).or_default());
+		Ok(())
 	}
 }
 
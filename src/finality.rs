@@ -0,0 +1,404 @@
+//! Verification of GRANDPA commits.
+//!
+//! Justification is valid if its precommits are signed by the supermajority of the validator set,
+//! and if every precommit targets either the commit target or its descendant, which is proven
+//! by headers in the justification's votes ancestries.
+
+use anyhow::{anyhow, bail, Result};
+use avail_subxt::{primitives::Header, utils::H256};
+use codec::Encode;
+use sp_core::{blake2_256, ed25519, Pair};
+use std::collections::{HashMap, HashSet};
+
+use crate::types::{GrandpaJustification, Precommit, SignerMessage};
+
+/// Validator which signed two precommits for different targets in the same round.
+#[derive(Clone, Debug)]
+pub struct Equivocation {
+	pub round: u64,
+	pub id: ed25519::Public,
+	pub first: Precommit,
+	pub second: Precommit,
+}
+
+/// Outcome of the successful commit verification.
+#[derive(Debug)]
+pub struct VerifiedCommit {
+	/// Number of distinct validators which signed the commit
+	pub signers: usize,
+	/// Equivocations found in the commit precommits
+	pub equivocations: Vec<Equivocation>,
+}
+
+pub fn is_signed_by_supermajority(num_signatures: usize, validator_set_size: usize) -> bool {
+	let supermajority = (validator_set_size * 2 / 3) + 1;
+	num_signatures >= supermajority
+}
+
+fn signed_message(precommit: &Precommit, round: u64, set_id: u64) -> Vec<u8> {
+	// Signed message is a triplet of a precommit, round number and set ID (taken from Substrate code)
+	Encode::encode(&(
+		&SignerMessage::PrecommitMessage(precommit.clone()),
+		&round,
+		&set_id,
+	))
+}
+
+/// Walks from the precommit target to the commit target using ancestry headers.
+/// Returns hashes of visited ancestry headers.
+fn descendant_route(
+	ancestries: &HashMap<H256, &Header>,
+	base_hash: H256,
+	base_number: u32,
+	precommit: &Precommit,
+) -> Result<Vec<H256>> {
+	let mut route = vec![];
+	let mut hash = precommit.target_hash;
+
+	while hash != base_hash {
+		let header = ancestries.get(&hash).ok_or_else(|| {
+			anyhow!(
+				"Precommit target {:?} is not a descendant of the commit target {base_hash:?}",
+				precommit.target_hash
+			)
+		})?;
+		if header.number <= base_number {
+			bail!(
+				"Precommit target {:?} is not a descendant of the commit target {base_hash:?}",
+				precommit.target_hash
+			);
+		}
+		route.push(hash);
+		hash = header.parent_hash;
+	}
+
+	Ok(route)
+}
+
+/// Verifies GRANDPA justification against the given validator set.
+///
+/// Duplicate precommits are counted once. Precommits from the same validator
+/// for different targets are reported as equivocations, and the validator is counted once.
+pub fn verify_justification(
+	justification: &GrandpaJustification,
+	set_id: u64,
+	validator_set: &[ed25519::Public],
+) -> Result<VerifiedCommit> {
+	let commit = &justification.commit;
+
+	let ancestries = justification
+		.votes_ancestries
+		.iter()
+		.map(|header| (Encode::using_encoded(header, blake2_256).into(), header))
+		.collect::<HashMap<H256, &Header>>();
+
+	let mut votes: HashMap<ed25519::Public, Precommit> = HashMap::new();
+	let mut equivocations = vec![];
+	let mut visited = HashSet::new();
+
+	for signed in &commit.precommits {
+		if !validator_set.contains(&signed.id) {
+			bail!(
+				"Precommit signed by {:?} which is not in the validator set",
+				signed.id
+			);
+		}
+
+		let message = signed_message(&signed.precommit, justification.round, set_id);
+		if !<ed25519::Pair as Pair>::verify(&signed.signature, &message, &signed.id) {
+			bail!("Invalid precommit signature of {:?}", signed.id);
+		}
+
+		if signed.precommit.target_number < commit.target_number {
+			bail!(
+				"Precommit target {} is lower than the commit target {}",
+				signed.precommit.target_number,
+				commit.target_number
+			);
+		}
+
+		let route = descendant_route(
+			&ancestries,
+			commit.target_hash,
+			commit.target_number,
+			&signed.precommit,
+		)?;
+		visited.extend(route);
+
+		match votes.get(&signed.id) {
+			None => {
+				votes.insert(signed.id, signed.precommit.clone());
+			},
+			Some(first) if first.target_hash == signed.precommit.target_hash => (),
+			Some(first) => equivocations.push(Equivocation {
+				round: justification.round,
+				id: signed.id,
+				first: first.clone(),
+				second: signed.precommit.clone(),
+			}),
+		}
+	}
+
+	if visited.len() != ancestries.len() {
+		bail!("Justification contains votes ancestries which are not used by any precommit");
+	}
+
+	let signers = votes.len();
+	if !is_signed_by_supermajority(signers, validator_set.len()) {
+		bail!(
+			"Not signed by the supermajority of the validator set ({signers}/{})",
+			validator_set.len()
+		);
+	}
+
+	Ok(VerifiedCommit {
+		signers,
+		equivocations,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{is_signed_by_supermajority, verify_justification};
+	use crate::{
+		types::{Commit, GrandpaJustification, Precommit, SignedPrecommit},
+		utils::test_utils::header,
+	};
+	use avail_subxt::primitives::Header;
+	use codec::Encode;
+	use sp_core::{blake2_256, ed25519, Pair, H256};
+	use test_case::test_case;
+
+	const ROUND: u64 = 7;
+	const SET_ID: u64 = 3;
+
+	#[test_case(1, 1 => true)]
+	#[test_case(1, 2 => false)]
+	#[test_case(2, 2 => true)]
+	#[test_case(2, 3 => false)]
+	#[test_case(3, 3 => true)]
+	#[test_case(3, 4 => true)]
+	#[test_case(4, 5 => true)]
+	#[test_case(66, 100 => false)]
+	#[test_case(67, 100 => true)]
+	fn check_supermajority_condition(num_signatures: usize, validator_set_size: usize) -> bool {
+		is_signed_by_supermajority(num_signatures, validator_set_size)
+	}
+
+	fn validators(count: u8) -> Vec<ed25519::Pair> {
+		(0..count)
+			.map(|i| ed25519::Pair::from_seed(&[i; 32]))
+			.collect()
+	}
+
+	fn public(pairs: &[ed25519::Pair]) -> Vec<ed25519::Public> {
+		pairs.iter().map(|pair| pair.public()).collect()
+	}
+
+	fn hash(header: &Header) -> H256 {
+		header.using_encoded(blake2_256).into()
+	}
+
+	fn sign(pair: &ed25519::Pair, target: &Header, set_id: u64) -> SignedPrecommit {
+		let precommit = Precommit {
+			target_hash: hash(target),
+			target_number: target.number,
+		};
+		let message = super::signed_message(&precommit, ROUND, set_id);
+		SignedPrecommit {
+			precommit,
+			signature: pair.sign(&message),
+			id: pair.public(),
+		}
+	}
+
+	fn justification(
+		target: &Header,
+		precommits: Vec<SignedPrecommit>,
+		votes_ancestries: Vec<Header>,
+	) -> GrandpaJustification {
+		GrandpaJustification {
+			round: ROUND,
+			commit: Commit {
+				target_hash: hash(target),
+				target_number: target.number,
+				precommits,
+			},
+			votes_ancestries,
+		}
+	}
+
+	// Chain of headers 10 <- 11 <- 12
+	fn chain() -> (Header, Header, Header) {
+		let target = header(10, H256::repeat_byte(1));
+		let child = header(11, hash(&target));
+		let grandchild = header(12, hash(&child));
+		(target, child, grandchild)
+	}
+
+	#[test]
+	fn verify_same_target() {
+		let (target, _, _) = chain();
+		let pairs = validators(4);
+		let precommits = pairs.iter().map(|p| sign(p, &target, SET_ID)).collect();
+
+		let verified = verify_justification(
+			&justification(&target, precommits, vec![]),
+			SET_ID,
+			&public(&pairs),
+		)
+		.unwrap();
+
+		assert_eq!(verified.signers, 4);
+		assert!(verified.equivocations.is_empty());
+	}
+
+	#[test]
+	fn verify_descendant_targets() {
+		let (target, child, grandchild) = chain();
+		let pairs = validators(4);
+		let precommits = vec![
+			sign(&pairs[0], &target, SET_ID),
+			sign(&pairs[1], &child, SET_ID),
+			sign(&pairs[2], &grandchild, SET_ID),
+		];
+		let ancestries = vec![child, grandchild];
+
+		let verified = verify_justification(
+			&justification(&target, precommits, ancestries),
+			SET_ID,
+			&public(&pairs),
+		)
+		.unwrap();
+
+		assert_eq!(verified.signers, 3);
+	}
+
+	#[test]
+	fn verify_missing_ancestry() {
+		let (target, _, grandchild) = chain();
+		let pairs = validators(4);
+		let precommits = vec![
+			sign(&pairs[0], &target, SET_ID),
+			sign(&pairs[1], &target, SET_ID),
+			sign(&pairs[2], &grandchild, SET_ID),
+		];
+		let ancestries = vec![grandchild];
+
+		let result = verify_justification(
+			&justification(&target, precommits, ancestries),
+			SET_ID,
+			&public(&pairs),
+		);
+
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn verify_unused_ancestry() {
+		let (target, child, _) = chain();
+		let pairs = validators(4);
+		let precommits = pairs.iter().map(|p| sign(p, &target, SET_ID)).collect();
+
+		let result = verify_justification(
+			&justification(&target, precommits, vec![child]),
+			SET_ID,
+			&public(&pairs),
+		);
+
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn verify_non_descendant_target() {
+		let (target, _, _) = chain();
+		let fork = header(11, H256::repeat_byte(2));
+		let pairs = validators(4);
+		let precommits = vec![
+			sign(&pairs[0], &target, SET_ID),
+			sign(&pairs[1], &target, SET_ID),
+			sign(&pairs[2], &fork, SET_ID),
+		];
+
+		let result = verify_justification(
+			&justification(&target, precommits, vec![fork]),
+			SET_ID,
+			&public(&pairs),
+		);
+
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn verify_duplicate_signers() {
+		let (target, _, _) = chain();
+		let pairs = validators(4);
+		let precommits = vec![
+			sign(&pairs[0], &target, SET_ID),
+			sign(&pairs[0], &target, SET_ID),
+			sign(&pairs[1], &target, SET_ID),
+			sign(&pairs[1], &target, SET_ID),
+		];
+
+		let result = verify_justification(
+			&justification(&target, precommits, vec![]),
+			SET_ID,
+			&public(&pairs),
+		);
+
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn verify_equivocation() {
+		let (target, child, _) = chain();
+		let pairs = validators(4);
+		let precommits = vec![
+			sign(&pairs[0], &target, SET_ID),
+			sign(&pairs[0], &child, SET_ID),
+			sign(&pairs[1], &target, SET_ID),
+			sign(&pairs[2], &target, SET_ID),
+		];
+
+		let verified = verify_justification(
+			&justification(&target, precommits, vec![child]),
+			SET_ID,
+			&public(&pairs),
+		)
+		.unwrap();
+
+		assert_eq!(verified.signers, 3);
+		assert_eq!(verified.equivocations.len(), 1);
+		assert_eq!(verified.equivocations[0].id, pairs[0].public());
+	}
+
+	#[test]
+	fn verify_wrong_set_id() {
+		let (target, _, _) = chain();
+		let pairs = validators(4);
+		let precommits = pairs.iter().map(|p| sign(p, &target, SET_ID + 1)).collect();
+
+		let result = verify_justification(
+			&justification(&target, precommits, vec![]),
+			SET_ID,
+			&public(&pairs),
+		);
+
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn verify_unknown_signer() {
+		let (target, _, _) = chain();
+		let pairs = validators(5);
+		let precommits = pairs.iter().map(|p| sign(p, &target, SET_ID)).collect();
+
+		let result = verify_justification(
+			&justification(&target, precommits, vec![]),
+			SET_ID,
+			&public(&pairs[..4]),
+		);
+
+		assert!(result.is_err());
+	}
+}
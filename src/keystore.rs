@@ -0,0 +1,278 @@
+//! Named signing keys used for transaction submission.
+//!
+//! Keys are loaded from the `avail_secret_key` configuration parameter (named `default`),
+//! from `AVAIL_SECRET_KEY_{NAME}` environment variables, and from encrypted JSON keystore files
+//! in the `avail_keystore_path` directory, named after the file. Keystore files are encrypted with
+//! ChaCha20-Poly1305, using a key derived from the password with PBKDF2-HMAC-SHA256.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chacha20poly1305::{
+	aead::{Aead, NewAead},
+	ChaCha20Poly1305, Key, Nonce,
+};
+use hmac::Hmac;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+	collections::{hash_map::Entry, HashMap},
+	env, fmt, fs,
+	path::Path,
+};
+use subxt::ext::sp_core::{crypto::Ss58Codec, Pair as _};
+
+use crate::types::{AvailSecretKey, RuntimeConfig};
+
+/// Name of the key configured with the `avail_secret_key` parameter
+pub const DEFAULT_KEY: &str = "default";
+
+/// Prefix of the environment variables holding named secret keys
+pub const SECRET_KEY_ENV_PREFIX: &str = "AVAIL_SECRET_KEY_";
+
+/// Environment variable holding the keystore password
+pub const KEYSTORE_PASSWORD_ENV: &str = "AVAIL_KEYSTORE_PASSWORD";
+
+/// Version of the keystore file format, incremented on incompatible changes
+pub const KEYSTORE_VERSION: u32 = 1;
+
+const KDF_ROUNDS: u32 = 600_000;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum KdfAlgorithm {
+	#[serde(rename = "pbkdf2-sha256")]
+	Pbkdf2Sha256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum CipherAlgorithm {
+	#[serde(rename = "chacha20-poly1305")]
+	ChaCha20Poly1305,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Kdf {
+	algorithm: KdfAlgorithm,
+	rounds: u32,
+	/// Hex encoded salt
+	salt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cipher {
+	algorithm: CipherAlgorithm,
+	/// Hex encoded nonce
+	nonce: String,
+}
+
+/// Keystore file with the secret key (secret URI, mnemonic or seed) encrypted with a password
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedKey {
+	version: u32,
+	/// SS58 encoded public key, used to identify the key without decrypting it
+	public_key: String,
+	kdf: Kdf,
+	cipher: Cipher,
+	/// Hex encoded encrypted secret key
+	ciphertext: String,
+}
+
+fn derive_key(password: &str, salt: &[u8], rounds: u32) -> [u8; 32] {
+	let mut key = [0u8; 32];
+	pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, rounds, &mut key);
+	key
+}
+
+impl EncryptedKey {
+	pub fn encrypt(secret: &str, password: &str) -> Result<Self> {
+		Self::encrypt_with_rounds(secret, password, KDF_ROUNDS)
+	}
+
+	fn encrypt_with_rounds(secret: &str, password: &str, rounds: u32) -> Result<Self> {
+		let AvailSecretKey(pair) = AvailSecretKey::try_from(secret.to_string())
+			.map_err(|_| anyhow!("Invalid secret key"))?;
+
+		let salt = rand::random::<[u8; SALT_LENGTH]>();
+		let nonce = rand::random::<[u8; NONCE_LENGTH]>();
+		let key = derive_key(password, &salt, rounds);
+		let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key))
+			.encrypt(Nonce::from_slice(&nonce), secret.as_bytes())
+			.map_err(|_| anyhow!("Failed to encrypt secret key"))?;
+
+		Ok(EncryptedKey {
+			version: KEYSTORE_VERSION,
+			public_key: pair.public().to_ss58check(),
+			kdf: Kdf {
+				algorithm: KdfAlgorithm::Pbkdf2Sha256,
+				rounds,
+				salt: hex::encode(salt),
+			},
+			cipher: Cipher {
+				algorithm: CipherAlgorithm::ChaCha20Poly1305,
+				nonce: hex::encode(nonce),
+			},
+			ciphertext: hex::encode(ciphertext),
+		})
+	}
+
+	pub fn decrypt(&self, password: &str) -> Result<AvailSecretKey> {
+		if self.version != KEYSTORE_VERSION {
+			bail!("Unsupported keystore version {}", self.version);
+		}
+
+		let salt = hex::decode(&self.kdf.salt).context("Invalid salt")?;
+		let nonce = hex::decode(&self.cipher.nonce).context("Invalid nonce")?;
+		if nonce.len() != NONCE_LENGTH {
+			bail!("Invalid nonce length");
+		}
+		let ciphertext = hex::decode(&self.ciphertext).context("Invalid ciphertext")?;
+
+		let key = derive_key(password, &salt, self.kdf.rounds);
+		let secret = ChaCha20Poly1305::new(Key::from_slice(&key))
+			.decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+			.map_err(|_| anyhow!("Failed to decrypt secret key, password is not valid"))?;
+		let secret = String::from_utf8(secret).context("Invalid secret key encoding")?;
+
+		AvailSecretKey::try_from(secret).map_err(|_| anyhow!("Invalid secret key"))
+	}
+
+	pub fn load(path: &Path) -> Result<Self> {
+		let file = fs::read(path)
+			.with_context(|| format!("Failed to read keystore file {}", path.display()))?;
+		serde_json::from_slice(&file)
+			.with_context(|| format!("Failed to parse keystore file {}", path.display()))
+	}
+
+	pub fn save(&self, path: &Path) -> Result<()> {
+		let file = serde_json::to_vec_pretty(self).context("Failed to serialize keystore")?;
+		fs::write(path, file)
+			.with_context(|| format!("Failed to write keystore file {}", path.display()))
+	}
+}
+
+/// Signing keys by name
+#[derive(Clone, Default)]
+pub struct Keystore(HashMap<String, AvailSecretKey>);
+
+impl fmt::Debug for Keystore {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_set().entries(self.0.keys()).finish()
+	}
+}
+
+impl Keystore {
+	/// Loads keys from the configuration, environment variables and the keystore directory
+	pub fn load(config: &RuntimeConfig) -> Result<Self> {
+		let mut keystore = Keystore::default();
+
+		if let Some(key) = config.avail_secret_key.clone() {
+			keystore.insert(DEFAULT_KEY, key)?;
+		}
+
+		for (variable, value) in env::vars() {
+			let Some(name) = variable.strip_prefix(SECRET_KEY_ENV_PREFIX) else {
+				continue;
+			};
+			let key = AvailSecretKey::try_from(value)
+				.map_err(|_| anyhow!("Invalid secret key in {variable} environment variable"))?;
+			keystore.insert(&name.to_lowercase(), key)?;
+		}
+
+		if let Some(keystore_path) = config.avail_keystore_path.as_ref() {
+			keystore.load_dir(Path::new(keystore_path))?;
+		}
+
+		Ok(keystore)
+	}
+
+	fn load_dir(&mut self, path: &Path) -> Result<()> {
+		let entries = fs::read_dir(path)
+			.with_context(|| format!("Failed to read keystore directory {}", path.display()))?;
+
+		let mut password = None;
+		for entry in entries {
+			let path = entry
+				.context("Failed to read keystore directory entry")?
+				.path();
+			if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+				continue;
+			}
+			let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+				continue;
+			};
+			if password.is_none() {
+				password = Some(env::var(KEYSTORE_PASSWORD_ENV).with_context(|| {
+					format!("{KEYSTORE_PASSWORD_ENV} environment variable is not set")
+				})?);
+			}
+			let password = password.as_deref().unwrap_or_default();
+			let key = EncryptedKey::load(&path)?
+				.decrypt(password)
+				.with_context(|| format!("Failed to load key {name}"))?;
+			self.insert(name, key)?;
+		}
+
+		Ok(())
+	}
+
+	fn insert(&mut self, name: &str, key: AvailSecretKey) -> Result<()> {
+		match self.0.entry(name.to_string()) {
+			Entry::Occupied(_) => Err(anyhow!("Signing key {name} is defined more than once")),
+			Entry::Vacant(entry) => {
+				entry.insert(key);
+				Ok(())
+			},
+		}
+	}
+
+	pub fn get(&self, name: &str) -> Option<&AvailSecretKey> {
+		self.0.get(name)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{EncryptedKey, Keystore};
+	use subxt::ext::sp_core::Pair as _;
+
+	const SECRET: &str = "//Alice";
+
+	#[test]
+	fn encrypted_key_roundtrip() {
+		let encrypted = EncryptedKey::encrypt_with_rounds(SECRET, "password", 1000).unwrap();
+		let json = serde_json::to_string(&encrypted).unwrap();
+		assert!(!json.contains(SECRET));
+
+		let encrypted: EncryptedKey = serde_json::from_str(&json).unwrap();
+		let key = encrypted.decrypt("password").unwrap();
+		let expected = EncryptedKey::encrypt_with_rounds(SECRET, "other", 1000).unwrap();
+		assert_eq!(encrypted.public_key, expected.public_key);
+		assert_eq!(
+			key.0.public(),
+			expected.decrypt("other").unwrap().0.public()
+		);
+	}
+
+	#[test]
+	fn encrypted_key_wrong_password() {
+		let encrypted = EncryptedKey::encrypt_with_rounds(SECRET, "password", 1000).unwrap();
+		assert!(encrypted.decrypt("wrong").is_err());
+	}
+
+	#[test]
+	fn encrypted_key_invalid_secret() {
+		assert!(EncryptedKey::encrypt_with_rounds("invalid", "password", 1000).is_err());
+	}
+
+	#[test]
+	fn keystore_debug_hides_keys() {
+		let mut keystore = Keystore::default();
+		let key = EncryptedKey::encrypt_with_rounds(SECRET, "password", 1000)
+			.unwrap()
+			.decrypt("password")
+			.unwrap();
+		keystore.insert("sequencer", key.clone()).unwrap();
+		assert_eq!(format!("{keystore:?}"), r#"{"sequencer"}"#);
+		assert!(keystore.insert("sequencer", key).is_err());
+	}
+}
@@ -1,9 +1,13 @@
 pub mod api;
 pub mod app_client;
+pub mod archive;
+mod builder;
 pub mod consts;
 #[cfg(feature = "crawl")]
 pub mod crawl_client;
 pub mod data;
+pub mod finality;
+pub mod keystore;
 pub mod light_client;
 pub mod network;
 pub mod proof;
@@ -12,3 +16,5 @@ pub mod sync_finality;
 pub mod telemetry;
 pub mod types;
 pub mod utils;
+
+pub use builder::{LightClientBuilder, LightClientHandle};
@@ -30,7 +30,6 @@ use kate_recovery::{
 };
 use kate_recovery::{data::Cell, matrix::RowIndex};
 use mockall::automock;
-use rocksdb::DB;
 use sp_core::blake2_256;
 use std::{
 	sync::{Arc, Mutex},
@@ -40,7 +39,7 @@ use tokio::sync::{broadcast, mpsc::Sender};
 use tracing::{error, info};
 
 use crate::{
-	data::{store_block_header_in_db, store_confidence_in_db},
+	data::Database,
 	network::{
 		p2p::Client as P2pClient,
 		rpc::{self, Client as RpcClient, Event},
@@ -65,20 +64,16 @@ pub trait LightClient {
 	async fn shrink_kademlia_map(&self) -> Result<()>;
 	async fn get_multiaddress_and_ip(&self) -> Result<(String, String)>;
 	async fn count_dht_entries(&self) -> Result<usize>;
-	fn store_block_header_in_db(&self, header: &Header, block_number: u32) -> Result<()>;
-	fn store_confidence_in_db(&self, count: u32, block_number: u32) -> Result<()>;
 }
 
 #[derive(Clone)]
 struct LightClientImpl {
-	db: Arc<DB>,
 	p2p_client: P2pClient,
 	rpc_client: RpcClient,
 }
 
-pub fn new(db: Arc<DB>, p2p_client: P2pClient, rpc_client: RpcClient) -> impl LightClient {
+pub fn new(p2p_client: P2pClient, rpc_client: RpcClient) -> impl LightClient {
 	LightClientImpl {
-		db,
 		p2p_client,
 		rpc_client,
 	}
@@ -113,18 +108,11 @@ impl LightClient for LightClientImpl {
 	async fn count_dht_entries(&self) -> Result<usize> {
 		self.p2p_client.count_dht_entries().await
 	}
-	fn store_confidence_in_db(&self, count: u32, block_number: u32) -> Result<()> {
-		store_confidence_in_db(self.db.clone(), block_number, count)
-			.context("Failed to store confidence in DB")
-	}
-	fn store_block_header_in_db(&self, header: &Header, block_number: u32) -> Result<()> {
-		store_block_header_in_db(self.db.clone(), block_number, header)
-			.context("Failed to store block header in DB")
-	}
 }
 
 pub async fn process_block(
 	light_client: &impl LightClient,
+	db: &impl Database,
 	metrics: &Arc<impl Metrics>,
 	cfg: &LightClientConfig,
 	pp: Arc<PublicParameters>,
@@ -215,11 +203,10 @@ pub async fn process_block(
 	cells.extend(rpc_fetched.clone());
 
 	if positions.len() > cells.len() {
-		error!(
-			block_number,
-			"Failed to fetch {} cells",
-			positions.len() - cells.len()
-		);
+		let failure = format!("Failed to fetch {} cells", positions.len() - cells.len());
+		error!(block_number, "{failure}");
+		db.store_block_failure(block_number, failure)
+			.context("Failed to store block state in DB")?;
 		return Ok(None);
 	}
 
@@ -235,10 +222,15 @@ pub async fn process_block(
 		);
 
 		// write confidence factor into on-disk database
-		light_client
-			.store_confidence_in_db(verified.len() as u32, block_number)
+		db.store_confidence(block_number, verified.len() as u32)
 			.context("Failed to store confidence in DB")?;
 
+		db.update_block_state(block_number, |block_state| {
+			block_state.confidence_achieved = true;
+			block_state.failure = None;
+		})
+		.context("Failed to store block state in DB")?;
+
 		state.lock().unwrap().confidence_achieved.set(block_number);
 
 		let conf = calculate_confidence(verified.len() as u32);
@@ -260,8 +252,7 @@ pub async fn process_block(
 	// another competing thread, which syncs all block headers
 	// in range [0, LATEST], where LATEST = latest block number
 	// when this process started
-	light_client
-		.store_block_header_in_db(header, block_number)
+	db.store_header(block_number, header)
 		.context("Failed to store block header in DB")?;
 
 	let mut begin = Instant::now();
@@ -418,6 +409,7 @@ pub struct Channels {
 /// # Arguments
 ///
 /// * `light_client` - Light client implementation
+/// * `db` - Database to store confidence and headers
 /// * `cfg` - Light client configuration
 /// * `block_tx` - Channel used to send header of verified block
 /// * `pp` - Public parameters (i.e. SRS) needed for proof verification
@@ -425,6 +417,7 @@ pub struct Channels {
 /// * `state` - Processed blocks state
 pub async fn run(
 	light_client: impl LightClient,
+	db: impl Database,
 	cfg: LightClientConfig,
 	pp: Arc<PublicParameters>,
 	metrics: Arc<impl Metrics>,
@@ -460,6 +453,7 @@ pub async fn run(
 
 		let process_block_result = process_block(
 			&light_client,
+			&db,
 			&metrics,
 			&cfg,
 			pp.clone(),
@@ -472,6 +466,9 @@ pub async fn run(
 			Ok(confidence) => confidence,
 			Err(error) => {
 				error!("Cannot process block: {error}");
+				if let Err(error) = db.store_block_failure(header.number, format!("{error:#}")) {
+					error!("Cannot store block state: {error:#}");
+				}
 				if let Err(error) = channels.error_sender.send(error).await {
 					error!("Cannot send error message: {error}");
 				}
@@ -498,7 +495,9 @@ pub async fn run(
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::{network::rpc::cell_count_for_confidence, telemetry, types::RuntimeConfig};
+	use crate::{
+		data::MemoryDB, network::rpc::cell_count_for_confidence, telemetry, types::RuntimeConfig,
+	};
 	use avail_subxt::{
 		api::runtime_types::avail_core::{
 			data_lookup::compact::CompactDataLookup,
@@ -632,12 +631,6 @@ mod tests {
 			let kate_proof = kate_proof.clone();
 			Box::pin(async move { Ok(kate_proof) })
 		});
-		mock_client
-			.expect_store_confidence_in_db()
-			.returning(|_, _| Ok(()));
-		mock_client
-			.expect_store_block_header_in_db()
-			.returning(|_, _| Ok(()));
 		mock_client
 			.expect_insert_rows_into_dht()
 			.returning(|_, _| Box::pin(async move { 1f32 }));
@@ -659,8 +652,10 @@ mod tests {
 		mock_metrics.expect_record().returning(|_| Ok(()));
 		mock_metrics.expect_set_multiaddress().returning(|_| ());
 		mock_metrics.expect_set_ip().returning(|_| ());
+		let db = MemoryDB::default();
 		process_block(
 			&mock_client,
+			&db,
 			&Arc::new(mock_metrics),
 			&cfg,
 			pp,
@@ -670,6 +665,9 @@ mod tests {
 		)
 		.await
 		.unwrap();
+
+		assert!(db.has_confidence(header.number).unwrap());
+		assert!(db.has_header(header.number).unwrap());
 	}
 
 	#[tokio::test]
@@ -767,12 +765,6 @@ mod tests {
 				Box::pin(async move { (fetched, unfetched) })
 			});
 		mock_client.expect_get_kate_proof().never();
-		mock_client
-			.expect_store_confidence_in_db()
-			.returning(|_, _| Ok(()));
-		mock_client
-			.expect_store_block_header_in_db()
-			.returning(|_, _| Ok(()));
 		mock_client
 			.expect_insert_rows_into_dht()
 			.returning(|_, _| Box::pin(async move { 1f32 }));
@@ -794,8 +786,10 @@ mod tests {
 		mock_metrics.expect_record().returning(|_| Ok(()));
 		mock_metrics.expect_set_multiaddress().returning(|_| ());
 		mock_metrics.expect_set_ip().returning(|_| ());
+		let db = MemoryDB::default();
 		process_block(
 			&mock_client,
+			&db,
 			&Arc::new(mock_metrics),
 			&cfg,
 			pp,
@@ -805,5 +799,8 @@ mod tests {
 		)
 		.await
 		.unwrap();
+
+		assert!(db.has_confidence(header.number).unwrap());
+		assert!(db.has_header(header.number).unwrap());
 	}
 }
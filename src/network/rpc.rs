@@ -3,7 +3,6 @@ use avail_subxt::{primitives::Header, utils::H256};
 use codec::Decode;
 use kate_recovery::matrix::{Dimensions, Position};
 use rand::{seq::SliceRandom, thread_rng, Rng};
-use rocksdb::DB;
 use serde::{de, Deserialize};
 use sp_core::bytes::from_hex;
 use std::{
@@ -19,14 +18,16 @@ use tracing::debug;
 
 use crate::{
 	consts::EXPECTED_NETWORK_VERSION,
+	data::Database,
 	network::rpc,
 	types::{GrandpaJustification, State},
 };
 
 mod client;
 mod event_loop;
+mod header_tree;
 
-pub use client::Client;
+pub use client::{Client, SubmitCall};
 use event_loop::EventLoop;
 const CELL_SIZE: usize = 32;
 const PROOF_SIZE: usize = 48;
@@ -164,11 +165,11 @@ impl Display for ExpectedVersion<'_> {
 	}
 }
 
-pub fn init(
-	db: Arc<DB>,
+pub fn init<T: Database>(
+	db: T,
 	state: Arc<Mutex<State>>,
 	nodes: &[String],
-) -> (Client, broadcast::Sender<Event>, EventLoop) {
+) -> (Client, broadcast::Sender<Event>, EventLoop<T>) {
 	// create channel for Event Loop Commands
 	let (command_sender, command_receiver) = mpsc::channel(1000);
 	// create output channel for RPC Subscription Events
@@ -1,6 +1,9 @@
 use anyhow::{Context, Result};
 use avail_subxt::{
-	api::data_availability::calls::types::SubmitData,
+	api::{
+		data_availability::calls::types::{CreateApplicationKey, SubmitData},
+		utility::calls::types::BatchAll,
+	},
 	avail::Pair,
 	primitives::{AvailExtrinsicParams, Header},
 	utils::H256,
@@ -17,7 +20,7 @@ use subxt::{
 use tokio::sync::{mpsc, oneshot};
 
 use super::{Node, WrappedProof};
-use crate::types::RuntimeVersion;
+use crate::types::{DataProof, DryRunOutcome, RuntimeDispatchInfo, RuntimeVersion};
 
 #[derive(Clone)]
 pub struct Client {
@@ -211,8 +214,9 @@ impl Client {
 
 	pub async fn submit_signed_and_watch(
 		&self,
-		extrinsic: Payload<SubmitData>,
+		extrinsic: SubmitCall,
 		pair_signer: PairSigner<AvailConfig, Pair>,
+		nonce: u32,
 		params: AvailExtrinsicParams,
 	) -> Result<TxProgress<AvailConfig, OnlineClient<AvailConfig>>> {
 		let (response_sender, response_receiver) = oneshot::channel();
@@ -220,6 +224,31 @@ impl Client {
 			.send(Command::SubmitSignedAndWatch {
 				extrinsic,
 				pair_signer: Box::new(pair_signer),
+				nonce,
+				params,
+				response_sender,
+			})
+			.await
+			.context("RPC Command Receiver not be dropped")?;
+		response_receiver
+			.await
+			.context("RPC Command Sender not to be dropped.")?
+	}
+
+	/// Creates signed extrinsic without submitting it, returning the encoded bytes
+	pub async fn create_signed(
+		&self,
+		extrinsic: SubmitCall,
+		pair_signer: PairSigner<AvailConfig, Pair>,
+		nonce: u32,
+		params: AvailExtrinsicParams,
+	) -> Result<Vec<u8>> {
+		let (response_sender, response_receiver) = oneshot::channel();
+		self.command_sender
+			.send(Command::CreateSigned {
+				extrinsic,
+				pair_signer: Box::new(pair_signer),
+				nonce,
 				params,
 				response_sender,
 			})
@@ -230,6 +259,69 @@ impl Client {
 			.context("RPC Command Sender not to be dropped.")?
 	}
 
+	pub async fn query_fee_info(&self, tx_bytes: Vec<u8>) -> Result<RuntimeDispatchInfo> {
+		let (response_sender, response_receiver) = oneshot::channel();
+		self.command_sender
+			.send(Command::QueryFeeInfo {
+				tx_bytes,
+				response_sender,
+			})
+			.await
+			.context("RPC Command Receiver not be dropped")?;
+		response_receiver
+			.await
+			.context("RPC Command Sender not to be dropped.")?
+	}
+
+	/// Gets encoded extrinsics of the block
+	pub async fn get_block_extrinsics(&self, block_hash: H256) -> Result<Vec<Vec<u8>>> {
+		let (response_sender, response_receiver) = oneshot::channel();
+		self.command_sender
+			.send(Command::GetBlockExtrinsics {
+				block_hash,
+				response_sender,
+			})
+			.await
+			.context("RPC Command Receiver not be dropped")?;
+		response_receiver
+			.await
+			.context("RPC Command Sender not to be dropped.")?
+	}
+
+	/// Gets merkle proof of the data submitted with the given extrinsic in the block
+	pub async fn request_data_proof(
+		&self,
+		block_hash: H256,
+		extrinsic: Vec<u8>,
+	) -> Result<DataProof> {
+		let (response_sender, response_receiver) = oneshot::channel();
+		self.command_sender
+			.send(Command::RequestDataProof {
+				block_hash,
+				extrinsic,
+				response_sender,
+			})
+			.await
+			.context("RPC Command Receiver not be dropped")?;
+		response_receiver
+			.await
+			.context("RPC Command Sender not to be dropped.")?
+	}
+
+	pub async fn dry_run(&self, tx_bytes: Vec<u8>) -> Result<DryRunOutcome> {
+		let (response_sender, response_receiver) = oneshot::channel();
+		self.command_sender
+			.send(Command::DryRun {
+				tx_bytes,
+				response_sender,
+			})
+			.await
+			.context("RPC Command Receiver not be dropped")?;
+		response_receiver
+			.await
+			.context("RPC Command Sender not to be dropped.")?
+	}
+
 	pub async fn submit_from_bytes_and_watch(
 		&self,
 		tx_bytes: Vec<u8>,
@@ -303,6 +395,20 @@ impl Client {
 			.context("RPC Command Sender not to be dropped.")?
 	}
 
+	pub async fn get_account_nonce(&self, account_id: AccountId32) -> Result<u32> {
+		let (response_sender, response_receiver) = oneshot::channel();
+		self.command_sender
+			.send(Command::GetAccountNonce {
+				account_id,
+				response_sender,
+			})
+			.await
+			.context("RPC Command Receiver not be dropped")?;
+		response_receiver
+			.await
+			.context("RPC Command Sender not to be dropped.")?
+	}
+
 	pub async fn get_genesis_hash(&self) -> Result<H256> {
 		let (response_sender, response_receiver) = oneshot::channel();
 		self.command_sender
@@ -315,6 +421,13 @@ impl Client {
 	}
 }
 
+/// Signed submission call, with data calls either submitted alone or packed with `utility.batch_all`
+pub enum SubmitCall {
+	Data(Payload<SubmitData>),
+	BatchAll(Payload<BatchAll>),
+	CreateApplicationKey(Payload<CreateApplicationKey>),
+}
+
 pub enum Command {
 	GetBlockHash {
 		block_number: u32,
@@ -379,12 +492,37 @@ pub enum Command {
 			oneshot::Sender<Result<TxProgress<AvailConfig, OnlineClient<AvailConfig>>>>,
 	},
 	SubmitSignedAndWatch {
-		extrinsic: Payload<SubmitData>,
+		extrinsic: SubmitCall,
 		pair_signer: Box<PairSigner<AvailConfig, Pair>>,
+		nonce: u32,
 		params: AvailExtrinsicParams,
 		response_sender:
 			oneshot::Sender<Result<TxProgress<AvailConfig, OnlineClient<AvailConfig>>>>,
 	},
+	CreateSigned {
+		extrinsic: SubmitCall,
+		pair_signer: Box<PairSigner<AvailConfig, Pair>>,
+		nonce: u32,
+		params: AvailExtrinsicParams,
+		response_sender: oneshot::Sender<Result<Vec<u8>>>,
+	},
+	QueryFeeInfo {
+		tx_bytes: Vec<u8>,
+		response_sender: oneshot::Sender<Result<RuntimeDispatchInfo>>,
+	},
+	GetBlockExtrinsics {
+		block_hash: H256,
+		response_sender: oneshot::Sender<Result<Vec<Vec<u8>>>>,
+	},
+	RequestDataProof {
+		block_hash: H256,
+		extrinsic: Vec<u8>,
+		response_sender: oneshot::Sender<Result<DataProof>>,
+	},
+	DryRun {
+		tx_bytes: Vec<u8>,
+		response_sender: oneshot::Sender<Result<DryRunOutcome>>,
+	},
 	GetPagedStorageKeys {
 		key: Vec<u8>,
 		count: u32,
@@ -401,6 +539,10 @@ pub enum Command {
 		block_number: u32,
 		response_sender: oneshot::Sender<Result<WrappedProof>>,
 	},
+	GetAccountNonce {
+		account_id: AccountId32,
+		response_sender: oneshot::Sender<Result<u32>>,
+	},
 	GetGenesisHash {
 		response_sender: oneshot::Sender<Result<H256>>,
 	},
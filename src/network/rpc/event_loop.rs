@@ -1,5 +1,4 @@
 use anyhow::{anyhow, Context, Result};
-use api::data_availability::calls::types::SubmitData;
 use avail_subxt::{
 	api::{self, runtime_types::sp_core::crypto::KeyTypeId},
 	avail::{self},
@@ -11,21 +10,23 @@ use avail_subxt::{
 use codec::Encode;
 use futures::Stream;
 use kate_recovery::{data::Cell, matrix::Position};
-use rocksdb::DB;
 use sp_core::{
 	blake2_256,
 	ed25519::{self, Public},
-	Pair,
 };
 use std::{
+	collections::VecDeque,
 	sync::{Arc, Mutex},
 	time::Instant,
 };
 use subxt::{
-	rpc::{types::BlockNumber, RpcParams},
+	rpc::{
+		types::{BlockNumber, DryRunResult},
+		RpcParams,
+	},
 	rpc_params,
 	storage::StorageKey,
-	tx::{PairSigner, Payload, SubmittableExtrinsic, TxProgress},
+	tx::{PairSigner, SubmittableExtrinsic, TxPayload, TxProgress},
 	utils::AccountId32,
 	OnlineClient,
 };
@@ -33,16 +34,26 @@ use tokio::sync::{broadcast::Sender, mpsc};
 use tokio_stream::StreamExt;
 use tracing::{info, instrument, trace, warn};
 
-use super::{client::Command, ExpectedVersion, Nodes, WrappedProof, CELL_WITH_PROOF_SIZE};
+use super::{
+	client::{Command, SubmitCall},
+	header_tree::HeaderTree,
+	ExpectedVersion, Nodes, WrappedProof, CELL_WITH_PROOF_SIZE,
+};
 use crate::{
-	data::store_finality_sync_checkpoint,
+	data::Database,
+	finality,
 	types::{
-		FinalitySyncCheckpoint, GrandpaJustification, OptionBlockRange, RuntimeVersion,
-		SignerMessage, State,
+		DataProof, DryRunOutcome, FinalitySyncCheckpoint, GrandpaJustification, OptionBlockRange,
+		RuntimeDispatchInfo, RuntimeVersion, State,
 	},
 	utils::filter_auth_set_changes,
 };
 
+/// Maximum number of received headers waiting for justification
+const MAX_UNVERIFIED_HEADERS: usize = 1024;
+/// Maximum number of justifications waiting for the matching header, oldest are dropped first
+const MAX_PENDING_JUSTIFICATIONS: usize = 1024;
+
 #[derive(Clone, Debug)]
 pub enum Event {
 	HeaderUpdate {
@@ -62,30 +73,30 @@ struct CurrentValidators {
 }
 
 struct BlockData {
-	justifications: Vec<GrandpaJustification>,
-	unverified_headers: Vec<(Header, Instant)>,
+	justifications: VecDeque<GrandpaJustification>,
+	unverified_headers: HeaderTree,
 	current_valset: CurrentValidators,
 	last_finalized_block_header: Option<Header>,
 }
 
-pub struct EventLoop {
+pub struct EventLoop<T: Database> {
 	subxt_client: Option<avail::Client>,
 	command_receiver: mpsc::Receiver<Command>,
 	event_sender: Sender<Event>,
 	nodes: Nodes,
-	db: Arc<DB>,
+	db: T,
 	state: Arc<Mutex<State>>,
 	block_data: BlockData,
 }
 
-impl EventLoop {
+impl<T: Database> EventLoop<T> {
 	pub fn new(
-		db: Arc<DB>,
+		db: T,
 		state: Arc<Mutex<State>>,
 		nodes: Nodes,
 		command_receiver: mpsc::Receiver<Command>,
 		event_sender: Sender<Event>,
-	) -> EventLoop {
+	) -> EventLoop<T> {
 		Self {
 			subxt_client: None,
 			command_receiver,
@@ -95,7 +106,7 @@ impl EventLoop {
 			state,
 			block_data: BlockData {
 				justifications: Default::default(),
-				unverified_headers: Default::default(),
+				unverified_headers: HeaderTree::new(MAX_UNVERIFIED_HEADERS),
 				current_valset: CurrentValidators {
 					set_id: Default::default(),
 					validator_set: Default::default(),
@@ -229,10 +240,14 @@ impl EventLoop {
 				let received_at = Instant::now();
 				self.state.lock().unwrap().latest = header.clone().number;
 				info!("Header no.: {}", header.number);
-				// push new Unverified Header
+				// insert new Unverified Header
 				self.block_data
 					.unverified_headers
-					.push((header.clone(), received_at));
+					.insert(header.clone(), received_at);
+				trace!(
+					"Unverified headers: {}",
+					self.block_data.unverified_headers.len()
+				);
 
 				// search the header logs for validator set change
 				let mut new_auths = filter_auth_set_changes(&header);
@@ -260,7 +275,10 @@ impl EventLoop {
 					"New justification at block no.: {}, hash: {:?}",
 					justification.commit.target_number, justification.commit.target_hash
 				);
-				self.block_data.justifications.push(justification);
+				if self.block_data.justifications.len() >= MAX_PENDING_JUSTIFICATIONS {
+					self.block_data.justifications.pop_front();
+				}
+				self.block_data.justifications.push_back(justification);
 			},
 		}
 		// check headers
@@ -268,141 +286,193 @@ impl EventLoop {
 	}
 
 	async fn verify_and_output_block_headers(&mut self) {
-		while let Some(justification) = self.block_data.justifications.pop() {
-			// iterate through Headers and try to find a matching one
-			if let Some(pos) = self
+		while let Some(justification) = self.block_data.justifications.pop_back() {
+			let last_finalized: Option<(u32, H256)> = self
+				.block_data
+				.last_finalized_block_header
+				.as_ref()
+				.map(|header| {
+					(
+						header.number,
+						Encode::using_encoded(header, blake2_256).into(),
+					)
+				});
+			let last_finalized_number = last_finalized.map(|(number, _)| number);
+
+			if last_finalized_number
+				.is_some_and(|number| justification.commit.target_number <= number)
+			{
+				trace!(
+					"Skipping justification for already finalized block {}",
+					justification.commit.target_number
+				);
+				continue;
+			}
+
+			// try to find header matching the justification
+			if !self
 				.block_data
 				.unverified_headers
-				.iter()
-				.map(|(h, _)| Encode::using_encoded(h, blake2_256).into())
-				.position(|hash| justification.commit.target_hash == hash)
+				.contains(&justification.commit.target_hash)
 			{
-				// basically, pop it out of the collection
-				let (header, received_at) = self.block_data.unverified_headers.swap_remove(pos);
-				// form a message which is signed in the Justification, it's a triplet of a Precommit,
-				// round number and set_id (taken from Substrate code)
-				let signed_message = Encode::encode(&(
-					&SignerMessage::PrecommitMessage(
-						justification.commit.precommits[0].clone().precommit,
-					),
-					&justification.round,
-					&self.block_data.current_valset.set_id, // Set ID is needed here.
-				));
+				trace!("Matched pair of header/justification not found.");
+				self.block_data.justifications.push_back(justification);
+				break;
+			}
 
-				// verify all the Signatures of the Justification signs,
-				// verify the hash of the block and extract all the signer addresses
-				let signer_addresses = justification
-					.commit
-					.precommits
-					.iter()
-					.map(|precommit| {
-						let is_ok = <ed25519::Pair as Pair>::verify(
-							&precommit.signature,
-							&signed_message,
-							&precommit.id,
-						);
-						is_ok
-							.then(|| precommit.clone().id)
-							.ok_or_else(|| anyhow!("Not signed by this signature!"))
-					})
-					.collect::<Result<Vec<_>>>();
-
-				let signer_addresses = signer_addresses.unwrap();
-				// match all the Signer addresses to the Current Validator Set
-				let num_matched_addresses = signer_addresses
-					.iter()
-					.filter(|x| {
-						self.block_data
-							.current_valset
-							.validator_set
-							.iter()
-							.any(|e| e.0.eq(&x.0))
-					})
-					.count();
+			// invalid justification is dropped, and its headers are kept unverified
+			let verified = match finality::verify_justification(
+				&justification,
+				self.block_data.current_valset.set_id,
+				&self.block_data.current_valset.validator_set,
+			) {
+				Ok(verified) => verified,
+				Err(error) => {
+					warn!(
+						"Justification verification failed for block {}: {error:#}",
+						justification.commit.target_number
+					);
+					continue;
+				},
+			};
 
-				info!(
-					"Number of matching signatures: {num_matched_addresses}/{} for block {}",
-					self.block_data.current_valset.validator_set.len(),
-					header.number
+			for equivocation in verified.equivocations {
+				warn!(
+					"Equivocation by {:?} in round {}: {:?} and {:?}",
+					equivocation.id, equivocation.round, equivocation.first, equivocation.second
 				);
+			}
 
-				assert!(
-					is_signed_by_supermajority(
-						num_matched_addresses,
-						self.block_data.current_valset.validator_set.len()
-					),
-					"Not signed by the supermajority of the validator set."
-				);
+			info!(
+				"Number of matching signatures: {}/{} for block {}",
+				verified.signers,
+				self.block_data.current_valset.validator_set.len(),
+				justification.commit.target_number
+			);
 
-				// store Finality Checkpoint if finality is synced
-				let finality_synced = self.state.lock().unwrap().finality_synced;
-				if !finality_synced {
-					info!("Storing finality checkpoint at block {}", header.number);
-					store_finality_sync_checkpoint(
-						self.db.clone(),
-						FinalitySyncCheckpoint {
-							set_id: self.block_data.current_valset.set_id,
-							number: header.number,
-							validator_set: self.block_data.current_valset.validator_set.clone(),
-						},
-					)
-					.unwrap();
-				}
+			// remove finalized chain from the tree, pruning stale forks
+			let Some(mut finalized) = self
+				.block_data
+				.unverified_headers
+				.finalize(&justification.commit.target_hash)
+			else {
+				continue;
+			};
+
+			// drop headers which are already finalized
+			if let Some(number) = last_finalized_number {
+				finalized.retain(|(header, _)| header.number > number);
+			}
 
-				// try and get get all the skipped blocks, if they exist
-				if let Some(last_header) = self.block_data.last_finalized_block_header.as_ref() {
-					for bl_num in (last_header.number + 1)..header.number {
-						info!("Sending skipped block {bl_num}");
-						let (header, received_at) = match self
-							.block_data
-							.unverified_headers
-							.iter()
-							.position(|(h, _)| h.number == bl_num)
-						{
-							Some(pos) => {
-								info!("Fetching header from unverified headers");
-								self.block_data.unverified_headers.swap_remove(pos)
-							},
-							None => {
-								info!("Fetching header from RPC");
-								let a = self.get_header_by_block_number(bl_num).await.unwrap().0;
-								(a, Instant::now())
-							},
-						};
-						// send as output event
-						self.event_sender
-							.send(Event::HeaderUpdate {
-								header,
-								received_at,
-							})
-							.unwrap();
-					}
+			// try and get get all the skipped blocks, if they exist
+			if let Err(error) = self
+				.fetch_skipped_headers(last_finalized, &mut finalized)
+				.await
+			{
+				// headers are not sent with gaps, so they are kept unverified until the next attempt
+				warn!("Cannot fetch skipped headers, retrying on the next event: {error:#}");
+				for (header, received_at) in finalized {
+					self.block_data
+						.unverified_headers
+						.insert(header, received_at);
 				}
+				self.block_data.justifications.push_back(justification);
+				break;
+			}
 
-				info!("Sending finalized block {}", header.number);
-				// reset Last Finalized Block Header
-				self.block_data.last_finalized_block_header = Some(header.clone());
+			let (header, _) = finalized.last().expect("Finalized chain is not empty");
+			let header = header.clone();
+
+			// store Finality Checkpoint if finality is synced
+			let finality_synced = self.state.lock().unwrap().finality_synced;
+			if !finality_synced {
+				info!("Storing finality checkpoint at block {}", header.number);
+				self.db
+					.store_finality_sync_checkpoint(FinalitySyncCheckpoint {
+						set_id: self.block_data.current_valset.set_id,
+						number: header.number,
+						validator_set: self.block_data.current_valset.validator_set.clone(),
+					})
+					.unwrap();
+			}
 
-				// finally, send the Verified Block Header
-				self.state
-					.lock()
-					.unwrap()
-					.header_verified
-					.set(header.number);
+			info!("Sending finalized block {}", header.number);
+			// reset Last Finalized Block Header
+			self.block_data.last_finalized_block_header = Some(header.clone());
+
+			// finally, send the Verified Block Headers in ascending order
+			self.state
+				.lock()
+				.unwrap()
+				.header_verified
+				.set(header.number);
+			for (header, received_at) in finalized {
+				if let Err(error) = self.db.update_block_state(header.number, |block_state| {
+					block_state.header_verified = true;
+					block_state.finality_verified = true;
+				}) {
+					warn!("Cannot store block {} state: {error:#}", header.number);
+				}
+				trace!("Sending block {}", header.number);
 				self.event_sender
 					.send(Event::HeaderUpdate {
 						header,
 						received_at,
 					})
 					.unwrap();
-			} else {
-				trace!("Matched pair of header/justification not found.");
-				self.block_data.justifications.push(justification);
-				break;
 			}
 		}
 	}
 
+	/// Prepends headers between the last finalized block and the finalized chain,
+	/// fetching them from RPC by parent hash so that only headers from the finalized fork are used.
+	/// Fails if any fetched header doesn't match the requested hash,
+	/// or if the chain is not linked to the last finalized header.
+	async fn fetch_skipped_headers(
+		&self,
+		last_finalized: Option<(u32, H256)>,
+		finalized: &mut Vec<(Header, Instant)>,
+	) -> Result<()> {
+		let Some((last_finalized_number, last_finalized_hash)) = last_finalized else {
+			return Ok(());
+		};
+
+		let first = &finalized.first().context("Finalized chain is empty")?.0;
+		let mut number = first.number;
+		let mut parent_hash = first.parent_hash;
+		let mut skipped = vec![];
+		while number > last_finalized_number + 1 {
+			info!("Fetching skipped block {} from RPC", number - 1);
+			let header = self.get_header_by_hash(parent_hash).await?;
+			let hash: H256 = Encode::using_encoded(&header, blake2_256).into();
+			if hash != parent_hash {
+				return Err(anyhow!(
+					"Header {} hash {hash:?} doesn't match the requested hash {parent_hash:?}",
+					header.number
+				));
+			}
+			if header.number.checked_add(1) != Some(number) {
+				return Err(anyhow!(
+					"Header {} is not the parent of the header {number}",
+					header.number
+				));
+			}
+			number = header.number;
+			parent_hash = header.parent_hash;
+			skipped.push((header, Instant::now()));
+		}
+
+		if parent_hash != last_finalized_hash {
+			return Err(anyhow!(
+				"Header {number} is not linked to the last finalized header {last_finalized_number}"
+			));
+		}
+
+		skipped.reverse();
+		finalized.splice(0..0, skipped);
+		Ok(())
+	}
+
 	async fn handle_command(&self, command: Command) {
 		match command {
 			Command::GetSystemVersion { response_sender } => {
@@ -510,12 +580,72 @@ impl EventLoop {
 			Command::SubmitSignedAndWatch {
 				extrinsic,
 				pair_signer,
+				nonce,
 				params,
 				response_sender,
 			} => {
-				let res = self
-					.submit_signed_and_watch(extrinsic, pair_signer.as_ref(), params)
-					.await;
+				let res = match extrinsic {
+					SubmitCall::Data(call) => {
+						self.submit_signed_and_watch(&call, pair_signer.as_ref(), nonce, params)
+							.await
+					},
+					SubmitCall::BatchAll(call) => {
+						self.submit_signed_and_watch(&call, pair_signer.as_ref(), nonce, params)
+							.await
+					},
+					SubmitCall::CreateApplicationKey(call) => {
+						self.submit_signed_and_watch(&call, pair_signer.as_ref(), nonce, params)
+							.await
+					},
+				};
+				_ = response_sender.send(res);
+			},
+			Command::CreateSigned {
+				extrinsic,
+				pair_signer,
+				nonce,
+				params,
+				response_sender,
+			} => {
+				let pair_signer = pair_signer.as_ref();
+				let res = match extrinsic {
+					SubmitCall::Data(call) => self.create_signed(&call, pair_signer, nonce, params),
+					SubmitCall::BatchAll(call) => {
+						self.create_signed(&call, pair_signer, nonce, params)
+					},
+					SubmitCall::CreateApplicationKey(call) => {
+						self.create_signed(&call, pair_signer, nonce, params)
+					},
+				};
+				_ = response_sender.send(res);
+			},
+			Command::QueryFeeInfo {
+				tx_bytes,
+				response_sender,
+			} => {
+				let res = self.query_fee_info(&tx_bytes).await;
+				_ = response_sender.send(res);
+			},
+			Command::GetBlockExtrinsics {
+				block_hash,
+				response_sender,
+			} => {
+				let res = self.get_block_extrinsics(block_hash).await;
+				_ = response_sender.send(res);
+			},
+			Command::RequestDataProof {
+				block_hash,
+				extrinsic,
+				response_sender,
+			} => {
+				let res = self.request_data_proof(block_hash, &extrinsic).await;
+				_ = response_sender.send(res);
+			},
+			Command::DryRun {
+				tx_bytes,
+				response_sender,
+			} => {
+				let res = self.dry_run(tx_bytes).await;
 				_ = response_sender.send(res);
 			},
 			Command::GetPagedStorageKeys {
@@ -545,6 +675,13 @@ impl EventLoop {
 				let res = self.request_finality_proof(block_number).await;
 				_ = response_sender.send(res);
 			},
+			Command::GetAccountNonce {
+				account_id,
+				response_sender,
+			} => {
+				let res = self.get_account_nonce(&account_id).await;
+				_ = response_sender.send(res);
+			},
 			Command::GetGenesisHash { response_sender } => {
 				let res = self.get_genesis_hash();
 				_ = response_sender.send(res);
@@ -699,13 +836,109 @@ impl EventLoop {
 
 	async fn submit_signed_and_watch(
 		&self,
-		extrinsic: Payload<SubmitData>,
+		extrinsic: &impl TxPayload,
 		pair_signer: &PairSigner<AvailConfig, avail::Pair>,
+		nonce: u32,
 		params: AvailExtrinsicParams,
 	) -> Result<TxProgress<AvailConfig, OnlineClient<AvailConfig>>> {
 		self.unpack_client()?
 			.tx()
-			.sign_and_submit_then_watch(&extrinsic, pair_signer, params)
+			.create_signed_with_nonce(extrinsic, pair_signer, nonce, params)
+			.map_err(|e| anyhow!(e))?
+			.submit_and_watch()
+			.await
+			.map_err(|e| anyhow!(e))
+	}
+
+	fn create_signed(
+		&self,
+		extrinsic: &impl TxPayload,
+		pair_signer: &PairSigner<AvailConfig, avail::Pair>,
+		nonce: u32,
+		params: AvailExtrinsicParams,
+	) -> Result<Vec<u8>> {
+		self.unpack_client()?
+			.tx()
+			.create_signed_with_nonce(extrinsic, pair_signer, nonce, params)
+			.map(|extrinsic| extrinsic.into_encoded())
+			.map_err(|e| anyhow!(e))
+	}
+
+	async fn query_fee_info(&self, tx_bytes: &[u8]) -> Result<RuntimeDispatchInfo> {
+		let mut params = RpcParams::new();
+		params.push(format!("0x{}", hex::encode(tx_bytes)))?;
+
+		self.unpack_client()?
+			.rpc()
+			.request("payment_queryInfo", params)
+			.await
+			.map_err(|e| anyhow!("Failed to query fee info. Error: {e}"))
+	}
+
+	async fn get_block_extrinsics(&self, block_hash: H256) -> Result<Vec<Vec<u8>>> {
+		let block = self
+			.unpack_client()?
+			.rpc()
+			.block(Some(block_hash))
+			.await?
+			.ok_or_else(|| anyhow!("Block with hash: {block_hash:?} not found"))?;
+
+		Ok(block
+			.block
+			.extrinsics
+			.into_iter()
+			.map(|extrinsic| extrinsic.0)
+			.collect())
+	}
+
+	async fn request_data_proof(&self, block_hash: H256, extrinsic: &[u8]) -> Result<DataProof> {
+		let client = self.unpack_client()?;
+		let block = client
+			.rpc()
+			.block(Some(block_hash))
+			.await?
+			.ok_or_else(|| anyhow!("Block with hash: {block_hash:?} not found"))?;
+
+		// Data proof is requested by the index of the extrinsic in the block,
+		// and block extrinsics are returned without the length prefix
+		let transaction_index = block
+			.block
+			.extrinsics
+			.iter()
+			.position(|block_extrinsic| {
+				block_extrinsic.0 == extrinsic || block_extrinsic.0.encode() == extrinsic
+			})
+			.ok_or_else(|| anyhow!("Extrinsic not found in block with hash: {block_hash:?}"))?;
+
+		let mut params = RpcParams::new();
+		params.push(transaction_index as u32)?;
+		params.push(block_hash)?;
+
+		client
+			.rpc()
+			.request("kate_queryDataProof", params)
+			.await
+			.map_err(|e| anyhow!("Failed to query data proof. Error: {e}"))
+	}
+
+	async fn dry_run(&self, tx_bytes: Vec<u8>) -> Result<DryRunOutcome> {
+		let client = self.unpack_client()?;
+		let result = SubmittableExtrinsic::from_bytes(client.clone(), tx_bytes)
+			.dry_run(None)
+			.await
+			.map_err(|e| anyhow!("Failed to dry run extrinsic. Error: {e}"))?;
+
+		Ok(match result {
+			DryRunResult::Success => DryRunOutcome::Success,
+			DryRunResult::DispatchError(error) => DryRunOutcome::DispatchError(error.to_string()),
+			DryRunResult::TransactionValidityError => DryRunOutcome::Invalid,
+		})
+	}
+
+	async fn get_account_nonce(&self, account_id: &AccountId32) -> Result<u32> {
+		self.unpack_client()?
+			.rpc()
+			.system_account_next_index(account_id)
 			.await
 			.map_err(|e| anyhow!(e))
 	}
@@ -771,26 +1004,3 @@ impl EventLoop {
 		Ok(client.genesis_hash())
 	}
 }
-
-fn is_signed_by_supermajority(num_signatures: usize, validator_set_size: usize) -> bool {
-	let supermajority = (validator_set_size * 2 / 3) + 1;
-	num_signatures >= supermajority
-}
-
-#[cfg(test)]
-mod tests {
-	use test_case::test_case;
-	#[test_case(1, 1 => true)]
-	#[test_case(1, 2 => false)]
-	#[test_case(2, 2 => true)]
-	#[test_case(2, 3 => false)]
-	#[test_case(3, 3 => true)]
-	#[test_case(3, 4 => true)]
-	#[test_case(4, 5 => true)]
-	#[test_case(66, 100 => false)]
-	#[test_case(67, 100 => true)]
-	fn check_supermajority_condition(num_signatures: usize, validator_set_size: usize) -> bool {
-		use crate::network::rpc::event_loop::is_signed_by_supermajority;
-		is_signed_by_supermajority(num_signatures, validator_set_size)
-	}
-}
@@ -0,0 +1,268 @@
+use avail_subxt::{primitives::Header, utils::H256};
+use codec::Encode;
+use sp_core::blake2_256;
+use std::{
+	collections::{BTreeMap, HashMap, HashSet},
+	time::Instant,
+};
+
+struct Node {
+	header: Header,
+	received_at: Instant,
+}
+
+/// Tree of received, but not yet finalized headers, keyed by header hash.
+///
+/// Headers are linked to their parents by parent hash, so multiple forks can be tracked
+/// until a justification finalizes one of them. Number of tracked headers is bounded,
+/// and the lowest headers are evicted first.
+pub struct HeaderTree {
+	nodes: HashMap<H256, Node>,
+	/// Hashes of the tracked headers by block number
+	numbers: BTreeMap<u32, HashSet<H256>>,
+	max_headers: usize,
+}
+
+fn hash(header: &Header) -> H256 {
+	Encode::using_encoded(header, blake2_256).into()
+}
+
+impl HeaderTree {
+	pub fn new(max_headers: usize) -> Self {
+		HeaderTree {
+			nodes: HashMap::new(),
+			numbers: BTreeMap::new(),
+			max_headers,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	pub fn contains(&self, hash: &H256) -> bool {
+		self.nodes.contains_key(hash)
+	}
+
+	/// Inserts header into the tree, evicting the lowest headers if tree is full.
+	pub fn insert(&mut self, header: Header, received_at: Instant) {
+		let hash = hash(&header);
+		self.numbers.entry(header.number).or_default().insert(hash);
+		self.nodes.insert(
+			hash,
+			Node {
+				header,
+				received_at,
+			},
+		);
+
+		while self.nodes.len() > self.max_headers {
+			let Some(lowest) = self
+				.numbers
+				.first_key_value()
+				.and_then(|(_, hashes)| hashes.iter().next().copied())
+			else {
+				break;
+			};
+			self.remove(&lowest);
+		}
+	}
+
+	fn remove(&mut self, hash: &H256) -> Option<Node> {
+		let node = self.nodes.remove(hash)?;
+		if let Some(hashes) = self.numbers.get_mut(&node.header.number) {
+			hashes.remove(hash);
+			if hashes.is_empty() {
+				self.numbers.remove(&node.header.number);
+			}
+		}
+		Some(node)
+	}
+
+	/// Removes finalized header and its ancestors from the tree, and prunes stale forks.
+	///
+	/// Returns finalized headers in ascending order, starting from the lowest ancestor found in the tree,
+	/// or `None` if the header with given hash is not in the tree.
+	pub fn finalize(&mut self, finalized_hash: &H256) -> Option<Vec<(Header, Instant)>> {
+		let mut finalized = vec![];
+		let mut hash = *finalized_hash;
+		while let Some(node) = self.remove(&hash) {
+			hash = node.header.parent_hash;
+			finalized.push((node.header, node.received_at));
+		}
+		finalized.reverse();
+
+		let finalized_number = finalized.last()?.0.number;
+		self.prune(finalized_hash, finalized_number);
+		Some(finalized)
+	}
+
+	/// Keeps only headers which are descendants of the finalized header.
+	/// Headers with unknown parents above the finalized block are kept, since their ancestry cannot be checked yet.
+	fn prune(&mut self, finalized_hash: &H256, finalized_number: u32) {
+		let headers = self
+			.numbers
+			.iter()
+			.flat_map(|(&number, hashes)| hashes.iter().map(move |&hash| (number, hash)))
+			.map(|(number, hash)| (number, hash, self.nodes[&hash].header.parent_hash))
+			.collect::<Vec<_>>();
+		let known = self.nodes.keys().copied().collect::<HashSet<_>>();
+
+		let mut kept = HashSet::from([*finalized_hash]);
+		for (number, hash, parent_hash) in headers {
+			let is_descendant = number > finalized_number
+				&& (kept.contains(&parent_hash)
+					|| (number > finalized_number + 1 && !known.contains(&parent_hash)));
+
+			if is_descendant {
+				kept.insert(hash);
+			} else {
+				self.remove(&hash);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{hash, HeaderTree};
+	use crate::utils::test_utils;
+	use avail_subxt::primitives::Header;
+	use sp_core::H256;
+	use std::time::Instant;
+
+	/// Header on the given fork, which is distinguished by the state root
+	fn header(number: u32, parent_hash: H256, fork: u8) -> Header {
+		Header {
+			state_root: H256::repeat_byte(fork),
+			..test_utils::header(number, parent_hash)
+		}
+	}
+
+	fn chain(parent: &Header, length: u32, fork: u8) -> Vec<Header> {
+		let mut parent_hash = hash(parent);
+		(1..=length)
+			.map(|i| {
+				let header = header(parent.number + i, parent_hash, fork);
+				parent_hash = hash(&header);
+				header
+			})
+			.collect()
+	}
+
+	fn numbers(headers: &[(Header, Instant)]) -> Vec<u32> {
+		headers.iter().map(|(header, _)| header.number).collect()
+	}
+
+	#[test]
+	fn finalize_in_ascending_order() {
+		let genesis = header(0, H256::default(), 0);
+		let headers = chain(&genesis, 5, 0);
+		let mut tree = HeaderTree::new(100);
+		for header in headers.iter().rev() {
+			tree.insert(header.clone(), Instant::now());
+		}
+
+		let finalized = tree.finalize(&hash(&headers[2])).unwrap();
+
+		assert_eq!(numbers(&finalized), vec![1, 2, 3]);
+		assert_eq!(tree.len(), 2);
+		assert!(tree.contains(&hash(&headers[3])));
+		assert!(tree.contains(&hash(&headers[4])));
+	}
+
+	#[test]
+	fn finalize_unknown_header() {
+		let genesis = header(0, H256::default(), 0);
+		let headers = chain(&genesis, 3, 0);
+		let mut tree = HeaderTree::new(100);
+		tree.insert(headers[0].clone(), Instant::now());
+
+		assert!(tree.finalize(&hash(&headers[2])).is_none());
+		assert_eq!(tree.len(), 1);
+	}
+
+	#[test]
+	fn finalize_prunes_stale_forks() {
+		let genesis = header(0, H256::default(), 0);
+		let canonical = chain(&genesis, 4, 0);
+		let fork = chain(&canonical[0], 4, 1);
+		let mut tree = HeaderTree::new(100);
+		for header in canonical.iter().chain(fork.iter()) {
+			tree.insert(header.clone(), Instant::now());
+		}
+		assert_eq!(tree.len(), 8);
+
+		let finalized = tree.finalize(&hash(&canonical[2])).unwrap();
+
+		assert_eq!(numbers(&finalized), vec![1, 2, 3]);
+		assert_eq!(tree.len(), 1);
+		assert!(tree.contains(&hash(&canonical[3])));
+	}
+
+	#[test]
+	fn finalize_keeps_headers_with_unknown_parents() {
+		let genesis = header(0, H256::default(), 0);
+		let headers = chain(&genesis, 6, 0);
+		let mut tree = HeaderTree::new(100);
+		for header in [&headers[0], &headers[1], &headers[4], &headers[5]] {
+			tree.insert(header.clone(), Instant::now());
+		}
+
+		let finalized = tree.finalize(&hash(&headers[1])).unwrap();
+
+		assert_eq!(numbers(&finalized), vec![1, 2]);
+		assert_eq!(tree.len(), 2);
+	}
+
+	#[test]
+	fn insert_evicts_lowest_headers() {
+		let genesis = header(0, H256::default(), 0);
+		let headers = chain(&genesis, 10, 0);
+		let mut tree = HeaderTree::new(4);
+		for header in &headers {
+			tree.insert(header.clone(), Instant::now());
+		}
+
+		assert_eq!(tree.len(), 4);
+		assert!(!tree.contains(&hash(&headers[5])));
+		assert!(tree.contains(&hash(&headers[6])));
+		assert!(tree.contains(&hash(&headers[9])));
+	}
+
+	#[test]
+	fn insert_evicts_lowest_forks() {
+		let genesis = header(0, H256::default(), 0);
+		let canonical = chain(&genesis, 3, 0);
+		let fork = chain(&genesis, 3, 1);
+		let mut tree = HeaderTree::new(4);
+		for header in canonical.iter().chain(fork.iter()) {
+			tree.insert(header.clone(), Instant::now());
+		}
+
+		assert_eq!(tree.len(), 4);
+		assert!(!tree.contains(&hash(&canonical[0])));
+		assert!(!tree.contains(&hash(&fork[0])));
+		assert!(tree.contains(&hash(&canonical[1])));
+		assert!(tree.contains(&hash(&fork[1])));
+	}
+
+	#[test]
+	fn finalize_again_after_reinsert() {
+		let genesis = header(0, H256::default(), 0);
+		let headers = chain(&genesis, 5, 0);
+		let mut tree = HeaderTree::new(100);
+		for header in &headers {
+			tree.insert(header.clone(), Instant::now());
+		}
+
+		// Finalized headers are inserted back if they cannot be sent without gaps
+		for (header, received_at) in tree.finalize(&hash(&headers[2])).unwrap() {
+			tree.insert(header, received_at);
+		}
+		let finalized = tree.finalize(&hash(&headers[2])).unwrap();
+
+		assert_eq!(numbers(&finalized), vec![1, 2, 3]);
+		assert_eq!(tree.len(), 2);
+	}
+}
@@ -16,10 +16,7 @@
 //! In case RPC is disabled, RPC calls will be skipped.
 
 use crate::{
-	data::{
-		is_block_header_in_db, is_confidence_in_db, store_block_header_in_db,
-		store_confidence_in_db,
-	},
+	data::Database,
 	network::{
 		p2p::Client as P2pClient,
 		rpc::{self, Client as RpcClient},
@@ -31,11 +28,12 @@ use crate::{
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use avail_subxt::{primitives::Header as DaHeader, utils::H256};
+use codec::Encode;
 use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
 use kate_recovery::{commitments, matrix::Dimensions};
 use kate_recovery::{data::Cell, matrix::Position};
 use mockall::automock;
-use rocksdb::DB;
+use sp_core::blake2_256;
 use std::{
 	ops::Range,
 	sync::{Arc, Mutex},
@@ -47,11 +45,7 @@ use tracing::{error, info, warn};
 #[async_trait]
 #[automock]
 pub trait SyncClient {
-	fn block_header_in_db(&self, block_number: u32) -> Result<bool>;
 	async fn get_header_by_block_number(&self, block_number: u32) -> Result<(DaHeader, H256)>;
-	fn store_block_header_in_db(&self, header: DaHeader, block_number: u32) -> Result<()>;
-	fn is_confidence_in_db(&self, block_number: u32) -> Result<bool>;
-	fn store_confidence_in_db(&self, count: u32, block_number: u32) -> Result<()>;
 	async fn get_kate_proof(&self, hash: H256, positions: &[Position]) -> Result<Vec<Cell>>;
 	async fn insert_cells_into_dht(&self, block: u32, cells: Vec<Cell>) -> f32;
 	async fn fetch_cells_from_dht(
@@ -62,14 +56,12 @@ pub trait SyncClient {
 }
 #[derive(Clone)]
 struct SyncClientImpl {
-	db: Arc<DB>,
 	network_client: P2pClient,
 	rpc_client: RpcClient,
 }
 
-pub fn new(db: Arc<DB>, network_client: P2pClient, rpc_client: RpcClient) -> impl SyncClient {
+pub fn new(network_client: P2pClient, rpc_client: RpcClient) -> impl SyncClient {
 	SyncClientImpl {
-		db,
 		network_client,
 		rpc_client,
 	}
@@ -77,11 +69,6 @@ pub fn new(db: Arc<DB>, network_client: P2pClient, rpc_client: RpcClient) -> imp
 
 #[async_trait]
 impl SyncClient for SyncClientImpl {
-	fn block_header_in_db(&self, block_number: u32) -> Result<bool> {
-		is_block_header_in_db(self.db.clone(), block_number)
-			.context("Failed to check if block header is in DB")
-	}
-
 	async fn get_header_by_block_number(&self, block_number: u32) -> Result<(DaHeader, H256)> {
 		self.rpc_client
 			.get_header_by_block_number(block_number)
@@ -89,21 +76,6 @@ impl SyncClient for SyncClientImpl {
 			.with_context(|| format!("Failed to get block {block_number} by block number"))
 	}
 
-	fn store_block_header_in_db(&self, header: DaHeader, block_number: u32) -> Result<()> {
-		store_block_header_in_db(self.db.clone(), block_number, &header)
-			.context("Failed to store block header in DB")
-	}
-
-	fn is_confidence_in_db(&self, block_number: u32) -> Result<bool> {
-		is_confidence_in_db(self.db.clone(), block_number)
-			.context("Failed to check if confidence is in DB")
-	}
-
-	fn store_confidence_in_db(&self, count: u32, block_number: u32) -> Result<()> {
-		store_confidence_in_db(self.db.clone(), block_number, count)
-			.context("Failed to store confidence in DB")
-	}
-
 	async fn get_kate_proof(&self, hash: H256, positions: &[Position]) -> Result<Vec<Cell>> {
 		self.rpc_client.request_kate_proof(hash, positions).await
 	}
@@ -126,42 +98,57 @@ impl SyncClient for SyncClientImpl {
 
 async fn process_block(
 	sync_client: &impl SyncClient,
+	db: &impl Database,
 	block_number: u32,
 	cfg: &SyncClientConfig,
 	pp: Arc<PublicParameters>,
 	block_verified_sender: Option<broadcast::Sender<BlockVerified>>,
 ) -> Result<()> {
-	if sync_client
-		.block_header_in_db(block_number)
-		.context("Failed to check if block header is in DB")?
-	{
-		// TODO: If block header storing fails, that block will be skipped upon restart
-		// Better option would be to check for confidence
-		info!("Block header {block_number} already in DB");
-		return Ok(());
+	let is_header_verified = db
+		.get_block_state(block_number)
+		.context("Failed to get block state from DB")?
+		.is_some_and(|block_state| block_state.header_verified);
+	let stored_header = if is_header_verified {
+		db.get_header(block_number)
+			.context("Failed to get block header from DB")?
+	} else {
+		None
 	};
 
-	// if block header look up fails, only then comes here for
-	// fetching and storing block header as part of (light weight)
-	// syncing process
-	let begin = Instant::now();
+	// Headers which are not verified (e.g. stored before a failure) are fetched again
+	let (header, header_hash) = match stored_header {
+		Some(header) => {
+			info!("Block header {block_number} already in DB");
+			let header_hash = Encode::using_encoded(&header, blake2_256).into();
+			(header, header_hash)
+		},
+		None => {
+			let begin = Instant::now();
 
-	let (header, header_hash) = sync_client.get_header_by_block_number(block_number).await?;
+			let (header, header_hash) =
+				sync_client.get_header_by_block_number(block_number).await?;
 
-	let app_lookup = extract_app_lookup(&header.extension);
+			let app_lookup = extract_app_lookup(&header.extension);
 
-	info!(block_number, "App index {:?}", app_lookup);
+			info!(block_number, "App index {:?}", app_lookup);
 
-	sync_client
-		.store_block_header_in_db(header.clone(), block_number)
-		.context("Failed to store block header in DB")?;
+			db.store_header(block_number, &header)
+				.context("Failed to store block header in DB")?;
+			// TODO: Add proper header verification on sync
+			db.update_block_state(block_number, |block_state| {
+				block_state.header_verified = true;
+			})
+			.context("Failed to store block state in DB")?;
 
-	info!(block_number, elapsed = ?begin.elapsed(), "Synced block header");
+			info!(block_number, elapsed = ?begin.elapsed(), "Synced block header");
+			(header, header_hash)
+		},
+	};
 
 	// If it's found that this certain block is not verified
 	// then it'll be verified now
-	if sync_client
-		.is_confidence_in_db(block_number)
+	if db
+		.has_confidence(block_number)
 		.context("Failed to check if confidence is in DB")?
 	{
 		return Ok(());
@@ -222,8 +209,7 @@ async fn process_block(
 	);
 
 	// write confidence factor into on-disk database
-	sync_client
-		.store_confidence_in_db(verified.len().try_into()?, block_number)
+	db.store_confidence(block_number, verified.len().try_into()?)
 		.context("Failed to store confidence in DB")?;
 
 	let inserted_cells = sync_client
@@ -248,6 +234,8 @@ async fn process_block(
 ///
 /// # Arguments
 ///
+/// * `sync_client` - Sync client implementation
+/// * `db` - Database to store confidence and headers
 /// * `cfg` - Sync client configuration
 /// * `start_block` - Sync start block
 /// * `end_block` - Sync end block
@@ -255,6 +243,7 @@ async fn process_block(
 /// * `block_verified_sender` - Optional channel to send verified blocks
 pub async fn run(
 	sync_client: impl SyncClient,
+	db: impl Database,
 	cfg: SyncClientConfig,
 	sync_range: Range<u32>,
 	pp: Arc<PublicParameters>,
@@ -282,11 +271,27 @@ pub async fn run(
 		// TODO: Should we handle unprocessed blocks differently?
 		let block_verified_sender = block_verified_sender.clone();
 		let pp = pp.clone();
-		if let Err(error) =
-			process_block(&sync_client, block_number, &cfg, pp, block_verified_sender).await
+		if let Err(error) = process_block(
+			&sync_client,
+			&db,
+			block_number,
+			&cfg,
+			pp,
+			block_verified_sender,
+		)
+		.await
 		{
 			error!(block_number, "Cannot process block: {error:#}");
+			if let Err(error) = db.store_block_failure(block_number, format!("{error:#}")) {
+				error!(block_number, "Cannot store block state: {error:#}");
+			}
 		} else {
+			if let Err(error) = db.update_block_state(block_number, |block_state| {
+				block_state.confidence_achieved = true;
+				block_state.failure = None;
+			}) {
+				error!(block_number, "Cannot store block state: {error:#}");
+			}
 			let mut state = state.lock().unwrap();
 			state.sync_confidence_achieved.set(block_number);
 		}
@@ -301,7 +306,10 @@ pub async fn run(
 mod tests {
 
 	use super::*;
-	use crate::types::{self, RuntimeConfig};
+	use crate::{
+		data::MemoryDB,
+		types::{self, RuntimeConfig},
+	};
 	use avail_subxt::{
 		api::runtime_types::avail_core::{
 			data_lookup::compact::CompactDataLookup,
@@ -357,11 +365,6 @@ mod tests {
 		};
 		let header_hash: H256 =
 			hex!("3767f8955d6f7306b1e55701b6316fa1163daa8d4cffdb05c3b25db5f5da1723").into();
-		mock_client
-			.expect_block_header_in_db()
-			.with(eq(42))
-			.returning(|_| Ok(false));
-
 		mock_client
 			.expect_get_header_by_block_number()
 			.with(eq(42))
@@ -370,14 +373,6 @@ mod tests {
 
 				Box::pin(async move { Ok((header, header_hash)) })
 			});
-		mock_client
-			.expect_store_block_header_in_db()
-			.withf(|_, x| *x == 42)
-			.returning(|_, _| Ok(()));
-		mock_client
-			.expect_is_confidence_in_db()
-			.with(eq(42))
-			.returning(|_| Ok(true));
 		mock_client
 			.expect_fetch_cells_from_dht()
 			.withf(|_, x: &u32| *x == 42)
@@ -433,18 +428,17 @@ mod tests {
 		if cfg.disable_rpc {
 			mock_client.expect_get_kate_proof().never();
 		}
-		mock_client
-			.expect_is_confidence_in_db()
-			.with(eq(42))
-			.returning(|_| Ok(true));
 
 		mock_client
 			.expect_insert_cells_into_dht()
 			.withf(move |x, _| *x == 42)
 			.returning(move |_, _| Box::pin(async move { 1f32 }));
-		process_block(&mock_client, 42, &cfg, pp, Some(block_tx))
+		let db = MemoryDB::default();
+		db.store_confidence(42, 1).unwrap();
+		process_block(&mock_client, &db, 42, &cfg, pp, Some(block_tx))
 			.await
 			.unwrap();
+		assert!(db.has_header(42).unwrap());
 	}
 
 	#[tokio::test]
@@ -499,11 +493,6 @@ mod tests {
 				59, 170, 52, 243, 140, 237, 0,
 			],
 		}];
-		mock_client
-			.expect_block_header_in_db()
-			.with(eq(42))
-			.returning(|_| Ok(false));
-
 		mock_client
 			.expect_get_header_by_block_number()
 			.with(eq(42))
@@ -512,14 +501,6 @@ mod tests {
 
 				Box::pin(async move { Ok((header, header_hash)) })
 			});
-		mock_client
-			.expect_store_block_header_in_db()
-			.withf(|_, x| *x == 42)
-			.returning(|_, _| Ok(()));
-		mock_client
-			.expect_is_confidence_in_db()
-			.with(eq(42))
-			.returning(|_| Ok(true));
 		mock_client
 			.expect_fetch_cells_from_dht()
 			.withf(|_, x: &u32| *x == 42)
@@ -569,17 +550,16 @@ mod tests {
 				Box::pin(async move { Ok(unfetched) })
 			});
 		}
-		mock_client
-			.expect_is_confidence_in_db()
-			.with(eq(42))
-			.returning(|_| Ok(true));
 		mock_client
 			.expect_insert_cells_into_dht()
 			.withf(move |x, _| *x == 42)
 			.returning(move |_, _| Box::pin(async move { 1f32 }));
-		process_block(&mock_client, 42, &cfg, pp, Some(block_tx))
+		let db = MemoryDB::default();
+		db.store_confidence(42, 1).unwrap();
+		process_block(&mock_client, &db, 42, &cfg, pp, Some(block_tx))
 			.await
 			.unwrap();
+		assert!(db.has_header(42).unwrap());
 	}
 	#[tokio::test]
 	pub async fn test_header_in_dbstore() {
@@ -587,13 +567,32 @@ mod tests {
 		let pp = Arc::new(couscous::public_params());
 		let cfg = SyncClientConfig::from(&RuntimeConfig::default());
 		let mut mock_client = MockSyncClient::new();
-		mock_client
-			.expect_block_header_in_db()
-			.withf(|block: &u32| *block == 42)
-			.returning(|_| Ok(true));
 		mock_client.expect_get_header_by_block_number().never();
-		mock_client.block_header_in_db(42).unwrap();
-		process_block(&mock_client, 42, &cfg, pp, Some(block_tx))
+		let db = MemoryDB::default();
+		let header = DaHeader {
+			parent_hash: H256::default(),
+			number: 42,
+			state_root: H256::default(),
+			extrinsics_root: H256::default(),
+			digest: Digest { logs: vec![] },
+			extension: V1(HeaderExtension {
+				commitment: KateCommitment {
+					rows: 0,
+					cols: 0,
+					data_root: H256::default(),
+					commitment: vec![],
+				},
+				app_lookup: CompactDataLookup {
+					size: 0,
+					index: vec![],
+				},
+			}),
+		};
+		db.store_header(42, &header).unwrap();
+		db.update_block_state(42, |block_state| block_state.header_verified = true)
+			.unwrap();
+		db.store_confidence(42, 1).unwrap();
+		process_block(&mock_client, &db, 42, &cfg, pp, Some(block_tx))
 			.await
 			.unwrap();
 	}
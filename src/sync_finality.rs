@@ -1,33 +1,35 @@
 use anyhow::{anyhow, bail, Context, Result};
 use avail_subxt::primitives::Header;
 use codec::Encode;
-use futures::future::join_all;
-use rocksdb::DB;
+use futures::{future::join_all, stream, Future, StreamExt};
 use sp_core::{
 	blake2_256,
 	ed25519::{self},
-	twox_128, Pair, H256,
+	twox_128, H256,
+};
+use std::{
+	ops::RangeInclusive,
+	sync::{Arc, Mutex},
 };
-use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc::Sender;
-use tracing::{error, info, trace};
+use tracing::{error, info, trace, warn};
 
 use crate::{
-	data::{
-		get_finality_sync_checkpoint, store_block_header_in_db, store_finality_sync_checkpoint,
-	},
+	data::Database,
+	finality,
 	network::rpc::{self, WrappedProof},
-	types::{FinalitySyncCheckpoint, SignerMessage, State},
+	types::{
+		FinalitySyncCheckpoint, FinalitySyncProgress, GrandpaJustification, State,
+		SyncFinalityConfig,
+	},
 	utils::filter_auth_set_changes,
 };
 
 pub trait SyncFinality {
 	fn get_client(&self) -> rpc::Client;
-	fn get_db(&self) -> Arc<DB>;
 }
 
 pub struct SyncFinalityImpl {
-	db: Arc<DB>,
 	rpc_client: rpc::Client,
 }
 
@@ -35,18 +37,16 @@ impl SyncFinality for SyncFinalityImpl {
 	fn get_client(&self) -> rpc::Client {
 		self.rpc_client.clone()
 	}
-
-	fn get_db(&self) -> Arc<DB> {
-		self.db.clone()
-	}
 }
 
-pub fn new(db: Arc<DB>, rpc_client: rpc::Client) -> impl SyncFinality {
-	SyncFinalityImpl { db, rpc_client }
+pub fn new(rpc_client: rpc::Client) -> impl SyncFinality {
+	SyncFinalityImpl { rpc_client }
 }
 
 const GRANDPA_KEY_ID: [u8; 4] = *b"gran";
 const GRANDPA_KEY_LEN: usize = 32;
+/// Number of verified headers after which finality sync checkpoint is stored
+const HEADERS_CHECKPOINT_INTERVAL: u32 = 1000;
 
 async fn get_valset_at_genesis(
 	rpc_client: rpc::Client,
@@ -105,11 +105,13 @@ async fn get_valset_at_genesis(
 
 pub async fn run(
 	sync_finality_impl: impl SyncFinality,
+	db: impl Database,
+	cfg: SyncFinalityConfig,
 	error_sender: Sender<anyhow::Error>,
 	state: Arc<Mutex<State>>,
 	from_header: Header,
 ) {
-	if let Err(err) = sync_finality(sync_finality_impl, state, from_header).await {
+	if let Err(err) = sync_finality(sync_finality_impl, db, cfg, state, from_header).await {
 		error!("Cannot sync finality {err}");
 		if let Err(error) = error_sender.send(err).await {
 			error!("Cannot send error message: {error}");
@@ -117,15 +119,159 @@ pub async fn run(
 	};
 }
 
+/// Finds the first block in the `(from, to]` range at which the GRANDPA set ID is greater than `set_id`.
+///
+/// Set ID is incremented in the same block which carries the authority set change digest,
+/// so the set ID is monotonic over block numbers and the change block can be found by bisection.
+async fn find_authority_set_change<F, Fut>(
+	mut from: u32,
+	mut to: u32,
+	set_id: u64,
+	set_id_at: F,
+) -> Result<Option<u32>>
+where
+	F: Fn(u32) -> Fut,
+	Fut: Future<Output = Result<u64>>,
+{
+	if to <= from || set_id_at(to).await? <= set_id {
+		return Ok(None);
+	}
+
+	// Set ID at `from` is not greater than `set_id`, and set ID at `to` is greater than `set_id`
+	while to - from > 1 {
+		let middle = from + (to - from) / 2;
+		if set_id_at(middle).await? > set_id {
+			to = middle;
+		} else {
+			from = middle;
+		}
+	}
+
+	Ok(Some(to))
+}
+
+/// Verifies that the header is the justification target, or its ancestor.
+///
+/// Ancestry is walked by parent hashes from the target, through the headers
+/// in the `(header, target]` range provided with the finality proof.
+fn verify_finalized_header(
+	justification: &GrandpaJustification,
+	header: &Header,
+	unknown_headers: &[Header],
+) -> Result<()> {
+	let block_number = header.number;
+	let header_hash: H256 = header.using_encoded(blake2_256).into();
+	let mut hash = justification.commit.target_hash;
+
+	while hash != header_hash {
+		let Some(ancestor) = unknown_headers
+			.iter()
+			.find(|ancestor| ancestor.using_encoded(blake2_256) == hash.0)
+		else {
+			bail!("Justification target {hash:?} doesn't finalize block {block_number}");
+		};
+		if ancestor.number <= block_number {
+			bail!("Justification target {hash:?} doesn't finalize block {block_number}");
+		}
+		hash = ancestor.parent_hash;
+	}
+
+	Ok(())
+}
+
+fn verify_justification(
+	justification: &GrandpaJustification,
+	set_id: u64,
+	validator_set: &[ed25519::Public],
+	header: &Header,
+	unknown_headers: &[Header],
+) -> Result<()> {
+	let block_number = header.number;
+	verify_finalized_header(justification, header, unknown_headers)?;
+
+	let verified = finality::verify_justification(justification, set_id, validator_set).context(
+		format!("Justification verification failed for block {block_number}"),
+	)?;
+
+	for equivocation in verified.equivocations {
+		warn!(
+			"Equivocation by {:?} in round {}: {:?} and {:?}",
+			equivocation.id, equivocation.round, equivocation.first, equivocation.second
+		);
+	}
+
+	info!(
+		"Number of matching signatures for block {block_number}: {}/{}",
+		verified.signers,
+		validator_set.len()
+	);
+
+	Ok(())
+}
+
+/// Fetches headers in the given range with bounded concurrency, and verifies that they are linked by parent hashes.
+///
+/// Headers are passed to `on_verified` in ascending order, only after their linkage has been verified.
+/// Returns the last verified header, if range is not empty.
+async fn verify_header_chain<F, Fut>(
+	range: RangeInclusive<u32>,
+	mut prev_hash: H256,
+	parallelism: usize,
+	fetch_header: F,
+	mut on_verified: impl FnMut(&Header) -> Result<()>,
+) -> Result<Option<Header>>
+where
+	F: Fn(u32) -> Fut,
+	Fut: Future<Output = Result<(Header, H256)>>,
+{
+	let mut last_header = None;
+	let mut headers = stream::iter(range.clone())
+		.map(&fetch_header)
+		.buffered(parallelism.max(1));
+
+	for block_number in range {
+		let (header, _) = headers
+			.next()
+			.await
+			.ok_or_else(|| anyhow!("Header stream ended before block no. {block_number}"))?
+			.context(format!("Couldn't get header for block no. {block_number}"))?;
+
+		if header.number != block_number {
+			bail!(
+				"Expected header for block no. {block_number}, got {}",
+				header.number
+			);
+		}
+		if header.parent_hash != prev_hash {
+			bail!("Parent hash doesn't match for block no. {block_number}!");
+		}
+		prev_hash = header.using_encoded(blake2_256).into();
+
+		on_verified(&header)?;
+		last_header = Some(header);
+	}
+
+	Ok(last_header)
+}
+
+fn set_progress(state: &Arc<Mutex<State>>, block_number: u32) {
+	let mut state = state.lock().unwrap();
+	if let Some(progress) = state.finality_sync.as_mut() {
+		progress.latest = block_number;
+	}
+}
+
 pub async fn sync_finality(
 	sync_finality: impl SyncFinality,
+	db: impl Database,
+	cfg: SyncFinalityConfig,
 	state: Arc<Mutex<State>>,
-	mut from_header: Header,
+	from_header: Header,
 ) -> Result<()> {
 	let rpc_client = sync_finality.get_client();
 	let gen_hash = rpc_client.get_genesis_hash().await?;
 
-	let checkpoint = get_finality_sync_checkpoint(sync_finality.get_db())?;
+	let checkpoint = db.get_finality_sync_checkpoint()?;
 
 	info!("Starting finality validation sync.");
 	let mut set_id: u64;
@@ -150,119 +296,281 @@ pub async fn sync_finality(
 	let last_block_num = from_header.number;
 
 	info!("Syncing finality from {curr_block_num} up to block no. {last_block_num}");
-	let db = sync_finality.get_db();
+	state.lock().unwrap().finality_sync = Some(FinalitySyncProgress::new(
+		curr_block_num,
+		last_block_num,
+		cfg.verify_headers,
+	));
 
-	let mut prev_hash = rpc_client
-		.get_block_hash(curr_block_num - 1)
-		.await
-		.context("Hash doesn't exist?")?;
+	// Hash of the last verified header, used to check the header chain linkage
+	let mut prev_hash = None;
+	if cfg.verify_headers {
+		let prev_block_num = curr_block_num.saturating_sub(1);
+		let hash = match db.get_header(prev_block_num)? {
+			Some(header) => header.using_encoded(blake2_256).into(),
+			None => rpc_client
+				.get_block_hash(prev_block_num)
+				.await
+				.context("Hash doesn't exist?")?,
+		};
+		prev_hash = Some(hash);
+	}
+
+	// Only blocks which rotate the validator set need to be verified,
+	// so sync jumps from one authority set change to the next one
 	loop {
-		if curr_block_num == last_block_num + 1 {
-			info!("Finished verifying finality up to block no. {last_block_num}!");
-			break;
-		}
-		let hash = rpc_client
-			.get_block_hash(curr_block_num)
-			.await
-			.context(format!(
-				"Couldn't get hash for block no. {}",
-				curr_block_num
-			))?;
-		from_header = rpc_client
-			.get_header_by_hash(hash)
-			.await
-			.context(format!("Couldn't get header for {}", hash))?;
-		store_block_header_in_db(db.clone(), curr_block_num, &from_header)?;
+		let change_block_num = find_authority_set_change(
+			curr_block_num.saturating_sub(1),
+			last_block_num,
+			set_id,
+			|block_number| rpc_client.get_current_set_id_by_block_number(block_number),
+		)
+		.await
+		.context(format!(
+			"Couldn't find authority set change after set ID {set_id}"
+		))?;
 
-		assert_eq!(
-			from_header.parent_hash, prev_hash,
-			"Parent hash doesn't match!"
-		);
-		prev_hash = from_header.using_encoded(blake2_256).into();
+		let mut verified_header = None;
+		if let Some(hash) = prev_hash.as_mut() {
+			let last_verified_block_num = change_block_num.unwrap_or(last_block_num);
+			// Checkpoint is stored only after headers up to its block number are verified and stored
+			let on_verified = |header: &Header| -> Result<()> {
+				db.store_header(header.number, header)?;
+				if header.number % HEADERS_CHECKPOINT_INTERVAL == 0
+					&& header.number != last_verified_block_num
+				{
+					db.store_finality_sync_checkpoint(FinalitySyncCheckpoint {
+						number: header.number + 1,
+						set_id,
+						validator_set: validator_set.clone(),
+					})?;
+				}
+				set_progress(&state, header.number);
+				Ok(())
+			};
 
-		let next_validator_set = filter_auth_set_changes(&from_header);
-		if next_validator_set.is_empty() {
-			curr_block_num += 1;
-			continue;
+			verified_header = verify_header_chain(
+				curr_block_num..=last_verified_block_num,
+				*hash,
+				cfg.parallelism,
+				|block_number| rpc_client.get_header_by_block_number(block_number),
+				on_verified,
+			)
+			.await?;
+
+			if let Some(header) = verified_header.as_ref() {
+				*hash = header.using_encoded(blake2_256).into();
+			}
 		}
 
+		let Some(block_number) = change_block_num else {
+			break;
+		};
+
+		info!("Authority set {set_id} changes at block no. {block_number}");
+		let header = match verified_header {
+			Some(header) => header,
+			None => {
+				rpc_client
+					.get_header_by_block_number(block_number)
+					.await
+					.context(format!("Couldn't get header for block no. {block_number}"))?
+					.0
+			},
+		};
+
+		let next_validator_set = filter_auth_set_changes(&header);
+		let Some(next_validator_set) = next_validator_set.first() else {
+			bail!(
+				"Set ID changed at block no. {block_number}, but no authority set change is found"
+			);
+		};
+
 		let proof: WrappedProof = rpc_client
-			.request_finality_proof(curr_block_num)
+			.request_finality_proof(block_number)
 			.await
 			.context(format!(
-				"Couldn't get finality proof for block no. {}",
-				curr_block_num
+				"Couldn't get finality proof for block no. {block_number}"
 			))?;
-		let proof_block_hash = proof.0.block;
-		let p_h = rpc_client
-			.get_header_by_hash(proof_block_hash)
-			.await
-			.context(format!("Couldn't get header for {}", proof_block_hash))?;
-
-		let signed_message = Encode::encode(&(
-			&SignerMessage::PrecommitMessage(
-				proof.0.justification.0.commit.precommits[0]
-					.clone()
-					.precommit,
-			),
-			&proof.0.justification.0.round,
-			&set_id,
-		));
-		// Verify all the signatures of the justification signs the hash of the block
-		let signer_addresses = proof
-			.0
-			.justification
-			.0
-			.commit
-			.precommits
-			.iter()
-			.map(|precommit| {
-				let is_ok = <ed25519::Pair as Pair>::verify(
-					&precommit.signature,
-					&signed_message,
-					&precommit.id,
-				);
-				is_ok
-					.then(|| precommit.clone().id)
-					.ok_or_else(|| anyhow!("Not signed by this signature!"))
-			})
-			.collect::<Result<Vec<_>>>();
+		trace!("Proof in block: {:?}", proof.0.block);
 
-		let Ok(signer_addresses) = signer_addresses else {
-			error!("Verification failed!");
-			bail!("Some of the signatures don't match");
-		};
+		verify_justification(
+			&proof.0.justification.0,
+			set_id,
+			&validator_set,
+			&header,
+			&proof.0.unknown_headers,
+		)?;
+		db.store_header(block_number, &header)?;
+		db.update_block_state(block_number, |block_state| {
+			block_state.finality_verified = true;
+		})?;
 
-		let num_matched_addresses = signer_addresses
+		curr_block_num = block_number + 1;
+		validator_set = next_validator_set
 			.iter()
-			.filter(|x| validator_set.iter().any(|e| e.0.eq(&x.0)))
-			.count();
-		info!(
-			"Number of matching signatures for block {curr_block_num}: {num_matched_addresses}/{}",
-			validator_set.len()
-		);
-		if num_matched_addresses < (validator_set.len() * 2 / 3) {
-			bail!("Not signed by the supermajority of the validator set.");
-		} else {
-			trace!("Proof in block: {}", p_h.number);
-			curr_block_num += 1;
-
-			validator_set = next_validator_set[0]
-				.iter()
-				.map(|a| ed25519::Public::from_raw(a.0 .0 .0 .0))
-				.collect();
-			set_id += 1;
-			store_finality_sync_checkpoint(
-				sync_finality.get_db(),
-				FinalitySyncCheckpoint {
-					number: curr_block_num,
-					set_id,
-					validator_set: validator_set.clone(),
-				},
-			)?;
-		}
+			.map(|a| ed25519::Public::from_raw(a.0 .0 .0 .0))
+			.collect();
+		set_id += 1;
+		db.store_finality_sync_checkpoint(FinalitySyncCheckpoint {
+			number: curr_block_num,
+			set_id,
+			validator_set: validator_set.clone(),
+		})?;
+		set_progress(&state, block_number);
 	}
+
+	info!("Finished verifying finality up to block no. {last_block_num}!");
+	set_progress(&state, last_block_num);
 	state.lock().unwrap().finality_synced = true;
 	info!("Finality is fully synced.");
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{find_authority_set_change, verify_finalized_header, verify_header_chain};
+	use crate::{
+		types::{Commit, GrandpaJustification},
+		utils::test_utils::header,
+	};
+	use anyhow::{anyhow, Result};
+	use avail_subxt::primitives::Header;
+	use codec::Encode;
+	use futures::future::{ready, Ready};
+	use sp_core::{blake2_256, H256};
+	use test_case::test_case;
+
+	fn set_id_at(changes: &'static [u32]) -> impl Fn(u32) -> Ready<Result<u64>> {
+		move |block_number| {
+			let set_id = changes.iter().filter(|&&change| change <= block_number);
+			ready(Ok(set_id.count() as u64))
+		}
+	}
+
+	#[test_case(0, 100, 0, &[], None ; "No changes")]
+	#[test_case(0, 100, 0, &[1], Some(1) ; "Change at first block")]
+	#[test_case(0, 100, 0, &[100], Some(100) ; "Change at last block")]
+	#[test_case(0, 100, 0, &[37, 64], Some(37) ; "First of two changes")]
+	#[test_case(37, 100, 1, &[37, 64], Some(64) ; "Second of two changes")]
+	#[test_case(64, 100, 2, &[37, 64], None ; "After last change")]
+	#[test_case(0, 36, 0, &[37, 64], None ; "Change after range")]
+	#[test_case(10, 10, 0, &[10], None ; "Empty range")]
+	#[tokio::test]
+	async fn find_authority_set_change_at(
+		from: u32,
+		to: u32,
+		set_id: u64,
+		changes: &'static [u32],
+		expected: Option<u32>,
+	) {
+		let change = find_authority_set_change(from, to, set_id, set_id_at(changes))
+			.await
+			.unwrap();
+		assert_eq!(change, expected);
+	}
+
+	fn header_chain(length: u32) -> Vec<Header> {
+		let mut parent_hash = H256::default();
+		(0..length)
+			.map(|number| {
+				let header = header(number, parent_hash);
+				parent_hash = header.using_encoded(blake2_256).into();
+				header
+			})
+			.collect()
+	}
+
+	fn fetch_header(headers: Vec<Header>) -> impl Fn(u32) -> Ready<Result<(Header, H256)>> {
+		move |block_number| {
+			let header = headers
+				.get(block_number as usize)
+				.cloned()
+				.map(|header| (header, H256::default()))
+				.ok_or_else(|| anyhow!("Header {block_number} not found"));
+			ready(header)
+		}
+	}
+
+	#[test_case(1 ; "Sequential")]
+	#[test_case(4 ; "Parallel")]
+	#[tokio::test]
+	async fn verify_header_chain_in_order(parallelism: usize) {
+		let headers = header_chain(100);
+		let prev_hash = headers[9].using_encoded(blake2_256).into();
+		let mut verified = vec![];
+
+		let last_header = verify_header_chain(
+			10..=99,
+			prev_hash,
+			parallelism,
+			fetch_header(headers),
+			|header| {
+				verified.push(header.number);
+				Ok(())
+			},
+		)
+		.await
+		.unwrap();
+
+		assert_eq!(last_header.map(|header| header.number), Some(99));
+		assert_eq!(verified, (10..=99).collect::<Vec<_>>());
+	}
+
+	#[tokio::test]
+	async fn verify_header_chain_broken_link() {
+		let mut headers = header_chain(20);
+		headers[15].parent_hash = H256::repeat_byte(1);
+		let prev_hash = headers[0].using_encoded(blake2_256).into();
+		let mut verified = vec![];
+
+		let result = verify_header_chain(1..=19, prev_hash, 4, fetch_header(headers), |header| {
+			verified.push(header.number);
+			Ok(())
+		})
+		.await;
+
+		assert!(result.is_err());
+		assert_eq!(verified, (1..15).collect::<Vec<_>>());
+	}
+
+	fn justification_for(target: &Header) -> GrandpaJustification {
+		GrandpaJustification {
+			round: 1,
+			commit: Commit {
+				target_hash: target.using_encoded(blake2_256).into(),
+				target_number: target.number,
+				precommits: vec![],
+			},
+			votes_ancestries: vec![],
+		}
+	}
+
+	#[test]
+	fn verify_finalized_header_target() {
+		let headers = header_chain(10);
+		let justification = justification_for(&headers[5]);
+		assert!(verify_finalized_header(&justification, &headers[5], &[]).is_ok());
+	}
+
+	#[test]
+	fn verify_finalized_header_ancestor() {
+		let headers = header_chain(10);
+		let justification = justification_for(&headers[8]);
+		assert!(verify_finalized_header(&justification, &headers[5], &headers[6..=8]).is_ok());
+		assert!(verify_finalized_header(&justification, &headers[5], &headers[7..=8]).is_err());
+	}
+
+	#[test]
+	fn verify_finalized_header_forged() {
+		let headers = header_chain(10);
+		let mut forged = headers[5].clone();
+		forged.state_root = H256::repeat_byte(1);
+
+		let justification = justification_for(&headers[5]);
+		assert!(verify_finalized_header(&justification, &forged, &[]).is_err());
+
+		let justification = justification_for(&headers[8]);
+		assert!(verify_finalized_header(&justification, &forged, &headers[6..=8]).is_err());
+	}
+}
@@ -11,12 +11,13 @@ use kate_recovery::{
 	matrix::{Dimensions, Partition},
 };
 use libp2p::{Multiaddr, PeerId};
+use schemars::JsonSchema;
 use serde::{de::Error, Deserialize, Serialize};
 use sp_core::{blake2_256, bytes, ed25519};
 use std::ops::Range;
 use std::str::FromStr;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::num::NonZeroUsize;
 use std::time::{Duration, Instant};
 use std::{fmt, fs};
@@ -39,7 +40,7 @@ pub struct CliOpts {
 	/// Testnet or devnet selection
 	#[arg(short, long, value_name = "network")]
 	pub network: Option<Network>,
-	/// Run a clean light client, deleting existing avail_path folder
+	/// Run a clean light client, deleting existing avail_path folder (not allowed with subcommands)
 	#[arg(long)]
 	pub clean: bool,
 	/// P2P port
@@ -48,6 +49,40 @@ pub struct CliOpts {
 	/// Log level
 	#[arg(long)]
 	pub verbosity: Option<LogLevel>,
+	#[command(subcommand)]
+	pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+	/// Export verified headers, confidence and app data into the archive
+	Export {
+		/// First block to export
+		#[arg(long)]
+		from: u32,
+		/// Last block to export
+		#[arg(long)]
+		to: u32,
+		/// AppID of the application data to export (app data is not exported if omitted)
+		#[arg(long, value_name = "appId")]
+		app_id: Option<u32>,
+		/// Path to the archive file
+		#[arg(short, long, value_name = "FILE")]
+		output: String,
+	},
+	/// Import the archive after verifying header linkage
+	Import {
+		/// Path to the archive file
+		#[arg(short, long, value_name = "FILE")]
+		input: String,
+	},
+	/// Encrypt the secret key read from the standard input into the keystore file,
+	/// using the password from the `AVAIL_KEYSTORE_PASSWORD` environment variable
+	EncryptKey {
+		/// Path to the keystore file, named after the key (e.g. `sequencer.json`)
+		#[arg(short, long, value_name = "FILE")]
+		output: String,
+	},
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -62,6 +97,51 @@ pub struct RuntimeVersion {
 	transaction_version: u32,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub struct Weight {
+	pub ref_time: u64,
+	pub proof_size: u64,
+}
+
+/// Weight, class and inclusion fee of the extrinsic, as returned by the `payment_queryInfo` RPC
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeDispatchInfo {
+	pub weight: Weight,
+	pub class: String,
+	/// Inclusion fee, excluding the tip
+	#[serde(with = "balance_format")]
+	pub partial_fee: u128,
+}
+
+/// Merkle proof of the submitted data against the header data root,
+/// as returned by the `kate_queryDataProof` RPC
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DataProof {
+	/// Root of the merkle tree, which is the data root of the header
+	#[schemars(with = "String")]
+	pub root: H256,
+	/// Hashes of the sibling nodes, from the leaf up to the root
+	#[schemars(with = "Vec<String>")]
+	pub proof: Vec<H256>,
+	pub number_of_leaves: u32,
+	pub leaf_index: u32,
+	/// Hash of the submitted data
+	#[schemars(with = "String")]
+	pub leaf: H256,
+}
+
+/// Outcome of the extrinsic dry run
+#[derive(Debug, Clone, PartialEq)]
+pub enum DryRunOutcome {
+	Success,
+	/// Extrinsic is valid, but the call would fail
+	DispatchError(String),
+	/// Extrinsic would be rejected by the transaction pool
+	Invalid,
+}
+
 /// Light to app client channel message struct
 #[derive(Clone, Debug)]
 pub struct BlockVerified {
@@ -153,6 +233,35 @@ pub mod block_matrix_partition_format {
 		}
 	}
 }
+
+/// Balances are serialized as strings, since they can exceed the JSON number precision
+pub mod balance_format {
+	use serde::{self, Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S>(balance: &u128, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&balance.to_string())
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<u128, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		#[derive(Deserialize)]
+		#[serde(untagged)]
+		enum Balance {
+			Number(u64),
+			String(String),
+		}
+
+		match Balance::deserialize(deserializer)? {
+			Balance::Number(balance) => Ok(balance.into()),
+			Balance::String(balance) => balance.parse().map_err(serde::de::Error::custom),
+		}
+	}
+}
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(try_from = "String")]
 pub struct CompactMultiaddress((PeerId, Multiaddr));
@@ -196,6 +305,61 @@ pub enum SecretKey {
 	Key { key: String },
 }
 
+/// Handling of web socket clients which don't receive messages fast enough
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SlowClientPolicy {
+	/// Drops messages which don't fit into the client queue
+	Drop,
+	/// Closes the client connection once the client queue is full
+	Disconnect,
+}
+
+/// Scope of the HTTP API access
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApiScope {
+	/// Access to the block, data and status endpoints
+	Read,
+	/// Access to the web socket and server-sent events subscriptions
+	Subscribe,
+	/// Access to the transaction submission
+	Submit,
+}
+
+impl FromStr for ApiScope {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"read" => Ok(ApiScope::Read),
+			"subscribe" => Ok(ApiScope::Subscribe),
+			"submit" => Ok(ApiScope::Submit),
+			_ => Err(anyhow!("Unknown API scope {s}")),
+		}
+	}
+}
+
+/// Secret value which is not exposed in logs
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct ApiSecret(pub String);
+
+impl fmt::Debug for ApiSecret {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("ApiSecret(..)")
+	}
+}
+
+/// Bearer token with the granted scopes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiToken {
+	pub token: ApiSecret,
+	pub scopes: Vec<ApiScope>,
+	/// Application IDs allowed for the submission, or any if not set
+	pub app_ids: Option<Vec<u32>>,
+}
+
 /// Representation of a configuration used by this project.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
@@ -204,6 +368,35 @@ pub struct RuntimeConfig {
 	pub http_server_host: String,
 	/// Light client HTTP server port (default: 7000).
 	pub http_server_port: u16,
+	/// Path to the PEM encoded certificate chain. If set together with the key path, HTTP server is served over TLS (default: None).
+	pub http_server_tls_cert_path: Option<String>,
+	/// Path to the PEM encoded private key of the TLS certificate (default: None).
+	pub http_server_tls_key_path: Option<String>,
+	/// Path to the PEM encoded CA certificates. If set, clients are required to present a certificate signed by one of them (default: None).
+	pub http_server_tls_client_ca_path: Option<String>,
+	/// Interval in seconds in which the TLS certificate and key files are checked for changes and reloaded (default: 60).
+	pub http_server_tls_reload_interval: u64,
+	/// Bearer tokens and their scopes, used to authorize HTTP API requests.
+	/// If neither tokens nor HMAC secret are set, all requests are allowed (default: empty).
+	#[serde(skip_serializing)]
+	pub http_api_tokens: Vec<ApiToken>,
+	/// Secret used to verify HMAC signed bearer tokens, in `{scopes}.{expires_at}.{signature}` format (default: None).
+	#[serde(skip_serializing)]
+	pub http_api_hmac_secret: Option<ApiSecret>,
+	/// Origins allowed to make cross-origin HTTP API requests. If empty, any origin is allowed (default: empty).
+	pub http_cors_origins: Vec<String>,
+	/// Maximum number of HTTP API requests per second from a single IP address. If not set, requests are not limited (default: None).
+	pub http_rate_limit: Option<u32>,
+	/// Maximum number of web socket subscriptions (default: 1000).
+	pub ws_max_subscriptions: usize,
+	/// Number of seconds after which subscriptions without connected web socket are removed (default: 60).
+	pub ws_subscription_ttl: u64,
+	/// Maximum number of messages queued for sending to the web socket client (default: 1000).
+	pub ws_client_queue_size: usize,
+	/// Policy for web socket clients with full message queue, `drop` or `disconnect` (default: disconnect).
+	pub ws_slow_client_policy: SlowClientPolicy,
+	/// Maximum number of blocks before the latest block from which web socket subscriptions can replay stored messages (default: 100).
+	pub ws_max_replay_blocks: u32,
 	/// Secret key for libp2p keypair. Can be either set to `seed` or to `key`.
 	/// If set to seed, keypair will be generated from that seed.
 	/// If set to key, a valid ed25519 private key must be provided, else the client will fail
@@ -266,6 +459,10 @@ pub struct RuntimeConfig {
 	pub sync_start_block: Option<u32>,
 	/// Enable or disable synchronizing finality. If disabled, finality is assumed to be verified until the starting block at the point the LC is started and is only checked for new blocks. (default: true)
 	pub sync_finality_enable: bool,
+	/// Enable or disable full header chain verification on finality sync (opt-in). If enabled, all headers are prefetched in parallel and their parent hashes are checked in order, otherwise only headers with authority set changes are fetched, which needs orders of magnitude fewer RPC calls. (default: false)
+	pub sync_finality_verify_headers: bool,
+	/// Maximum number of headers fetched in parallel on finality sync header verification (default: 20).
+	pub sync_finality_parallelism: usize,
 	/// Maximum number of cells per request for proof queries (default: 30).
 	pub max_cells_per_rpc: Option<usize>,
 	/// Threshold for the number of cells fetched via DHT for the app client (default: 5000)
@@ -311,9 +508,29 @@ pub struct RuntimeConfig {
 	/// Avail account secret key. (default: None)
 	#[serde(skip_serializing)]
 	pub avail_secret_key: Option<AvailSecretKey>,
+	/// Directory with the encrypted JSON keystore files, each holding one signing key named after the file.
+	/// Keystore password is read from the `AVAIL_KEYSTORE_PASSWORD` environment variable (default: None).
+	pub avail_keystore_path: Option<String>,
+	/// Names of the signing keys which can be chosen in submit requests, besides the `default` one (default: empty).
+	pub submit_keys: Vec<String>,
+	/// Maximum number of data blobs packed into a single `utility.batch_all` transaction. If set to 1, each blob is submitted in a separate transaction (default: 1).
+	pub submit_batch_size: usize,
+	/// Maximum encoded size in bytes of the data packed into a single `utility.batch_all` transaction. Blob larger than the limit is submitted in a separate transaction (default: 1048576).
+	pub submit_max_batch_bytes: usize,
+	/// Maximum size of the submitted data blob in bytes (default: 524288).
+	pub submit_max_blob_size: usize,
+	/// Tip added to the fee of the submitted data transactions (default: 0).
+	pub submit_tip: u64,
+	/// Number of times the submission is retried if transaction is rejected because of a stale or future nonce (default: 3).
+	pub submit_retries: usize,
+	/// Number of seconds the status of the asynchronously submitted transaction is kept, after it is no longer tracked (default: 86400).
+	pub submit_retention: u64,
 	#[cfg(feature = "crawl")]
 	#[serde(flatten)]
 	pub crawl: crate::crawl_client::CrawlConfig,
+	#[cfg(feature = "grpc")]
+	#[serde(flatten)]
+	pub grpc: crate::api::v2::grpc::GrpcConfig,
 }
 
 #[derive(Deserialize, Clone)]
@@ -499,6 +716,22 @@ impl From<&RuntimeConfig> for SyncClientConfig {
 	}
 }
 
+/// Finality sync configuration (see [RuntimeConfig] for details)
+#[derive(Clone)]
+pub struct SyncFinalityConfig {
+	pub verify_headers: bool,
+	pub parallelism: usize,
+}
+
+impl From<&RuntimeConfig> for SyncFinalityConfig {
+	fn from(val: &RuntimeConfig) -> Self {
+		SyncFinalityConfig {
+			verify_headers: val.sync_finality_verify_headers,
+			parallelism: val.sync_finality_parallelism,
+		}
+	}
+}
+
 /// App client configuration (see [RuntimeConfig] for details)
 pub struct AppClientConfig {
 	pub dht_parallelization_limit: usize,
@@ -520,6 +753,19 @@ impl Default for RuntimeConfig {
 		RuntimeConfig {
 			http_server_host: "127.0.0.1".to_owned(),
 			http_server_port: 7000,
+			http_server_tls_cert_path: None,
+			http_server_tls_key_path: None,
+			http_server_tls_client_ca_path: None,
+			http_server_tls_reload_interval: 60,
+			http_api_tokens: vec![],
+			http_api_hmac_secret: None,
+			http_cors_origins: vec![],
+			http_rate_limit: None,
+			ws_max_subscriptions: 1000,
+			ws_subscription_ttl: 60,
+			ws_client_queue_size: 1000,
+			ws_slow_client_policy: SlowClientPolicy::Disconnect,
+			ws_max_replay_blocks: 100,
 			port: 37000,
 			secret_key: None,
 			tcp_port_reuse: false,
@@ -549,6 +795,8 @@ impl Default for RuntimeConfig {
 			block_matrix_partition: None,
 			sync_start_block: None,
 			sync_finality_enable: true,
+			sync_finality_verify_headers: false,
+			sync_finality_parallelism: 20,
 			max_cells_per_rpc: Some(30),
 			kad_record_ttl: 24 * 60 * 60,
 			threshold: 5000,
@@ -564,8 +812,18 @@ impl Default for RuntimeConfig {
 			max_kad_record_size: 8192,
 			max_kad_provided_keys: 1024,
 			avail_secret_key: None,
+			avail_keystore_path: None,
+			submit_keys: vec![],
+			submit_batch_size: 1,
+			submit_max_batch_bytes: 1024 * 1024,
+			submit_max_blob_size: 512 * 1024,
+			submit_tip: 0,
+			submit_retries: 3,
+			submit_retention: 24 * 60 * 60,
 			#[cfg(feature = "crawl")]
 			crawl: crate::crawl_client::CrawlConfig::default(),
+			#[cfg(feature = "grpc")]
+			grpc: crate::api::v2::grpc::GrpcConfig::default(),
 		}
 	}
 }
@@ -727,6 +985,51 @@ pub struct State {
 	pub sync_confidence_achieved: Option<BlockRange>,
 	pub sync_data_verified: Option<BlockRange>,
 	pub finality_synced: bool,
+	pub finality_sync: Option<FinalitySyncProgress>,
+}
+
+/// Progress of the finality sync, used to report sync speed and remaining time
+#[derive(Clone, Debug)]
+pub struct FinalitySyncProgress {
+	pub started_at: Instant,
+	pub first: u32,
+	pub latest: u32,
+	pub target: u32,
+	/// Progress advances header by header only if the full header chain is verified,
+	/// otherwise it jumps from one authority set change to the next one
+	pub verify_headers: bool,
+}
+
+impl FinalitySyncProgress {
+	pub fn new(first: u32, target: u32, verify_headers: bool) -> Self {
+		FinalitySyncProgress {
+			started_at: Instant::now(),
+			first,
+			latest: first,
+			target,
+			verify_headers,
+		}
+	}
+
+	/// Number of blocks with verified finality per second since the sync has started
+	pub fn blocks_per_second(&self) -> f64 {
+		let elapsed = self.started_at.elapsed().as_secs_f64();
+		if elapsed <= 0.0 {
+			return 0.0;
+		}
+		self.latest.saturating_sub(self.first) as f64 / elapsed
+	}
+
+	/// Estimated time remaining until the target block, if sync has made any progress.
+	/// Not estimated when jumping between authority set changes, since jumps are not evenly spaced.
+	pub fn eta(&self) -> Option<Duration> {
+		if !self.verify_headers {
+			return None;
+		}
+		let blocks_per_second = self.blocks_per_second();
+		let remaining = self.target.saturating_sub(self.latest) as f64;
+		(blocks_per_second > 0.0).then(|| Duration::from_secs_f64(remaining / blocks_per_second))
+	}
 }
 
 pub trait OptionBlockRange {
@@ -759,7 +1062,113 @@ impl OptionBlockRange for Option<BlockRange> {
 	}
 }
 
-#[derive(Debug, Decode, Encode)]
+impl State {
+	/// Restores sync ranges from the block states persisted in previous runs.
+	/// Only blocks processed without gaps from the sync start block are restored.
+	pub fn restore_sync(&mut self, sync_start_block: u32, block_states: &[(u32, BlockState)]) {
+		fn restore(
+			range: &mut Option<BlockRange>,
+			sync_start_block: u32,
+			block_states: &[(u32, BlockState)],
+			is_processed: impl Fn(&BlockState) -> bool,
+		) {
+			let processed = (sync_start_block..).zip(block_states).take_while(
+				|(expected, (block_number, block_state))| {
+					expected == block_number && is_processed(block_state)
+				},
+			);
+			for (block_number, _) in processed {
+				range.set(block_number);
+			}
+		}
+
+		restore(
+			&mut self.sync_header_verified,
+			sync_start_block,
+			block_states,
+			|block_state| block_state.header_verified,
+		);
+		restore(
+			&mut self.sync_confidence_achieved,
+			sync_start_block,
+			block_states,
+			|block_state| block_state.confidence_achieved,
+		);
+		restore(
+			&mut self.sync_data_verified,
+			sync_start_block,
+			block_states,
+			|block_state| block_state.data_verified,
+		);
+	}
+}
+
+/// Processing state of the block, persisted in the database
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockState {
+	pub header_verified: bool,
+	/// Header finality is verified, either by the GRANDPA justification,
+	/// or by being an ancestor of the finality verified header
+	#[serde(default)]
+	pub finality_verified: bool,
+	pub confidence_achieved: bool,
+	pub data_verified: bool,
+	/// Reason of the last processing failure, cleared once the block is processed
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub failure: Option<String>,
+}
+
+/// Status of the transaction submitted without waiting for finalization
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum SubmissionStatus {
+	Submitted,
+	Ready,
+	InBlock,
+	Finalized,
+	Dropped,
+	Invalid,
+}
+
+impl SubmissionStatus {
+	/// Returns `true` if transaction status will not change anymore
+	pub fn is_final(&self) -> bool {
+		matches!(
+			self,
+			SubmissionStatus::Finalized | SubmissionStatus::Dropped | SubmissionStatus::Invalid
+		)
+	}
+}
+
+/// Submitted transaction and its status, persisted in the database
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Submission {
+	#[schemars(with = "String")]
+	pub hash: H256,
+	pub status: SubmissionStatus,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
+	pub block_hash: Option<H256>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub index: Option<u32>,
+	/// Reason of the transaction failure, if transaction failed or its status cannot be tracked
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+impl Submission {
+	pub fn new(hash: H256) -> Self {
+		Submission {
+			hash,
+			status: SubmissionStatus::Submitted,
+			block_hash: None,
+			index: None,
+			error: None,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Decode, Encode)]
 pub struct FinalitySyncCheckpoint {
 	pub number: u32,
 	pub set_id: u64,
@@ -800,7 +1209,7 @@ pub struct Commit {
 pub struct GrandpaJustification {
 	pub round: u64,
 	pub commit: Commit,
-	pub _votes_ancestries: Vec<DaHeader>,
+	pub votes_ancestries: Vec<DaHeader>,
 }
 
 impl<'de> Deserialize<'de> for GrandpaJustification {
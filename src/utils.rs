@@ -2,6 +2,7 @@ use anyhow::Context;
 use avail_core::{
 	data_lookup::compact::{CompactDataLookup, DataLookupItem},
 	data_lookup::Error as DataLookupError,
+	traits::GetAppId,
 	AppId, DataLookup,
 };
 use avail_subxt::{
@@ -23,6 +24,7 @@ use kate_recovery::{
 	data::Cell,
 	matrix::{Dimensions, Position},
 };
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn decode_app_data(data: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
 	let extrisic: AppUncheckedExtrinsic =
@@ -34,6 +36,21 @@ pub fn decode_app_data(data: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
 	}
 }
 
+/// Decodes application ID of the extrinsic, which is `0` for unsigned extrinsics
+pub fn decode_app_id(data: &[u8]) -> anyhow::Result<u32> {
+	let extrinsic: AppUncheckedExtrinsic =
+		<_ as Decode>::decode(&mut &data[..]).context("Couldn't decode AvailExtrinsic")?;
+	Ok(extrinsic.app_id().0)
+}
+
+/// Returns the current Unix timestamp in seconds
+pub fn unix_timestamp() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|duration| duration.as_secs())
+		.unwrap_or_default()
+}
+
 /// Calculates confidence from given number of verified cells
 pub fn calculate_confidence(count: u32) -> f64 {
 	100f64 * (1f64 - 1f64 / 2u32.pow(count) as f64)
@@ -123,6 +140,39 @@ fn diff_positions(positions: &[Position], cells: &[Cell]) -> Vec<Position> {
 		.collect::<Vec<_>>()
 }
 
+/// Fixtures shared by the tests of different modules
+#[cfg(test)]
+pub(crate) mod test_utils {
+	use avail_subxt::{
+		api::runtime_types::avail_core::{
+			data_lookup::compact::CompactDataLookup,
+			header::extension::{v2, HeaderExtension},
+			kate_commitment::v2::KateCommitment,
+		},
+		primitives::Header as DaHeader,
+		utils::H256,
+	};
+	use subxt::config::substrate::Digest;
+
+	/// Header with the given number and parent hash, and the empty extension
+	pub fn header(number: u32, parent_hash: H256) -> DaHeader {
+		DaHeader {
+			parent_hash,
+			number,
+			state_root: H256::default(),
+			extrinsics_root: H256::default(),
+			extension: HeaderExtension::V2(v2::HeaderExtension {
+				commitment: KateCommitment::default(),
+				app_lookup: CompactDataLookup {
+					size: 0,
+					index: vec![],
+				},
+			}),
+			digest: Digest { logs: vec![] },
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::{can_reconstruct, diff_positions};